@@ -1,6 +1,18 @@
+use std::io;
+
 mod routes;
 
 fn main() {
-    routes::home()
+    routes::home();
+    routes::algorithms();
+
     // ... entry point for the REST API application ...
+    // a plan posted on stdin stands in for the request body until then
+    if std::env::args().nth(1).as_deref() == Some("plan") {
+        let body = io::read_to_string(io::stdin()).unwrap_or_default();
+        match routes::plan(&body) {
+            Ok(runtime) => println!("{runtime}"),
+            Err(err) => eprintln!("Error: {err}"),
+        }
+    }
 }
@@ -1,3 +1,24 @@
+use core::{error::DivesyncError, plan::DivePlan, registry::registry};
+
 pub fn home() {
     println!("Home route")
 }
+
+/// List registered decompression algorithms with their parameters
+pub fn algorithms() {
+    for entry in registry().entries() {
+        let params: Vec<String> = entry
+            .params
+            .iter()
+            .map(|p| format!("{}={}", p.name, p.default))
+            .collect();
+
+        println!("{} [{}]", entry.name, params.join(", "))
+    }
+}
+
+/// Runtime table as JSON of a dive plan posted as JSON, the schema is
+/// the same as plan files of the CLI
+pub fn plan(body: &str) -> Result<String, DivesyncError> {
+    DivePlan::from_json(body)?.runtime()?.to_json()
+}
@@ -1,4 +1,5 @@
 use clap::{value_parser, Arg, ArgAction};
+use core::registry::registry;
 
 pub fn build_depth_arg() -> Arg {
     Arg::new("depth")
@@ -27,10 +28,19 @@ pub fn build_interval_arg() -> Arg {
         .short('i')
         .long("interval")
         .action(ArgAction::Set)
-        .value_name("interval")
-        .help("Interval period at which to run algorithm, default value is 5min")
+        .value_name("seconds")
+        .help("Interval period in seconds between output rows, default value is 300s")
         .value_parser(value_parser!(u32))
-        .default_value("5")
+        .default_value("300")
+}
+
+pub fn build_integration_step_arg() -> Arg {
+    Arg::new("integration-step")
+        .long("integration-step")
+        .action(ArgAction::Set)
+        .value_name("seconds")
+        .help("Longest integration step in seconds while the depth changes, default 10s, only used by algorithms without exact travel loading")
+        .value_parser(value_parser!(u32).range(1..))
 }
 
 pub fn build_save_csv_arg() -> Arg {
@@ -39,7 +49,15 @@ pub fn build_save_csv_arg() -> Arg {
         .long("csv")
         .action(ArgAction::Set)
         .value_name("csv")
-        .help("Save the output to CSV")
+        .help("Stream the compartments of every step to a CSV file")
+}
+
+pub fn build_save_uddf_arg() -> Arg {
+    Arg::new("uddf")
+        .long("uddf")
+        .action(ArgAction::Set)
+        .value_name("file")
+        .help("Save the run profile to a UDDF file")
 }
 
 pub fn build_gas_arg() -> Arg {
@@ -49,8 +67,8 @@ pub fn build_gas_arg() -> Arg {
         .action(ArgAction::Set)
         .value_parser(value_parser!(String))
         .value_name("gas")
-        .help("Gas mixture, oxygen and helium, in the format of -g 'O2%,He%'")
-        .default_value("21,0")
+        .help("Gas mixture, eg. Air, EAN32, TX18/45, HeliOx21/79, Mix20.95/78.08/0/0.97 (O2/N2/He/Ar) or 'O2%,He%'")
+        .default_value("Air")
 }
 
 pub fn build_plot_arg() -> Arg {
@@ -64,10 +82,322 @@ pub fn build_plot_arg() -> Arg {
 }
 
 pub fn build_algo_arg() -> Arg {
+    let registry = registry();
+
+    let mut long_help = String::from("Decompression algorithm, registered algorithms:\n");
+    for entry in registry.entries() {
+        long_help.push_str(&format!("  {} - {}\n", entry.name, entry.description));
+        if !entry.aliases.is_empty() {
+            long_help.push_str(&format!("      aliases: {}\n", entry.aliases.join(", ")));
+        }
+        for param in &entry.params {
+            long_help.push_str(&format!(
+                "      {}: {} (default {}, {} to {})\n",
+                param.name, param.description, param.default, param.min, param.max
+            ));
+        }
+    }
+
     Arg::new("algo")
         .short('a')
         .long("algorithm")
         .action(ArgAction::Set)
         .value_name("algo")
-        .help("Decompression algorithm (DSAT, ZHL16-A, ZHL16-B, ZHL16-C)")
+        .help(format!(
+            "Decompression algorithm ({})",
+            registry.names().join(", ")
+        ))
+        .long_help(long_help)
+}
+
+pub fn build_algo_param_arg() -> Arg {
+    Arg::new("param")
+        .short('P')
+        .long("param")
+        .action(ArgAction::Append)
+        .value_parser(value_parser!(String))
+        .value_name("name=value")
+        .help("Algorithm parameter, can be given multiple times, eg. -P gf_low=30 -P gf_high=85")
+}
+
+pub fn build_coefficients_arg() -> Arg {
+    Arg::new("coefficients")
+        .long("coefficients")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(String))
+        .value_name("file")
+        .help("Load ZHL coefficient table from a TOML or JSON file, used as algorithm unless -a is given")
+}
+
+pub fn build_deco_gas_arg() -> Arg {
+    Arg::new("deco-gas")
+        .long("deco-gas")
+        .action(ArgAction::Append)
+        .value_parser(value_parser!(String))
+        .value_name("deco-gas")
+        .help("Deco gas mixture, eg. EAN50 or O2, can be given multiple times")
+}
+
+pub fn build_contingency_arg() -> Arg {
+    Arg::new("contingency")
+        .long("contingency")
+        .action(ArgAction::SetTrue)
+        .help("Print lost gas and deeper/longer contingency table")
+}
+
+pub fn build_compare_algo_arg() -> Arg {
+    Arg::new("with")
+        .short('w')
+        .long("with")
+        .action(ArgAction::Append)
+        .value_parser(value_parser!(String))
+        .value_name("algo[:name=value,...]")
+        .help("Algorithm to compare, can be given multiple times, eg. -w zhl16-c:gf_low=30,gf_high=85, all registered algorithms by default")
+}
+
+pub fn build_sac_arg() -> Arg {
+    Arg::new("sac")
+        .long("sac")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(f32))
+        .value_name("sac")
+        .help("Surface air consumption in liters per minute, used for deco gas volumes")
+        .default_value("20")
+}
+
+pub fn build_table_gases_arg() -> Arg {
+    Arg::new("gas")
+        .short('g')
+        .long("gas")
+        .action(ArgAction::Append)
+        .value_parser(value_parser!(String))
+        .value_name("gas")
+        .help("Gas mixture of a table column, can be given multiple times")
+        .default_values(["Air", "EAN32", "EAN36"])
+}
+
+pub fn build_from_depth_arg() -> Arg {
+    Arg::new("from")
+        .long("from")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(f32))
+        .value_name("depth")
+        .help("Shallowest depth of the table in meters")
+        .default_value("9")
+}
+
+pub fn build_to_depth_arg() -> Arg {
+    Arg::new("to")
+        .long("to")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(f32))
+        .value_name("depth")
+        .help("Deepest depth of the table in meters")
+        .default_value("40")
+}
+
+pub fn build_step_arg() -> Arg {
+    Arg::new("step")
+        .long("step")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(f32))
+        .value_name("step")
+        .help("Depth step of the table in meters")
+        .default_value("3")
+}
+
+pub fn build_max_ppo2_arg() -> Arg {
+    Arg::new("max-ppo2")
+        .long("max-ppo2")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(f32))
+        .value_name("ppo2")
+        .help("Partial pressure of oxygen used for the MOD of each gas")
+        .default_value("1.4")
+}
+
+pub fn build_format_arg() -> Arg {
+    Arg::new("format")
+        .short('f')
+        .long("format")
+        .action(ArgAction::Set)
+        .value_parser(["csv", "markdown", "json"])
+        .value_name("format")
+        .help("Output format")
+        .default_value("markdown")
+}
+
+pub fn build_bottom_times_arg() -> Arg {
+    Arg::new("times")
+        .short('t')
+        .long("times")
+        .action(ArgAction::Append)
+        .value_parser(value_parser!(u32))
+        .value_name("minutes")
+        .help("Bottom time of a table row, can be given multiple times")
+        .default_values([
+            "10", "15", "20", "25", "30", "40", "50", "60", "70", "80", "90", "120",
+        ])
+}
+
+pub fn build_book_format_arg() -> Arg {
+    Arg::new("format")
+        .short('f')
+        .long("format")
+        .action(ArgAction::Set)
+        .value_parser(["csv", "text"])
+        .value_name("format")
+        .help("Output format")
+        .default_value("text")
+}
+
+pub fn build_surface_interval_arg() -> Arg {
+    Arg::new("surface-interval")
+        .short('s')
+        .long("surface-interval")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(f32))
+        .value_name("minutes")
+        .help("Surface interval after the dive in minutes")
+}
+
+pub fn build_next_depth_arg() -> Arg {
+    Arg::new("next-depth")
+        .long("next-depth")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(f32))
+        .value_name("depth")
+        .help("Depth of the next dive in meters, to compute residual nitrogen time")
+}
+
+pub fn build_repetitive_mode_arg() -> Arg {
+    Arg::new("mode")
+        .short('m')
+        .long("mode")
+        .action(ArgAction::Set)
+        .value_parser(["rounded", "tissue", "table"])
+        .value_name("mode")
+        .help("Round up to the top of each group, follow the exact tissue tension or look up the USN tables given with --usn-tables")
+        .default_value("rounded")
+}
+
+pub fn build_usn_tables_arg() -> Arg {
+    Arg::new("usn-tables")
+        .long("usn-tables")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(String))
+        .value_name("file")
+        .required_if_eq("mode", "table")
+        .help("Load USN no-decompression, surface interval and residual nitrogen tables from a TOML or JSON file")
+}
+
+pub fn build_plan_file_arg() -> Arg {
+    Arg::new("file")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(String))
+        .value_name("file")
+        .help("Dive plan file, .toml, .yaml or .json")
+        .required(true)
+}
+
+pub fn build_plan_format_arg() -> Arg {
+    Arg::new("format")
+        .short('f')
+        .long("format")
+        .action(ArgAction::Set)
+        .value_parser(["text", "json", "ssrf", "uddf"])
+        .value_name("format")
+        .help("Output format, ssrf writes a planned dive for Subsurface and uddf a UDDF document")
+        .default_value("text")
+}
+
+pub fn build_log_file_arg() -> Arg {
+    Arg::new("file")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(String))
+        .value_name("file")
+        .help("Dive log, .ssrf or .xml for Subsurface, .uddf or .csv")
+        .required(true)
+}
+
+pub fn build_csv_preset_arg() -> Arg {
+    Arg::new("preset")
+        .long("preset")
+        .action(ArgAction::Set)
+        .value_parser(["shearwater", "garmin", "suunto"])
+        .value_name("preset")
+        .help("Column layout of a CSV log")
+}
+
+pub fn build_time_column_arg() -> Arg {
+    Arg::new("time-column")
+        .long("time-column")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(String))
+        .value_name("column")
+        .help("CSV column holding the time, in seconds or [h:]m:ss")
+}
+
+pub fn build_depth_column_arg() -> Arg {
+    Arg::new("depth-column")
+        .long("depth-column")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(String))
+        .value_name("column")
+        .help("CSV column holding the depth")
+}
+
+pub fn build_units_arg() -> Arg {
+    Arg::new("units")
+        .long("units")
+        .action(ArgAction::Set)
+        .value_parser(["metric", "imperial"])
+        .value_name("units")
+        .help("Units of CSV columns without a unit in their header")
+        .default_value("metric")
+}
+
+pub fn build_dive_number_arg() -> Arg {
+    Arg::new("dive")
+        .long("dive")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(u32))
+        .value_name("number")
+        .help("Only the dive with this number in the log")
+}
+
+pub fn build_resample_arg() -> Arg {
+    Arg::new("resample")
+        .long("resample")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(u32))
+        .value_name("seconds")
+        .help("Timestep in seconds the log is resampled to, default 10s")
+}
+
+pub fn build_smooth_arg() -> Arg {
+    Arg::new("smooth")
+        .long("smooth")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(String))
+        .value_name("filter")
+        .help("Smooth depths with median:N, average:N or exponential:ALPHA")
+}
+
+pub fn build_surface_depth_arg() -> Arg {
+    Arg::new("surface-depth")
+        .long("surface-depth")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(f32))
+        .value_name("meters")
+        .help("Depths shallower than this are the surface, default 1m")
+}
+
+pub fn build_split_arg() -> Arg {
+    Arg::new("split")
+        .long("split")
+        .action(ArgAction::Set)
+        .value_parser(value_parser!(u32))
+        .value_name("seconds")
+        .help("Split the log into dives at surface intervals of at least this many seconds, default 300s")
 }
@@ -1,8 +1,16 @@
-use clap::{arg, Command};
+use clap::Command;
 
 use crate::args::{
-    build_algo_arg, build_depth_arg, build_gas_arg, build_interval_arg, build_plot_arg,
-    build_save_csv_arg, build_time_arg,
+    build_algo_arg, build_algo_param_arg, build_book_format_arg, build_bottom_times_arg,
+    build_coefficients_arg, build_compare_algo_arg, build_contingency_arg, build_csv_preset_arg,
+    build_deco_gas_arg, build_depth_arg, build_depth_column_arg, build_dive_number_arg,
+    build_format_arg, build_from_depth_arg, build_gas_arg, build_integration_step_arg,
+    build_interval_arg, build_log_file_arg, build_max_ppo2_arg, build_next_depth_arg,
+    build_plan_file_arg, build_plan_format_arg, build_plot_arg, build_repetitive_mode_arg,
+    build_resample_arg, build_sac_arg, build_save_csv_arg, build_save_uddf_arg, build_smooth_arg,
+    build_split_arg, build_step_arg, build_surface_depth_arg, build_surface_interval_arg,
+    build_table_gases_arg, build_time_arg, build_time_column_arg, build_to_depth_arg,
+    build_units_arg, build_usn_tables_arg,
 };
 
 pub fn build_ndl_command() -> Command {
@@ -10,12 +18,21 @@ pub fn build_ndl_command() -> Command {
         .about("Compute no decompression limits")
         .arg(build_depth_arg())
         .arg(build_algo_arg())
+        .arg(build_algo_param_arg())
+        .arg(build_coefficients_arg())
 }
 
 pub fn build_deco_command() -> Command {
     Command::new("deco")
         .about("Compute deco stops")
-        .arg(arg!([NAME]))
+        .arg(build_depth_arg())
+        .arg(build_algo_arg())
+        .arg(build_algo_param_arg())
+        .arg(build_coefficients_arg())
+        .arg(build_time_arg())
+        .arg(build_gas_arg())
+        .arg(build_deco_gas_arg())
+        .arg(build_contingency_arg())
 }
 
 pub fn build_run_command() -> Command {
@@ -23,9 +40,128 @@ pub fn build_run_command() -> Command {
         .about("Run a given dive profile")
         .arg(build_depth_arg())
         .arg(build_algo_arg())
+        .arg(build_algo_param_arg())
+        .arg(build_coefficients_arg())
         .arg(build_time_arg())
         .arg(build_save_csv_arg())
+        .arg(build_save_uddf_arg())
         .arg(build_plot_arg())
         .arg(build_gas_arg())
         .arg(build_interval_arg())
+        .arg(build_integration_step_arg())
+}
+
+pub fn build_compare_command() -> Command {
+    Command::new("compare")
+        .about("Compare a dive across several algorithms")
+        .arg(build_depth_arg())
+        .arg(build_time_arg())
+        .arg(build_gas_arg())
+        .arg(build_deco_gas_arg())
+        .arg(build_compare_algo_arg())
+        .arg(build_sac_arg())
+}
+
+pub fn build_table_command() -> Command {
+    Command::new("table")
+        .about("Generate printable dive tables")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("ndl")
+                .about("No decompression limits across depths and gases")
+                .arg(build_from_depth_arg())
+                .arg(build_to_depth_arg())
+                .arg(build_step_arg())
+                .arg(build_table_gases_arg())
+                .arg(build_max_ppo2_arg())
+                .arg(build_algo_arg())
+                .arg(build_algo_param_arg())
+                .arg(build_coefficients_arg())
+                .arg(build_format_arg()),
+        )
+        .subcommand(
+            Command::new("deco")
+                .about("Deco stops across depths and bottom times")
+                .arg(build_from_depth_arg())
+                .arg(build_to_depth_arg())
+                .arg(build_step_arg())
+                .arg(build_bottom_times_arg())
+                .arg(build_gas_arg())
+                .arg(build_deco_gas_arg())
+                .arg(build_max_ppo2_arg())
+                .arg(build_algo_arg())
+                .arg(build_algo_param_arg())
+                .arg(build_coefficients_arg())
+                .arg(build_book_format_arg()),
+        )
+}
+
+pub fn build_group_command() -> Command {
+    Command::new("group")
+        .about("Compute the US Navy repetitive group and residual nitrogen time")
+        .arg(build_depth_arg())
+        .arg(build_time_arg())
+        .arg(build_gas_arg())
+        .arg(build_deco_gas_arg())
+        .arg(build_algo_arg())
+        .arg(build_algo_param_arg())
+        .arg(build_coefficients_arg())
+        .arg(build_surface_interval_arg())
+        .arg(build_next_depth_arg())
+        .arg(build_repetitive_mode_arg())
+        .arg(build_usn_tables_arg())
+}
+
+pub fn build_plan_command() -> Command {
+    Command::new("plan")
+        .about("Print the runtime table of a dive plan file")
+        .arg(build_plan_file_arg())
+        .arg(build_plan_format_arg())
+}
+
+pub fn build_import_command() -> Command {
+    Command::new("import")
+        .about("Import the dives of a dive computer log")
+        .arg(build_log_file_arg())
+        .arg(build_csv_preset_arg())
+        .arg(build_time_column_arg())
+        .arg(build_depth_column_arg())
+        .arg(build_units_arg())
+        .arg(build_gas_arg())
+        .arg(build_resample_arg())
+        .arg(build_smooth_arg())
+        .arg(build_surface_depth_arg())
+        .arg(build_split_arg())
+}
+
+pub fn build_replay_command() -> Command {
+    Command::new("replay")
+        .about("Replay a logged dive and compare against the dive computer")
+        .arg(build_log_file_arg())
+        .arg(build_compare_algo_arg())
+        .arg(build_dive_number_arg())
+        .arg(build_csv_preset_arg())
+        .arg(build_time_column_arg())
+        .arg(build_depth_column_arg())
+        .arg(build_units_arg())
+        .arg(build_gas_arg())
+}
+
+pub fn build_check_command() -> Command {
+    Command::new("check")
+        .about("Check a logged dive for ascent rate, profile and stop violations")
+        .arg(build_log_file_arg())
+        .arg(build_dive_number_arg())
+        .arg(build_algo_arg())
+        .arg(build_algo_param_arg())
+        .arg(build_coefficients_arg())
+        .arg(build_csv_preset_arg())
+        .arg(build_time_column_arg())
+        .arg(build_depth_column_arg())
+        .arg(build_units_arg())
+        .arg(build_gas_arg())
+        .arg(build_resample_arg())
+        .arg(build_smooth_arg())
+        .arg(build_surface_depth_arg())
+        .arg(build_split_arg())
 }
@@ -0,0 +1,40 @@
+use std::error::Error;
+
+use clap::ArgMatches;
+
+use core::violations::ProfileAnalyzer;
+
+use crate::handlers::algo_from_args;
+use crate::handlers::import::{dives_from_args, preprocess_from_args};
+
+pub fn handle_check_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let number = args.get_one::<u32>("dive");
+    let analyzer = ProfileAnalyzer::new(algo_from_args(args, "zhl16-c")?);
+
+    let mut dives = vec![];
+    for logged in dives_from_args(args)? {
+        if number.is_some() && logged.number.as_ref() != number {
+            continue;
+        }
+
+        dives.extend(preprocess_from_args(args, logged)?);
+    }
+
+    let profiles = dives
+        .iter()
+        .map(|dive| Ok((dive.to_profile()?, dive.environment())))
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    for (dive, violations) in dives.iter().zip(analyzer.analyze_series(&profiles)) {
+        println!("{dive}");
+        if violations.is_empty() {
+            println!("no violations");
+        }
+        for violation in violations {
+            println!("{violation}");
+        }
+        println!();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::error::Error;
+
+use clap::ArgMatches;
+
+use core::{
+    compare::{AlgorithmConfig, Comparator},
+    gas::GasMix,
+    profile::DiveProfile,
+    registry::registry,
+};
+
+pub fn handle_compare_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let depth = args.get_one::<f32>("depth").expect("depth is required");
+    let time = args.get_one::<u32>("time").expect("time is required");
+    let sac = args.get_one::<f32>("sac").expect("sac has a default");
+
+    let gas = match args.get_one::<String>("gas") {
+        Some(txt) => txt.parse::<GasMix>()?,
+        None => GasMix::air(),
+    };
+
+    let mut profile = DiveProfile::new();
+    profile.add_level(*depth, *time, gas);
+
+    if let Some(deco_gases) = args.get_many::<String>("deco-gas") {
+        for txt in deco_gases {
+            profile.add_deco_mix(txt.parse::<GasMix>()?);
+        }
+    }
+
+    let configs = match args.get_many::<String>("with") {
+        Some(specs) => specs
+            .map(|txt| txt.parse::<AlgorithmConfig>())
+            .collect::<Result<Vec<_>, _>>()?,
+        None => registry()
+            .implemented_names()
+            .iter()
+            .map(|name| AlgorithmConfig::new(name))
+            .collect(),
+    };
+
+    let table = Comparator::new(*sac).compare(&configs, &profile)?;
+
+    println!("Comparison for depth: {depth}m, time: {time}min");
+    print!("{table}");
+    Ok(())
+}
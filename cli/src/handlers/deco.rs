@@ -3,12 +3,50 @@ use std::error::Error;
 use clap::ArgMatches;
 
 use core::{
-    algorithm::get_algo,
-    gas::{GasMix, PPO2},
-    profile::{DiveProfile, DiveProfileLevel},
+    contingency::ContingencyGenerator, deco::time_to_surface, gas::GasMix, profile::DiveProfile,
 };
 
+use crate::handlers::algo_from_args;
+
 pub fn handle_deco_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
-    // TODO: implement handle deco cmd
+    let depth = args.get_one::<f32>("depth").expect("depth is required");
+    let time = args.get_one::<u32>("time").expect("time is required");
+
+    let gas = match args.get_one::<String>("gas") {
+        Some(txt) => txt.parse::<GasMix>()?,
+        None => GasMix::air(),
+    };
+
+    let algo = algo_from_args(args, "zhl16-c")?;
+
+    let mut profile = DiveProfile::new();
+    profile.add_level(*depth, *time, gas);
+
+    if let Some(deco_gases) = args.get_many::<String>("deco-gas") {
+        for txt in deco_gases {
+            profile.add_deco_mix(txt.parse::<GasMix>()?);
+        }
+    }
+
+    if args.get_flag("contingency") {
+        let table = ContingencyGenerator::default().generate(algo.as_ref(), &profile);
+        println!("{table}");
+        return Ok(());
+    }
+
+    let stops = algo.compute_deco_stops(profile);
+
+    println!(
+        "Deco stops for depth: {depth}m, time: {time}min, with algorithm: {}",
+        algo.variant()
+    );
+    for stop in &stops {
+        println!(
+            "  {:>3.0}m {:>4}min  {}",
+            stop.depth, stop.time, stop.gas_mix
+        );
+    }
+    println!("Time to surface: {}min", time_to_surface(*depth, &stops));
+
     Ok(())
 }
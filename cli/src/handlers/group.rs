@@ -0,0 +1,75 @@
+use std::error::Error;
+
+use clap::ArgMatches;
+
+use core::{
+    gas::GasMix,
+    profile::DiveProfile,
+    repetitive::{RepetitiveMode, RepetitiveTracker},
+    tables::usn::UsnTables,
+};
+
+use crate::handlers::algo_from_args;
+
+pub fn handle_group_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let depth = args.get_one::<f32>("depth").expect("depth is required");
+    let time = args.get_one::<u32>("time").expect("time is required");
+    let mode = args
+        .get_one::<String>("mode")
+        .expect("mode has a default")
+        .parse::<RepetitiveMode>()?;
+
+    let gas = match args.get_one::<String>("gas") {
+        Some(txt) => txt.parse::<GasMix>()?,
+        None => GasMix::air(),
+    };
+
+    let algo = algo_from_args(args, "zhl16-c")?;
+
+    let mut profile = DiveProfile::new();
+    profile.add_level(*depth, *time, gas);
+
+    if let Some(deco_gases) = args.get_many::<String>("deco-gas") {
+        for txt in deco_gases {
+            profile.add_deco_mix(txt.parse::<GasMix>()?);
+        }
+    }
+
+    let stops = algo.compute_deco_stops(profile.clone());
+
+    let mut tracker = match args.get_one::<String>("usn-tables") {
+        Some(path) if mode == RepetitiveMode::Table => {
+            RepetitiveTracker::with_tables(UsnTables::from_file(path)?)
+        }
+        _ => RepetitiveTracker::new(mode),
+    };
+    let group = tracker.add_dive(&profile, &stops)?;
+
+    println!(
+        "Repetitive group after {depth}m for {time}min ({mode} mode): {}",
+        fmt_group(group)
+    );
+
+    if let Some(interval) = args.get_one::<f32>("surface-interval") {
+        let group = tracker.add_surface_interval(*interval)?;
+        println!(
+            "Repetitive group after {interval}min surface interval: {}",
+            fmt_group(group)
+        );
+    }
+
+    if let Some(next_depth) = args.get_one::<f32>("next-depth") {
+        match tracker.residual_nitrogen_time(*next_depth) {
+            Some(rnt) => println!("Residual nitrogen time at {next_depth}m: {rnt}min"),
+            None => println!(
+                "Residual nitrogen time at {next_depth}m: not reachable at this depth or not in the tables"
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+fn fmt_group<T: ToString>(group: Option<T>) -> String {
+    group.map_or("none".to_string(), |g| g.to_string())
+}
@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::path::Path;
+
+use clap::ArgMatches;
+
+use core::{
+    formats::{
+        csv_log::{self, ColumnMapping, CsvPreset, Units},
+        preprocess::{Filter, Preprocessor},
+        subsurface, uddf, LoggedDive,
+    },
+    gas::GasMix,
+};
+
+pub fn handle_import_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    for dive in dives_from_args(args)? {
+        for processed in preprocess_from_args(args, dive)? {
+            print_dive(&processed)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Preprocess `dive` with the defaults of `Preprocessor`, overridden by the
+/// `--surface-depth`, `--resample`, `--smooth` and `--split` options
+pub fn preprocess_from_args(
+    args: &ArgMatches,
+    dive: LoggedDive,
+) -> Result<Vec<LoggedDive>, Box<dyn Error>> {
+    let mut preprocessor = Preprocessor::new();
+
+    if let Some(depth) = args.get_one::<f32>("surface-depth") {
+        preprocessor.set_surface_depth(*depth);
+    }
+    if let Some(step) = args.get_one::<u32>("resample") {
+        preprocessor.set_timestep(*step);
+    }
+    if let Some(txt) = args.get_one::<String>("smooth") {
+        preprocessor.set_filter(txt.parse::<Filter>()?);
+    }
+    if let Some(interval) = args.get_one::<u32>("split") {
+        preprocessor.set_min_surface_interval(*interval);
+    }
+
+    Ok(preprocessor.process(&dive)?)
+}
+
+/// Dives of the log `file`, the format is chosen by the file extension
+pub fn dives_from_args(args: &ArgMatches) -> Result<Vec<LoggedDive>, Box<dyn Error>> {
+    let file = args.get_one::<String>("file").expect("file is required");
+
+    let extension = Path::new(file)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase());
+
+    let dives = match extension.as_deref() {
+        Some("ssrf") | Some("xml") => subsurface::from_file(file)?,
+        Some("uddf") => uddf::from_file(file)?,
+        Some("csv") => vec![csv_log::from_file(file, &mapping_from_args(args)?)?],
+        _ => return Err(format!("unknown dive log format: {file}").into()),
+    };
+
+    Ok(dives)
+}
+
+/// Column mapping of a preset with columns replaced by `--time-column` and
+/// `--depth-column`
+fn mapping_from_args(args: &ArgMatches) -> Result<ColumnMapping, Box<dyn Error>> {
+    let mut mapping = match args.get_one::<String>("preset") {
+        Some(txt) => txt.parse::<CsvPreset>()?.mapping(),
+        None => ColumnMapping::new("Time", "Depth"),
+    };
+
+    if let Some(column) = args.get_one::<String>("time-column") {
+        mapping.time = column.clone();
+    }
+    if let Some(column) = args.get_one::<String>("depth-column") {
+        mapping.depth = column.clone();
+    }
+    if let Some(txt) = args.get_one::<String>("units") {
+        mapping.set_units(txt.parse::<Units>()?);
+    }
+    if let Some(txt) = args.get_one::<String>("gas") {
+        mapping.set_gas_mix(txt.parse::<GasMix>()?);
+    }
+
+    Ok(mapping)
+}
+
+fn print_dive(dive: &LoggedDive) -> Result<(), Box<dyn Error>> {
+    let profile = dive.to_profile()?;
+
+    println!("{dive}");
+    println!(
+        "  {} profile segments, {}",
+        profile.segments.len(),
+        dive.environment()
+    );
+    Ok(())
+}
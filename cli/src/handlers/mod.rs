@@ -1,3 +1,50 @@
+use std::error::Error;
+
+use clap::ArgMatches;
+
+use core::{
+    algorithm::{get_algo_with_params, DecoAlgorithm},
+    registry::{register_algorithm, zhl16_table_entry, AlgorithmParams},
+    zhl16::coefficients::CoefficientTable,
+};
+
+pub mod check;
+pub mod compare;
 pub mod deco;
+pub mod group;
+pub mod import;
 pub mod ndl;
+pub mod plan;
+pub mod replay;
 pub mod run;
+pub mod table;
+
+/// Build algorithm from `--algorithm`, `--param` and `--coefficients` arguments
+pub fn algo_from_args(
+    args: &ArgMatches,
+    default: &str,
+) -> Result<Box<dyn DecoAlgorithm>, Box<dyn Error>> {
+    let mut default = default.to_string();
+    if let Some(path) = args.get_one::<String>("coefficients") {
+        let table = CoefficientTable::from_file(path)?;
+        default = table.name.clone();
+        register_algorithm(zhl16_table_entry(table)?)?;
+    }
+
+    let name = match args.get_one::<String>("algo") {
+        Some(txt) => txt.as_str(),
+        None => default.as_str(),
+    };
+
+    let mut params = AlgorithmParams::new();
+    if let Some(values) = args.get_many::<String>("param") {
+        for txt in values {
+            let Some((key, value)) = txt.split_once('=') else {
+                return Err(format!("invalid parameter: {txt}, expected name=value").into());
+            };
+            params.insert(key.trim().to_string(), value.trim().parse::<f32>()?);
+        }
+    }
+
+    Ok(get_algo_with_params(name, &params)?)
+}
@@ -2,29 +2,16 @@ use std::error::Error;
 
 use clap::ArgMatches;
 
-use core::{
-    algorithm::get_algo,
-    gas::{GasMix, PPO2},
-    profile::{DiveProfile, DiveProfileLevel},
-};
+use core::{gas::GasMix, profile::DiveProfile};
+
+use crate::handlers::algo_from_args;
 
 pub fn handle_ndl_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
     let depth = args.get_one::<f32>("depth").expect("depth is required");
-    let algo = args
-        .get_one::<String>("algo")
-        .expect("algorithm is required");
-
-    let algo = get_algo(algo)?;
+    let algo = algo_from_args(args, "zhl16-c")?;
 
-    let profile = DiveProfile {
-        levels: vec![{
-            DiveProfileLevel {
-                depth: *depth,
-                time: 0,
-                gas_mix: GasMix::new_nitrox(PPO2),
-            }
-        }],
-    };
+    let mut profile = DiveProfile::new();
+    profile.add_level(*depth, 0, GasMix::air());
 
     let ndl = algo.compute_ndl(profile);
 
@@ -0,0 +1,25 @@
+use std::error::Error;
+
+use clap::ArgMatches;
+
+use core::{
+    formats::{subsurface, uddf},
+    plan::DivePlan,
+};
+
+pub fn handle_plan_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let file = args.get_one::<String>("file").expect("file is required");
+    let format = args
+        .get_one::<String>("format")
+        .expect("format has a default");
+
+    let plan = DivePlan::from_file(file)?;
+
+    match format.as_str() {
+        "json" => println!("{}", plan.runtime()?.to_json()?),
+        "ssrf" => print!("{}", subsurface::export_plan(&plan)?),
+        "uddf" => print!("{}", uddf::export_plan(&plan)?),
+        _ => print!("{}", plan.runtime()?),
+    }
+    Ok(())
+}
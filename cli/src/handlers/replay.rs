@@ -0,0 +1,51 @@
+use std::error::Error;
+
+use clap::ArgMatches;
+
+use core::{compare::AlgorithmConfig, replay::rank};
+
+use crate::handlers::import::dives_from_args;
+
+/// Common conservatism settings of dive computers, replayed when no
+/// `--with` is given
+const COMPUTER_SETTINGS: [&str; 5] = [
+    "zhl16-c:gf_low=30,gf_high=70",
+    "zhl16-c:gf_low=35,gf_high=75",
+    "zhl16-c:gf_low=40,gf_high=85",
+    "zhl16-c:gf_low=50,gf_high=80",
+    "zhl16-c",
+];
+
+pub fn handle_replay_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let configs = match args.get_many::<String>("with") {
+        Some(values) => values
+            .map(|txt| txt.parse::<AlgorithmConfig>())
+            .collect::<Result<Vec<_>, _>>()?,
+        None => COMPUTER_SETTINGS
+            .iter()
+            .map(|txt| txt.parse::<AlgorithmConfig>())
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    let number = args.get_one::<u32>("dive");
+
+    let dives = dives_from_args(args)?;
+    let selected = dives
+        .iter()
+        .filter(|d| number.is_none() || d.number.as_ref() == number);
+
+    for dive in selected {
+        println!("{dive}");
+
+        let reports = rank(dive, &configs)?;
+        if reports.iter().all(|r| r.score().is_none()) {
+            println!("no NDL, ceiling or TTS logged by the computer\n");
+            continue;
+        }
+
+        for report in reports {
+            println!("{report}");
+        }
+    }
+
+    Ok(())
+}
@@ -1,15 +1,20 @@
 use std::error::Error;
+use std::fs::{self, File};
 
 use clap::ArgMatches;
 
 use core::{
-    algorithm::get_algo,
-    gas::{GasMix, PPO2},
-    profile::{DiveProfile, DiveProfileLevel},
-    runner::AlgorithmRunner,
+    deco::ASCENT_RATE,
+    formats::uddf,
+    gas::GasMix,
+    profile::DiveProfileBuilder,
+    runner::{AlgorithmRunResult, AlgorithmRunner, RunSummary, StepCsvWriter},
 };
 
-use crate::{plotter::CliPlotter, utils::str_to_gas};
+use crate::{handlers::algo_from_args, plotter::CliPlotter};
+
+/// Descent rate in meters per minute, the time of the run includes the descent
+const DESCENT_RATE: f32 = 20.0;
 
 pub fn handle_run_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
     let depth = args.get_one::<f32>("depth").expect("depth is required");
@@ -17,28 +22,58 @@ pub fn handle_run_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
     let time = args.get_one::<u32>("time").expect("time is required");
     let interval = match args.get_one::<u32>("interval") {
         Some(&num) => num,
-        None => 5,
+        None => 300,
     };
 
     let gas = match args.get_one::<String>("gas") {
-        Some(txt) => str_to_gas(txt)?,
-        None => GasMix::new_nitrox(0.21),
+        Some(txt) => txt.parse::<GasMix>()?,
+        None => GasMix::air(),
     };
 
-    let algo = match args.get_one::<String>("algo") {
-        Some(txt) => get_algo(txt)?,
-        None => get_algo("zhl16-a")?,
-    };
+    let algo = algo_from_args(args, "zhl16-a")?;
 
-    let mut profile = DiveProfile::new();
-    profile.add_level(*depth, *time, gas);
+    // descent and ascent ramps are loaded as travel, split at
+    // `--integration-step` for algorithms without an exact solution
+    let descent = (depth / DESCENT_RATE * 60.0).round() as u32;
+    let profile = DiveProfileBuilder::new(gas)
+        .descend(*depth, DESCENT_RATE)
+        .hold((time * 60).saturating_sub(descent))
+        .ascend(0.0, ASCENT_RATE)
+        .build()?;
 
+    let mut csv = match args.get_one::<String>("csv") {
+        Some(path) => Some(StepCsvWriter::new(File::create(path)?)),
+        None => None,
+    };
+    let uddf_path = args.get_one::<String>("uddf");
+
+    // steps are streamed, results are only kept for the UDDF export
     let mut runner = AlgorithmRunner::new(algo);
-    runner.run(interval, profile);
+    if let Some(seconds) = args.get_one::<u32>("integration-step") {
+        runner.set_step(*seconds as f32 / 60.0);
+    }
+    let mut summary = RunSummary::new();
+    let mut result = AlgorithmRunResult::new(interval);
+    for step in runner.steps(interval, &profile)? {
+        if let Some(writer) = csv.as_mut() {
+            writer.write(&step)?;
+        }
+        summary.add(&step);
+        if uddf_path.is_some() {
+            result.add_step(step);
+        }
+    }
+
+    if let Some(writer) = csv.as_mut() {
+        writer.flush()?;
+    }
+    if let Some(path) = uddf_path {
+        fs::write(path, uddf::export_run(&result)?)?;
+    }
 
-    // println!("{:?}", runner.result());
+    println!("{summary}");
 
-    if let Some(val) = args.get_one::<String>("plot") {
+    if args.get_one::<String>("plot").is_some() {
         let plotter = CliPlotter::new();
         plotter.plot()?;
     }
@@ -0,0 +1,92 @@
+use std::error::Error;
+
+use clap::ArgMatches;
+
+use core::{
+    gas::GasMix,
+    tables::{deco::DecoTableGenerator, ndl::NdlTableGenerator},
+};
+
+use crate::handlers::algo_from_args;
+
+pub fn handle_table_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    match args.subcommand() {
+        Some(("ndl", sub_matches)) => handle_ndl_table_cmd(sub_matches),
+        Some(("deco", sub_matches)) => handle_deco_table_cmd(sub_matches),
+        _ => unreachable!("clap should ensure we don't get here"),
+    }
+}
+
+fn handle_ndl_table_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let from = args.get_one::<f32>("from").expect("from has a default");
+    let to = args.get_one::<f32>("to").expect("to has a default");
+    let step = args.get_one::<f32>("step").expect("step has a default");
+    let max_ppo2 = args
+        .get_one::<f32>("max-ppo2")
+        .expect("max-ppo2 has a default");
+    let format = args
+        .get_one::<String>("format")
+        .expect("format has a default");
+
+    let gases = args
+        .get_many::<String>("gas")
+        .expect("gas has a default")
+        .map(|txt| txt.parse::<GasMix>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let algo = algo_from_args(args, "zhl16-c")?;
+
+    let mut generator = NdlTableGenerator::new(*from, *to, *step);
+    generator.set_max_ppo2(*max_ppo2);
+    let table = generator.generate(algo.as_ref(), &gases)?;
+
+    match format.as_str() {
+        "csv" => print!("{}", table.to_csv()?),
+        "json" => println!("{}", table.to_json()?),
+        _ => print!("{}", table.to_markdown()),
+    }
+    Ok(())
+}
+
+fn handle_deco_table_cmd(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let from = args.get_one::<f32>("from").expect("from has a default");
+    let to = args.get_one::<f32>("to").expect("to has a default");
+    let step = args.get_one::<f32>("step").expect("step has a default");
+    let max_ppo2 = args
+        .get_one::<f32>("max-ppo2")
+        .expect("max-ppo2 has a default");
+    let format = args
+        .get_one::<String>("format")
+        .expect("format has a default");
+
+    let times = args
+        .get_many::<u32>("times")
+        .expect("times has a default")
+        .copied()
+        .collect();
+
+    let gas = match args.get_one::<String>("gas") {
+        Some(txt) => txt.parse::<GasMix>()?,
+        None => GasMix::air(),
+    };
+
+    let deco_gases = match args.get_many::<String>("deco-gas") {
+        Some(txts) => txts
+            .map(|txt| txt.parse::<GasMix>())
+            .collect::<Result<Vec<_>, _>>()?,
+        None => vec![],
+    };
+
+    let algo = algo_from_args(args, "zhl16-c")?;
+
+    let mut generator = DecoTableGenerator::new(*from, *to, *step);
+    generator.set_bottom_times(times);
+    generator.set_max_ppo2(*max_ppo2);
+    let table = generator.generate(algo.as_ref(), &gas, &deco_gases)?;
+
+    match format.as_str() {
+        "csv" => print!("{}", table.to_csv()?),
+        _ => print!("{table}"),
+    }
+    Ok(())
+}
@@ -1,6 +1,10 @@
-use clap::{arg, value_parser, Arg, ArgAction, Command};
+use clap::Command;
 
-use crate::cmds::{build_deco_command, build_ndl_command, build_run_command};
+use crate::cmds::{
+    build_check_command, build_compare_command, build_deco_command, build_group_command,
+    build_import_command, build_ndl_command, build_plan_command, build_replay_command,
+    build_run_command, build_table_command,
+};
 
 pub fn init() -> Command {
     Command::new("DiveSync")
@@ -13,4 +17,11 @@ pub fn init() -> Command {
         .subcommand(build_ndl_command())
         .subcommand(build_deco_command())
         .subcommand(build_run_command())
+        .subcommand(build_compare_command())
+        .subcommand(build_table_command())
+        .subcommand(build_group_command())
+        .subcommand(build_plan_command())
+        .subcommand(build_import_command())
+        .subcommand(build_replay_command())
+        .subcommand(build_check_command())
 }
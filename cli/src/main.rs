@@ -3,29 +3,43 @@ mod cmds;
 mod handlers;
 mod init;
 mod plotter;
-mod utils;
 
-use handlers::{deco::handle_deco_cmd, ndl::handle_ndl_cmd, run::handle_run_cmd};
+use handlers::{
+    check::handle_check_cmd, compare::handle_compare_cmd, deco::handle_deco_cmd,
+    group::handle_group_cmd, import::handle_import_cmd, ndl::handle_ndl_cmd, plan::handle_plan_cmd,
+    replay::handle_replay_cmd, run::handle_run_cmd, table::handle_table_cmd,
+};
 use init::init;
 
-use core::{
-    algorithm::get_algo,
-    gas::{GasMix, PPO2},
-    profile::{DiveProfile, DiveProfileLevel},
-};
 use std::error::Error;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn run() -> Result<(), Box<dyn Error>> {
     let cmd = init();
 
     let matches = cmd.get_matches();
 
     match matches.subcommand() {
         Some(("ndl", sub_matches)) => handle_ndl_cmd(sub_matches)?,
-        Some(("deco", _sub_matches)) => {
-            println!("{matches:?}");
-        }
+        Some(("deco", sub_matches)) => handle_deco_cmd(sub_matches)?,
         Some(("run", sub_matches)) => handle_run_cmd(sub_matches)?,
+        Some(("compare", sub_matches)) => handle_compare_cmd(sub_matches)?,
+        Some(("table", sub_matches)) => handle_table_cmd(sub_matches)?,
+        Some(("group", sub_matches)) => handle_group_cmd(sub_matches)?,
+        Some(("plan", sub_matches)) => handle_plan_cmd(sub_matches)?,
+        Some(("import", sub_matches)) => handle_import_cmd(sub_matches)?,
+        Some(("replay", sub_matches)) => handle_replay_cmd(sub_matches)?,
+        Some(("check", sub_matches)) => handle_check_cmd(sub_matches)?,
         _ => unreachable!("clap should ensure we don't get here"),
     };
     Ok(())
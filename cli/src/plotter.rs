@@ -10,7 +10,7 @@ impl CliPlotter {
     }
 
     pub fn plot(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let filename = format!("plots/{}.png", timestamp());
+        let filename = format!("plots/{}.png", timestamp()?);
         let root = BitMapBackend::new(&filename, (640, 480)).into_drawing_area();
 
         root.fill(&WHITE)?;
@@ -46,9 +46,9 @@ impl CliPlotter {
             5,
             &RED,
             &|c, s, st| {
-                return EmptyElement::at(c)    // We want to construct a composed element on-the-fly
+                EmptyElement::at(c)    // We want to construct a composed element on-the-fly
             + Circle::new((0,0),s,st.filled()) // At this point, the new pixel coordinate is established
-            + Text::new(format!("{:?}", c), (10, 0), ("sans-serif", 10).into_font());
+            + Text::new(format!("{:?}", c), (10, 0), ("sans-serif", 10).into_font())
             },
         ))?;
 
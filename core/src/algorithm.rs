@@ -1,42 +1,64 @@
-use csv::Writer;
-use std::env;
-use std::error::Error;
 use std::fmt;
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use crate::deco::DecoStop;
-use crate::dsat::algorithm::DSATAlgorithm;
+use crate::error::DivesyncError;
 use crate::gas::GasMix;
 use crate::profile::DiveProfile;
+use crate::registry::{registry, AlgorithmParams};
 use crate::tissue::CompartmentSnapshot;
-use crate::utils::calc_ata;
-use crate::utils::timestamp;
-use crate::zhl16::algorithm::ZHL16Algorithm;
 use crate::zhl16::tissue::ZHL16Variant;
 
-pub trait DecoAlgorithm {
+pub trait DecoAlgorithm: DecoAlgorithmClone {
     fn variant(&self) -> DecoAlgorithmVariant;
     fn compute_deco_stops(&self, dive_profile: DiveProfile) -> Vec<DecoStop>;
     fn compute_ndl(&self, dive_profile: DiveProfile) -> u32;
     fn snapshot(&self) -> Vec<CompartmentSnapshot>;
     fn run(&mut self, mix: GasMix, ata: f32, time: f32);
+
+    /// Run `time` minutes while the ambient pressure changes linearly from
+    /// `start_ata` to `end_ata`, defaults to a single step at the mean
+    /// pressure for algorithms without an exact solution
+    fn run_travel(&mut self, mix: GasMix, start_ata: f32, end_ata: f32, time: f32) {
+        self.run(mix, (start_ata + end_ata) / 2.0, time);
+    }
 }
 
-pub fn get_algo(algo: &str) -> Result<Box<dyn DecoAlgorithm>, Box<dyn std::error::Error>> {
-    let algo: DecoAlgorithmVariant = algo.into();
-    match algo {
-        DecoAlgorithmVariant::Dsat => Ok(Box::new(DSATAlgorithm::new())),
-        DecoAlgorithmVariant::ZHL16(ZHL16Variant::A) => {
-            Ok(Box::new(ZHL16Algorithm::new(ZHL16Variant::A)))
-        }
-        DecoAlgorithmVariant::ZHL16(ZHL16Variant::B) => {
-            Ok(Box::new(ZHL16Algorithm::new(ZHL16Variant::B)))
-        }
-        DecoAlgorithmVariant::ZHL16(ZHL16Variant::C) => {
-            Ok(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)))
-        }
-        _ => panic!("unable to find algorithm"),
+/// Copy of a boxed algorithm with its tissues, implemented for every
+/// algorithm that is `Clone`
+pub trait DecoAlgorithmClone {
+    fn clone_box(&self) -> Box<dyn DecoAlgorithm>;
+}
+
+impl<T: DecoAlgorithm + Clone + 'static> DecoAlgorithmClone for T {
+    fn clone_box(&self) -> Box<dyn DecoAlgorithm> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn DecoAlgorithm> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Build algorithm from the global registry with default parameters
+pub fn get_algo(algo: &str) -> Result<Box<dyn DecoAlgorithm>, DivesyncError> {
+    get_algo_with_params(algo, &AlgorithmParams::new())
+}
+
+/// Build algorithm from the global registry, params are validated against
+/// the parameter schema of the registered algorithm. Placeholders are
+/// rejected so their output is never mistaken for a model result
+pub fn get_algo_with_params(
+    algo: &str,
+    params: &AlgorithmParams,
+) -> Result<Box<dyn DecoAlgorithm>, DivesyncError> {
+    let registry = registry();
+
+    match registry.find(algo) {
+        Some(entry) if !entry.implemented => Err(DivesyncError::NotImplemented(entry.name.clone())),
+        _ => registry.build(algo, params),
     }
 }
 
@@ -44,17 +66,28 @@ pub fn get_algo(algo: &str) -> Result<Box<dyn DecoAlgorithm>, Box<dyn std::error
 pub enum DecoAlgorithmVariant {
     ZHL16(ZHL16Variant),
     Dsat,
+    Haldane,
+    Workman,
+    Rgbm,
+    /// Algorithm registered outside of core, identified by its registry name
+    Custom(String),
 }
 
-impl From<&str> for DecoAlgorithmVariant {
-    fn from(s: &str) -> Self {
+impl FromStr for DecoAlgorithmVariant {
+    type Err = DivesyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "dsat" => DecoAlgorithmVariant::Dsat,
-            "zhl16" => DecoAlgorithmVariant::ZHL16(ZHL16Variant::A),
-            "zhl16-a" => DecoAlgorithmVariant::ZHL16(ZHL16Variant::A),
-            "zhl16-b" => DecoAlgorithmVariant::ZHL16(ZHL16Variant::B),
-            "zhl16-c" => DecoAlgorithmVariant::ZHL16(ZHL16Variant::C),
-            _ => panic!("Invalid DecoAlgorithm: {}", s),
+            "dsat" => Ok(DecoAlgorithmVariant::Dsat),
+            "haldane" => Ok(DecoAlgorithmVariant::Haldane),
+            "workman" => Ok(DecoAlgorithmVariant::Workman),
+            "rgbm" => Ok(DecoAlgorithmVariant::Rgbm),
+            "zhl16" => Ok(DecoAlgorithmVariant::ZHL16(ZHL16Variant::A)),
+            "zhl16-a" => Ok(DecoAlgorithmVariant::ZHL16(ZHL16Variant::A)),
+            "zhl16-b" => Ok(DecoAlgorithmVariant::ZHL16(ZHL16Variant::B)),
+            "zhl16-c" => Ok(DecoAlgorithmVariant::ZHL16(ZHL16Variant::C)),
+            "zhl12" | "zh-l12" => Ok(DecoAlgorithmVariant::ZHL16(ZHL16Variant::L12)),
+            _ => Err(DivesyncError::UnknownAlgorithm(s.to_string())),
         }
     }
 }
@@ -63,13 +96,56 @@ impl fmt::Display for DecoAlgorithmVariant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DecoAlgorithmVariant::Dsat => write!(f, "DSAT"),
-            DecoAlgorithmVariant::ZHL16(ZHL16Variant::A) => write!(f, "ZHL16-A"),
-            DecoAlgorithmVariant::ZHL16(ZHL16Variant::B) => write!(f, "ZHL16-B"),
-            DecoAlgorithmVariant::ZHL16(ZHL16Variant::C) => write!(f, "ZHL16-C"),
+            DecoAlgorithmVariant::Haldane => write!(f, "Haldane"),
+            DecoAlgorithmVariant::Workman => write!(f, "Workman"),
+            DecoAlgorithmVariant::Rgbm => write!(f, "RGBM"),
+            DecoAlgorithmVariant::ZHL16(variant) => write!(f, "{variant}"),
+            DecoAlgorithmVariant::Custom(name) => write!(f, "{name}"),
         }
     }
 }
 
+#[cfg(test)]
 mod test {
-    // TODO: algorithm tests
+    use super::*;
+
+    #[test]
+    fn test_variant_from_str() {
+        let variant: DecoAlgorithmVariant = "ZHL16-C".parse().unwrap();
+        assert_eq!(variant.to_string(), "ZHL16-C");
+
+        let variant: DecoAlgorithmVariant = "zh-l12".parse().unwrap();
+        assert_eq!(variant.to_string(), "ZHL12");
+
+        let variant: DecoAlgorithmVariant = "dsat".parse().unwrap();
+        assert_eq!(variant.to_string(), "DSAT");
+    }
+
+    #[test]
+    fn test_variant_from_str_unknown() {
+        let err = "vpm-b".parse::<DecoAlgorithmVariant>().unwrap_err();
+
+        assert!(matches!(err, DivesyncError::UnknownAlgorithm(name) if name == "vpm-b"));
+    }
+
+    #[test]
+    fn test_get_algo() {
+        assert_eq!(
+            get_algo("zhl16-b").unwrap().variant().to_string(),
+            "ZHL16-B"
+        );
+        assert!(get_algo("haldane-9").is_err());
+    }
+
+    #[test]
+    fn test_get_algo_with_params() {
+        let params = AlgorithmParams::from([("gf_high".to_string(), 85.0)]);
+
+        assert!(get_algo_with_params("zhl16-c", &params).is_ok());
+        assert!(get_algo_with_params("dsat", &params).is_err());
+        assert!(matches!(
+            get_algo("dsat"),
+            Err(DivesyncError::NotImplemented(name)) if name == "DSAT"
+        ));
+    }
 }
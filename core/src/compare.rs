@@ -0,0 +1,300 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::get_algo_with_params;
+use crate::deco::{ascent_gas_volumes, time_to_surface, DecoStop, DEFAULT_SAC};
+use crate::error::DivesyncError;
+use crate::gas::GasMix;
+use crate::profile::{DiveProfile, Segment};
+use crate::registry::AlgorithmParams;
+
+/// Registered algorithm with its parameters, parsed from
+/// `name[:param=value,...]`, eg. `zhl16-c:gf_low=30,gf_high=85`, files
+/// accept that text or a `name` and `params` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawAlgorithmConfig")]
+pub struct AlgorithmConfig {
+    #[serde(skip_serializing)]
+    pub label: String,
+    pub name: String,
+    pub params: AlgorithmParams,
+}
+
+impl AlgorithmConfig {
+    pub fn new(name: &str) -> Self {
+        Self {
+            label: name.to_string(),
+            name: name.to_string(),
+            params: AlgorithmParams::new(),
+        }
+    }
+
+    pub fn param(mut self, name: &str, value: f32) -> Self {
+        self.params.insert(name.to_string(), value);
+        self
+    }
+}
+
+impl FromStr for AlgorithmConfig {
+    type Err = DivesyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, params) = s.split_once(':').unwrap_or((s, ""));
+
+        let mut config = AlgorithmConfig::new(name.trim());
+        config.label = s.to_string();
+
+        for txt in params.split(',').filter(|p| !p.trim().is_empty()) {
+            let parsed = txt
+                .split_once('=')
+                .and_then(|(key, value)| Some((key.trim(), value.trim().parse::<f32>().ok()?)));
+
+            match parsed {
+                Some((key, value)) => config = config.param(key, value),
+                None => {
+                    return Err(DivesyncError::Parse(format!(
+                        "invalid parameter: {txt}, expected name=value"
+                    )))
+                }
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawAlgorithmConfig {
+    Text(String),
+    Table {
+        name: String,
+        #[serde(default)]
+        params: AlgorithmParams,
+    },
+}
+
+impl TryFrom<RawAlgorithmConfig> for AlgorithmConfig {
+    type Error = DivesyncError;
+
+    fn try_from(raw: RawAlgorithmConfig) -> Result<Self, Self::Error> {
+        match raw {
+            RawAlgorithmConfig::Text(txt) => txt.parse(),
+            RawAlgorithmConfig::Table { name, params } => Ok(Self {
+                label: name.clone(),
+                name,
+                params,
+            }),
+        }
+    }
+}
+
+/// Result of a single algorithm, `ndl` is the no-stop limit at the depth
+/// of the last level, times are in minutes and gas volumes in surface liters
+#[derive(Debug, Clone)]
+pub struct ComparisonRow {
+    pub label: String,
+    pub variant: String,
+    pub ndl: u32,
+    pub stops: Vec<DecoStop>,
+    pub tts: u32,
+    pub run_time: u32,
+    pub deco_gas: Vec<(GasMix, f32)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComparisonTable {
+    pub rows: Vec<ComparisonRow>,
+}
+
+impl fmt::Display for ComparisonTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .rows
+            .iter()
+            .map(|r| r.label.len())
+            .max()
+            .unwrap_or(0)
+            .max("Algorithm".len());
+
+        writeln!(
+            f,
+            "{:<width$} {:>4} {:>4} {:>4} | {:<32} | Deco gas",
+            "Algorithm", "NDL", "TTS", "RT", "Stops"
+        )?;
+
+        for row in &self.rows {
+            let stops = match row.stops.is_empty() {
+                true => "-".to_string(),
+                false => row
+                    .stops
+                    .iter()
+                    .map(|s| format!("{:.0}m/{}", s.depth, s.time))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+            let gas = row
+                .deco_gas
+                .iter()
+                .map(|(mix, liters)| format!("{mix} {liters:.0}L"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            writeln!(
+                f,
+                "{:<width$} {:>4} {:>4} {:>4} | {:<32} | {}",
+                row.label, row.ndl, row.tts, row.run_time, stops, gas
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Run one dive profile through several algorithms
+pub struct Comparator {
+    sac: f32,
+}
+
+impl Comparator {
+    /// `sac` is the surface air consumption in liters per minute used
+    /// for the deco gas volumes
+    pub fn new(sac: f32) -> Self {
+        Self { sac }
+    }
+
+    pub fn compare(
+        &self,
+        configs: &[AlgorithmConfig],
+        profile: &DiveProfile,
+    ) -> Result<ComparisonTable, DivesyncError> {
+        let mut rows = vec![];
+
+        for config in configs {
+            rows.push(self.compare_one(config, profile)?);
+        }
+
+        Ok(ComparisonTable { rows })
+    }
+
+    fn compare_one(
+        &self,
+        config: &AlgorithmConfig,
+        profile: &DiveProfile,
+    ) -> Result<ComparisonRow, DivesyncError> {
+        let algo = get_algo_with_params(&config.name, &config.params)?;
+
+        // no-stop limit at the last hold, previous segments are kept
+        let mut ndl_profile = profile.clone();
+        if let Some(Segment::Hold { duration, .. }) = ndl_profile.segments.last_mut() {
+            *duration = 0;
+        }
+        let ndl = algo.compute_ndl(ndl_profile);
+
+        let stops = algo.compute_deco_stops(profile.clone());
+        let (depth, deco_gas) = match profile.last_exposure() {
+            Some(last) => (
+                last.end_depth,
+                ascent_gas_volumes(last.end_depth, &last.gas_mix, &stops, self.sac),
+            ),
+            None => (0.0, vec![]),
+        };
+        let tts = time_to_surface(depth, &stops);
+
+        Ok(ComparisonRow {
+            label: config.label.clone(),
+            variant: algo.variant().to_string(),
+            ndl,
+            stops,
+            tts,
+            run_time: profile.bottom_time() + tts,
+            deco_gas,
+        })
+    }
+}
+
+impl Default for Comparator {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAC)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn plan() -> DiveProfile {
+        let mut profile = DiveProfile::new();
+        profile.add_level(45.0, 30, GasMix::air());
+        profile.add_deco_mix(GasMix::new_nitrox(0.5).unwrap());
+        profile
+    }
+
+    #[test]
+    fn test_config_from_str() {
+        let config: AlgorithmConfig = "zhl16-c:gf_low=30, gf_high=85".parse().unwrap();
+
+        assert_eq!(config.name, "zhl16-c");
+        assert_eq!(config.label, "zhl16-c:gf_low=30, gf_high=85");
+        assert_eq!(config.params["gf_high"], 85.0);
+
+        let config: AlgorithmConfig = "dsat".parse().unwrap();
+        assert!(config.params.is_empty());
+
+        assert!("zhl16-c:gf_low".parse::<AlgorithmConfig>().is_err());
+    }
+
+    #[test]
+    fn test_config_serde() {
+        let text: AlgorithmConfig = serde_json::from_str("\"zhl16-c:gf_low=30\"").unwrap();
+        let table: AlgorithmConfig =
+            serde_json::from_str(r#"{"name": "zhl16-c", "params": {"gf_low": 30}}"#).unwrap();
+
+        assert_eq!(text.name, table.name);
+        assert_eq!(text.params, table.params);
+        assert_eq!(table.label, "zhl16-c");
+        assert!(serde_json::from_str::<AlgorithmConfig>("\"zhl16-c:gf_low\"").is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let configs = vec![
+            AlgorithmConfig::new("zhl16-c"),
+            "zhl16-c:gf_low=30,gf_high=70".parse().unwrap(),
+            AlgorithmConfig::new("workman"),
+        ];
+
+        let table = Comparator::default().compare(&configs, &plan()).unwrap();
+        let [raw, gf, workman] = &table.rows[..] else {
+            panic!("expected 3 rows");
+        };
+
+        assert_eq!(raw.variant, "ZHL16-C");
+        assert_eq!(workman.variant, "Workman");
+        assert!(gf.tts > raw.tts);
+        assert_eq!(gf.run_time, 30 + gf.tts);
+        assert_eq!(raw.ndl, gf.ndl.max(raw.ndl));
+        assert!(raw.deco_gas.iter().any(|(mix, _)| mix.fo2() == 0.5));
+        assert!(table.to_string().contains("zhl16-c:gf_low=30,gf_high=70"));
+    }
+
+    #[test]
+    fn test_compare_unknown_algorithm() {
+        let configs = vec![AlgorithmConfig::new("vpm-b")];
+
+        let err = Comparator::default()
+            .compare(&configs, &plan())
+            .unwrap_err();
+
+        assert!(matches!(err, DivesyncError::UnknownAlgorithm(_)));
+
+        let err = Comparator::default()
+            .compare(&[AlgorithmConfig::new("dsat")], &plan())
+            .unwrap_err();
+
+        assert!(matches!(err, DivesyncError::NotImplemented(_)));
+    }
+}
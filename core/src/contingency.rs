@@ -0,0 +1,311 @@
+use std::fmt;
+
+use crate::algorithm::DecoAlgorithm;
+use crate::deco::{time_to_surface, DecoStop};
+use crate::gas::GasMix;
+use crate::profile::{DiveProfile, Segment};
+
+/// Default extra depth in meters for the deeper contingency
+pub const EXTRA_DEPTH: f32 = 3.0;
+
+/// Default extra bottom time in minutes for the longer contingency
+pub const EXTRA_TIME: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContingencyKind {
+    Planned,
+    Deeper,
+    Longer,
+    DeeperLonger,
+}
+
+impl ContingencyKind {
+    pub fn all() -> [ContingencyKind; 4] {
+        [
+            ContingencyKind::Planned,
+            ContingencyKind::Deeper,
+            ContingencyKind::Longer,
+            ContingencyKind::DeeperLonger,
+        ]
+    }
+}
+
+impl fmt::Display for ContingencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContingencyKind::Planned => write!(f, "Planned"),
+            ContingencyKind::Deeper => write!(f, "Deeper"),
+            ContingencyKind::Longer => write!(f, "Longer"),
+            ContingencyKind::DeeperLonger => write!(f, "Deeper+Longer"),
+        }
+    }
+}
+
+/// Single alternative schedule of a contingency table,
+/// `lost_mix` is the deco gas assumed lost, if any
+#[derive(Debug, Clone)]
+pub struct ContingencySchedule {
+    pub kind: ContingencyKind,
+    pub lost_mix: Option<GasMix>,
+    pub depth: f32,
+    pub bottom_time: u32,
+    pub stops: Vec<DecoStop>,
+    pub run_time: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContingencyTable {
+    pub schedules: Vec<ContingencySchedule>,
+}
+
+impl ContingencyTable {
+    /// All stop depths used by any schedule, deepest first
+    pub fn stop_depths(&self) -> Vec<f32> {
+        let mut depths: Vec<f32> = vec![];
+
+        for stop in self.schedules.iter().flat_map(|s| &s.stops) {
+            if !depths.contains(&stop.depth) {
+                depths.push(stop.depth);
+            }
+        }
+
+        depths.sort_by(|a, b| b.total_cmp(a));
+        depths
+    }
+}
+
+impl fmt::Display for ContingencyTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let depths = self.stop_depths();
+
+        write!(
+            f,
+            "{:<14} {:<11} {:>5} {:>4} |",
+            "Case", "Lost", "Depth", "BT"
+        )?;
+        for depth in &depths {
+            write!(f, " {:>3}", format!("{depth:.0}"))?;
+        }
+        writeln!(f, " | {:>4}", "RT")?;
+
+        for schedule in &self.schedules {
+            let lost = match &schedule.lost_mix {
+                Some(mix) => mix.to_string(),
+                None => "-".to_string(),
+            };
+
+            write!(
+                f,
+                "{:<14} {:<11} {:>5} {:>4} |",
+                schedule.kind.to_string(),
+                lost,
+                format!("{:.0}m", schedule.depth),
+                schedule.bottom_time
+            )?;
+
+            for depth in &depths {
+                match schedule.stops.iter().find(|s| s.depth == *depth) {
+                    Some(stop) => write!(f, " {:>3}", stop.time)?,
+                    None => write!(f, " {:>3}", "-")?,
+                }
+            }
+
+            writeln!(f, " | {:>4}", schedule.run_time)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Generate backup schedules for a dive plan, each schedule is computed
+/// with `compute_deco_stops` of the same algorithm so all variants share
+/// the same conservatism
+pub struct ContingencyGenerator {
+    extra_depth: f32,
+    extra_time: u32,
+}
+
+impl ContingencyGenerator {
+    pub fn new(extra_depth: f32, extra_time: u32) -> Self {
+        Self {
+            extra_depth,
+            extra_time,
+        }
+    }
+
+    /// Build the full matrix of deeper/longer variants, each with all
+    /// deco gases and with every single deco gas lost
+    pub fn generate(&self, algo: &dyn DecoAlgorithm, profile: &DiveProfile) -> ContingencyTable {
+        let mut lost_mixes: Vec<Option<GasMix>> = vec![None];
+        lost_mixes.extend(profile.deco_mixes.iter().cloned().map(Some));
+
+        let mut schedules = vec![];
+
+        for kind in ContingencyKind::all() {
+            for (i, lost_mix) in lost_mixes.iter().enumerate() {
+                let mut variant = self.apply(&kind, profile);
+
+                // index 0 is the schedule with all gases available
+                if i > 0 {
+                    variant.deco_mixes.remove(i - 1);
+                }
+
+                schedules.push(build_schedule(
+                    algo,
+                    kind.clone(),
+                    lost_mix.clone(),
+                    variant,
+                ));
+            }
+        }
+
+        ContingencyTable { schedules }
+    }
+
+    /// Apply contingency to the deepest hold of the profile, travel to and
+    /// from it is kept
+    fn apply(&self, kind: &ContingencyKind, profile: &DiveProfile) -> DiveProfile {
+        let mut profile = profile.clone();
+        let max_depth = profile.max_depth();
+
+        let deeper = matches!(
+            kind,
+            ContingencyKind::Deeper | ContingencyKind::DeeperLonger
+        );
+        let longer = matches!(
+            kind,
+            ContingencyKind::Longer | ContingencyKind::DeeperLonger
+        );
+
+        let deepest = profile
+            .segments
+            .iter()
+            .position(|s| matches!(s, Segment::Hold { depth, .. } if *depth == max_depth));
+
+        for (i, segment) in profile.segments.iter_mut().enumerate() {
+            match segment {
+                Segment::Hold {
+                    depth, duration, ..
+                } if Some(i) == deepest => {
+                    if deeper {
+                        *depth += self.extra_depth;
+                    }
+                    if longer {
+                        *duration += self.extra_time * 60;
+                    }
+                }
+                Segment::Travel { depth, .. } if deeper && *depth == max_depth => {
+                    *depth += self.extra_depth;
+                }
+                _ => {}
+            }
+        }
+
+        profile
+    }
+}
+
+impl Default for ContingencyGenerator {
+    fn default() -> Self {
+        Self::new(EXTRA_DEPTH, EXTRA_TIME)
+    }
+}
+
+fn build_schedule(
+    algo: &dyn DecoAlgorithm,
+    kind: ContingencyKind,
+    lost_mix: Option<GasMix>,
+    profile: DiveProfile,
+) -> ContingencySchedule {
+    let depth = profile.max_depth();
+    let ascent_depth = profile.last_exposure().map_or(0.0, |e| e.end_depth);
+    let bottom_time = profile.bottom_time();
+
+    let stops = algo.compute_deco_stops(profile);
+    let run_time = bottom_time + time_to_surface(ascent_depth, &stops);
+
+    ContingencySchedule {
+        kind,
+        lost_mix,
+        depth,
+        bottom_time,
+        stops,
+        run_time,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deco::total_stop_time;
+    use crate::zhl16::{algorithm::ZHL16Algorithm, tissue::ZHL16Variant};
+
+    fn plan() -> DiveProfile {
+        let mut profile = DiveProfile::new();
+        profile.add_level(40.0, 25, GasMix::air());
+        profile.add_deco_mix(GasMix::new_nitrox(0.5).unwrap());
+        profile.add_deco_mix(GasMix::new_nitrox(1.0).unwrap());
+        profile
+    }
+
+    #[test]
+    fn test_contingency_matrix_size() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+        let table = ContingencyGenerator::default().generate(&algo, &plan());
+
+        // 4 kinds, each with all gases plus each of 2 deco gases lost
+        assert_eq!(table.schedules.len(), 12);
+    }
+
+    #[test]
+    fn test_contingency_deeper_longer_adds_deco() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+        let table = ContingencyGenerator::default().generate(&algo, &plan());
+
+        let find = |kind: ContingencyKind| {
+            table
+                .schedules
+                .iter()
+                .find(|s| s.kind == kind && s.lost_mix.is_none())
+                .unwrap()
+        };
+
+        let planned = find(ContingencyKind::Planned);
+        let deeper = find(ContingencyKind::Deeper);
+        let longer = find(ContingencyKind::Longer);
+        let both = find(ContingencyKind::DeeperLonger);
+
+        assert_eq!(deeper.depth, 43.0);
+        assert_eq!(longer.bottom_time, 28);
+        assert!(total_stop_time(&deeper.stops) >= total_stop_time(&planned.stops));
+        assert!(total_stop_time(&longer.stops) >= total_stop_time(&planned.stops));
+        assert!(both.run_time > planned.run_time);
+    }
+
+    #[test]
+    fn test_contingency_lost_gas_adds_deco() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+        let table = ContingencyGenerator::default().generate(&algo, &plan());
+
+        let planned = &table.schedules[0];
+        let lost_ean50 = &table.schedules[1];
+        let lost_oxygen = &table.schedules[2];
+
+        assert_eq!(lost_ean50.lost_mix.as_ref().unwrap().fo2(), 0.5);
+        assert!(lost_ean50.run_time >= planned.run_time);
+        assert!(lost_oxygen.run_time > planned.run_time);
+    }
+
+    #[test]
+    fn test_contingency_table_display() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+        let table = ContingencyGenerator::default().generate(&algo, &plan());
+
+        let slate = table.to_string();
+
+        // header and schedules
+        assert_eq!(slate.lines().count(), table.schedules.len() + 1);
+        assert!(slate.contains("Deeper+Longer"));
+        assert!(slate.contains("EAN50"));
+    }
+}
@@ -1,3 +1,251 @@
+use crate::gas::GasMix;
+use crate::utils::calc_ata;
+
+/// Ascent rate in meters per minute used when travelling between stops
+pub const ASCENT_RATE: f32 = 10.0;
+
+/// Distance in meters between consecutive decompression stops
+pub const STOP_INTERVAL: f32 = 3.0;
+
+/// Maximum partial pressure of oxygen at which a deco gas may be breathed
+pub const MAX_DECO_PPO2: f32 = 1.6;
+
+/// Surface air consumption in liters per minute used for gas planning
+pub const DEFAULT_SAC: f32 = 20.0;
+
+/// Upper bound of minutes spent at a single deco stop
+const MAX_STOP_TIME: u32 = 1440;
+
+/// Single decompression stop, time is in whole minutes
+#[derive(Debug, Clone)]
 pub struct DecoStop {
-    // Define the structure for decompression stop details
+    pub depth: f32,
+    pub time: u32,
+    pub gas_mix: GasMix,
+}
+
+impl DecoStop {
+    pub fn new(depth: f32, time: u32, gas_mix: GasMix) -> Self {
+        Self {
+            depth,
+            time,
+            gas_mix,
+        }
+    }
+}
+
+/// Gradient factors used to adjust conservatism of M-value based algorithms,
+/// values are fractions, eg. GF 30/85 is `GradientFactors::new(0.30, 0.85)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientFactors {
+    pub low: f32,
+    pub high: f32,
+}
+
+impl GradientFactors {
+    pub fn new(low: f32, high: f32) -> Self {
+        Self { low, high }
+    }
+
+    /// Get gradient factor at given depth, linearly interpolated between
+    /// GF low at the first stop and GF high at the surface
+    pub fn at_depth(&self, depth: f32, first_stop: f32) -> f32 {
+        if first_stop <= 0.0 {
+            return self.high;
+        }
+
+        self.high - (self.high - self.low) * (depth / first_stop)
+    }
+}
+
+impl Default for GradientFactors {
+    fn default() -> Self {
+        Self::new(1.0, 1.0)
+    }
+}
+
+/// Total time spent at stops in minutes
+pub fn total_stop_time(stops: &[DecoStop]) -> u32 {
+    stops.iter().map(|s| s.time).sum()
+}
+
+/// Total time to surface from depth, sum of all stops and travel time
+/// at `ASCENT_RATE`, rounded up to the next minute
+pub fn time_to_surface(depth: f32, stops: &[DecoStop]) -> u32 {
+    total_stop_time(stops) + (depth / ASCENT_RATE).ceil() as u32
+}
+
+/// Select mix to breathe at given depth, the richest deco mix which does not
+/// exceed `MAX_DECO_PPO2` is used, otherwise falls back to bottom mix
+pub fn best_mix_at_depth(depth: f32, bottom_mix: &GasMix, deco_mixes: &[GasMix]) -> GasMix {
+    let ata = calc_ata(depth);
+    let mut best = bottom_mix;
+
+    for mix in deco_mixes {
+        if mix.pp_o2(ata) <= MAX_DECO_PPO2 && mix.fo2() > best.fo2() {
+            best = mix;
+        }
+    }
+
+    best.clone()
+}
+
+/// Tissue state of an algorithm as seen by `schedule_stops`
+pub trait DecoTissues {
+    /// Deepest ceiling in meters at gradient factor `gf`, 0.0 if direct
+    /// ascent to the surface is allowed
+    fn ceiling(&self, gf: f32) -> f32;
+
+    /// Breathe `mix` at `depth` for `time` minutes
+    fn expose(&mut self, mix: &GasMix, depth: f32, time: f32);
+}
+
+/// Next shallower stop depth, multiple of `STOP_INTERVAL`
+pub fn next_stop_depth(depth: f32) -> f32 {
+    let next = ((depth / STOP_INTERVAL).ceil() - 1.0) * STOP_INTERVAL;
+    next.max(0.0)
+}
+
+/// Ascend from `depth` on the `STOP_INTERVAL` grid, holding at each stop
+/// until the ceiling clears the next one. Gradient factors are interpolated
+/// from GF low at the first stop to GF high at the surface, travel between
+/// stops is approximated at the mean depth of the ascent
+pub fn schedule_stops<T: DecoTissues>(
+    tissues: &mut T,
+    depth: f32,
+    bottom_mix: &GasMix,
+    deco_mixes: &[GasMix],
+    gf: GradientFactors,
+) -> Vec<DecoStop> {
+    let mut stops = vec![];
+    let mut depth = depth;
+    let mut first_stop: Option<f32> = None;
+
+    while depth > 0.0 {
+        let next_depth = next_stop_depth(depth);
+        let gf_next = match first_stop {
+            Some(first_stop) => gf.at_depth(next_depth, first_stop),
+            None => gf.low,
+        };
+
+        let mix = best_mix_at_depth(depth, bottom_mix, deco_mixes);
+
+        if tissues.ceiling(gf_next) > next_depth {
+            let first_stop = *first_stop.get_or_insert(depth);
+            let gf_next = gf.at_depth(next_depth, first_stop);
+
+            let mut time = 0;
+            while tissues.ceiling(gf_next) > next_depth && time < MAX_STOP_TIME {
+                tissues.expose(&mix, depth, 1.0);
+                time += 1;
+            }
+
+            stops.push(DecoStop::new(depth, time, mix.clone()));
+        }
+
+        let travel_time = (depth - next_depth) / ASCENT_RATE;
+        tissues.expose(&mix, (depth + next_depth) / 2.0, travel_time);
+
+        depth = next_depth;
+    }
+
+    stops
+}
+
+/// Surface liters of each mix breathed during the ascent from `depth`,
+/// including travel between stops, at a consumption of `sac` liters per minute
+pub fn ascent_gas_volumes(
+    depth: f32,
+    bottom_mix: &GasMix,
+    stops: &[DecoStop],
+    sac: f32,
+) -> Vec<(GasMix, f32)> {
+    let mut volumes: Vec<(GasMix, f32)> = vec![];
+    let mut add = |mix: &GasMix, depth: f32, time: f32| {
+        let liters = sac * calc_ata(depth) * time;
+        match volumes.iter_mut().find(|(m, _)| m == mix) {
+            Some((_, total)) => *total += liters,
+            None => volumes.push((mix.clone(), liters)),
+        }
+    };
+
+    let mut current_depth = depth;
+    let mut mix = bottom_mix;
+    for stop in stops {
+        let travel = (current_depth - stop.depth) / ASCENT_RATE;
+        add(mix, (current_depth + stop.depth) / 2.0, travel);
+
+        mix = &stop.gas_mix;
+        add(mix, stop.depth, stop.time as f32);
+        current_depth = stop.depth;
+    }
+    add(mix, current_depth / 2.0, current_depth / ASCENT_RATE);
+
+    volumes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gas::AIR_O2;
+
+    #[test]
+    fn test_next_stop_depth() {
+        assert_eq!(next_stop_depth(40.0), 39.0);
+        assert_eq!(next_stop_depth(39.0), 36.0);
+        assert_eq!(next_stop_depth(3.0), 0.0);
+        assert_eq!(next_stop_depth(1.0), 0.0);
+    }
+
+    #[test]
+    fn test_gradient_factor_at_depth() {
+        let gf = GradientFactors::new(0.3, 0.85);
+
+        assert_eq!(gf.at_depth(21.0, 21.0), 0.3);
+        assert_eq!(gf.at_depth(0.0, 21.0), 0.85);
+        assert_eq!(gf.at_depth(0.0, 0.0), 0.85);
+    }
+
+    #[test]
+    fn test_best_mix_at_depth() {
+        let air = GasMix::air();
+        let ean50 = GasMix::new_nitrox(0.5).unwrap();
+        let oxygen = GasMix::new_nitrox(1.0).unwrap();
+        let deco_mixes = vec![ean50, oxygen];
+
+        assert_eq!(best_mix_at_depth(30.0, &air, &deco_mixes).fo2(), AIR_O2);
+        assert_eq!(best_mix_at_depth(21.0, &air, &deco_mixes).fo2(), 0.5);
+        assert_eq!(best_mix_at_depth(6.0, &air, &deco_mixes).fo2(), 1.0);
+    }
+
+    #[test]
+    fn test_time_to_surface() {
+        let air = GasMix::air();
+        let stops = vec![
+            DecoStop::new(6.0, 2, air.clone()),
+            DecoStop::new(3.0, 5, air),
+        ];
+
+        assert_eq!(total_stop_time(&stops), 7);
+        assert_eq!(time_to_surface(30.0, &stops), 10);
+    }
+
+    #[test]
+    fn test_ascent_gas_volumes() {
+        let air = GasMix::air();
+        let ean50 = GasMix::new_nitrox(0.5).unwrap();
+        let stops = vec![
+            DecoStop::new(6.0, 2, ean50.clone()),
+            DecoStop::new(3.0, 5, ean50.clone()),
+        ];
+
+        let volumes = ascent_gas_volumes(26.0, &air, &stops, 20.0);
+
+        assert_eq!(volumes.len(), 2);
+        // 2min travel at 16m
+        assert_eq!(volumes[0], (air, 2.0 * 20.0 * 2.6));
+        // stops plus travel on EAN50
+        let ean50_liters = 20.0 * (2.0 * 1.6 + 0.3 * 1.45 + 5.0 * 1.3 + 0.3 * 1.15);
+        assert!((volumes[1].1 - ean50_liters).abs() < 1e-3);
+    }
 }
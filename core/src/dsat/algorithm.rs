@@ -4,9 +4,9 @@ use crate::{
     gas::GasMix,
     profile::DiveProfile,
     tissue::CompartmentSnapshot,
-    utils::calc_ata,
 };
 
+#[derive(Clone)]
 pub struct DSATAlgorithm {}
 
 pub struct DSATParams {
@@ -20,25 +20,24 @@ impl DecoAlgorithm for DSATAlgorithm {
         DecoAlgorithmVariant::Dsat
     }
 
-    fn compute_deco_stops(&self, dive_profile: DiveProfile) -> Vec<DecoStop> {
+    fn compute_deco_stops(&self, _dive_profile: DiveProfile) -> Vec<DecoStop> {
         // Implement DSAT algorithm calculations
         // ...
         vec![]
     }
 
-    fn compute_ndl(&self, dive_profile: DiveProfile) -> u32 {
+    fn compute_ndl(&self, _dive_profile: DiveProfile) -> u32 {
         42
     }
 
     fn snapshot(&self) -> Vec<CompartmentSnapshot> {
-        let snaps = vec![];
-
-        snaps
+        vec![]
     }
 
-    fn run(&mut self, mix: GasMix, ata: f32, time: f32) {}
+    fn run(&mut self, _mix: GasMix, _ata: f32, _time: f32) {}
 }
 
+#[allow(dead_code)]
 impl DSATAlgorithm {
     const COMPARTMENT_HALF_TIMES: [f64; 16] = [
         5.0, 8.0, 12.5, 18.5, 27.0, 38.3, 54.3, 77.0, 109.0, 146.0, 187.0, 239.0, 305.0, 390.0,
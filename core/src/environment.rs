@@ -0,0 +1,172 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::DivesyncError;
+use crate::profile::{DiveProfile, Segment};
+
+/// Density of fresh water relative to sea water
+pub const FRESH_WATER_DENSITY: f32 = 1000.0 / 1025.0;
+
+/// Highest altitude in meters accepted for a dive site
+pub const MAX_ALTITUDE: f32 = 6000.0;
+
+/// Pressure of one ata in bar
+pub const BAR_PER_ATA: f32 = 1.01325;
+
+/// Salinity in grams per liter below which water is treated as fresh
+pub const FRESH_WATER_SALINITY: f32 = 1020.0;
+
+/// Constants of the barometric formula, `p = (1 - a * h)^b`
+const BAROMETRIC_LAPSE: f32 = 2.25577e-5;
+const BAROMETRIC_EXPONENT: f32 = 5.25588;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Water {
+    #[default]
+    Salt,
+    Fresh,
+}
+
+impl Water {
+    /// Water type of a salinity in grams per liter
+    pub fn from_salinity(salinity: f32) -> Self {
+        match salinity < FRESH_WATER_SALINITY {
+            true => Water::Fresh,
+            false => Water::Salt,
+        }
+    }
+
+    /// Density relative to sea water
+    pub fn density(&self) -> f32 {
+        match self {
+            Water::Salt => 1.0,
+            Water::Fresh => FRESH_WATER_DENSITY,
+        }
+    }
+}
+
+impl fmt::Display for Water {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Water::Salt => write!(f, "salt water"),
+            Water::Fresh => write!(f, "fresh water"),
+        }
+    }
+}
+
+/// Dive site conditions, algorithms assume sea level and salt water so
+/// depths are converted to equivalent sea level depths (Cross correction)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Environment {
+    /// Altitude of the surface in meters
+    pub altitude: f32,
+    pub water: Water,
+}
+
+impl Environment {
+    pub fn new(altitude: f32, water: Water) -> Self {
+        Self { altitude, water }
+    }
+
+    /// Altitude with a surface pressure of `pressure` ata, pressures above
+    /// sea level give 0m
+    pub fn from_surface_pressure(pressure: f32, water: Water) -> Self {
+        let altitude = (1.0 - pressure.powf(1.0 / BAROMETRIC_EXPONENT)) / BAROMETRIC_LAPSE;
+        Self::new(altitude.clamp(0.0, MAX_ALTITUDE), water)
+    }
+
+    /// Pressure at the surface in ata, barometric formula
+    pub fn surface_pressure(&self) -> f32 {
+        (1.0 - BAROMETRIC_LAPSE * self.altitude).powf(BAROMETRIC_EXPONENT)
+    }
+
+    /// Ambient pressure in ata at `depth` meters
+    pub fn ambient_pressure(&self, depth: f32) -> f32 {
+        self.surface_pressure() + depth * self.water.density() / 10.0
+    }
+
+    /// Sea level depth with the same ratio of ambient to surface pressure
+    pub fn equivalent_depth(&self, depth: f32) -> f32 {
+        depth * self.water.density() / self.surface_pressure()
+    }
+
+    /// Inverse of `equivalent_depth`
+    pub fn actual_depth(&self, equivalent_depth: f32) -> f32 {
+        equivalent_depth * self.surface_pressure() / self.water.density()
+    }
+
+    /// Copy of `profile` with equivalent depths, travel rates are scaled
+    /// to keep the time of each segment
+    pub fn equivalent_profile(&self, profile: &DiveProfile) -> DiveProfile {
+        let mut equivalent = profile.clone();
+
+        for segment in &mut equivalent.segments {
+            match segment {
+                Segment::Travel { depth, rate, .. } => {
+                    *depth = self.equivalent_depth(*depth);
+                    *rate = self.equivalent_depth(*rate);
+                }
+                Segment::Hold { depth, .. } => *depth = self.equivalent_depth(*depth),
+                Segment::GasSwitch { .. } | Segment::SurfaceInterval { .. } => {}
+            }
+        }
+
+        equivalent
+    }
+
+    pub fn validate(&self) -> Result<(), DivesyncError> {
+        match self.altitude.is_finite() && (0.0..=MAX_ALTITUDE).contains(&self.altitude) {
+            true => Ok(()),
+            false => Err(DivesyncError::InvalidPlan(format!(
+                "altitude {}m out of range, expected 0 to {MAX_ALTITUDE}m",
+                self.altitude
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "altitude {:.0}m, {}", self.altitude, self.water)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::round_f32;
+
+    #[test]
+    fn test_sea_level_salt_water() {
+        let env = Environment::default();
+
+        assert_eq!(env.surface_pressure(), 1.0);
+        assert_eq!(env.ambient_pressure(20.0), 3.0);
+        assert_eq!(env.equivalent_depth(30.0), 30.0);
+    }
+
+    #[test]
+    fn test_altitude_fresh_water() {
+        let env = Environment::new(2000.0, Water::Fresh);
+
+        assert!((env.surface_pressure() - 0.785).abs() < 1e-3);
+        assert!(env.equivalent_depth(30.0) > 30.0);
+        assert_eq!(
+            round_f32(env.actual_depth(env.equivalent_depth(30.0)), 3),
+            30.0
+        );
+        assert!(Environment::new(-10.0, Water::Salt).validate().is_err());
+
+        let from_pressure =
+            Environment::from_surface_pressure(env.surface_pressure(), Water::Fresh);
+        assert!((from_pressure.altitude - 2000.0).abs() < 1.0);
+        assert_eq!(
+            Environment::from_surface_pressure(1.02, Water::Salt).altitude,
+            0.0
+        );
+        assert_eq!(Water::from_salinity(1000.0), Water::Fresh);
+    }
+}
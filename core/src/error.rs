@@ -0,0 +1,122 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::gas::GasError;
+
+#[derive(Debug)]
+pub enum DivesyncError {
+    /// No algorithm matches the given name
+    UnknownAlgorithm(String),
+    /// Algorithm name or alias is already registered
+    DuplicateAlgorithm(String),
+    /// Algorithm does not accept parameter, (algorithm, param)
+    UnknownParam(String, String),
+    /// Algorithm is a registered placeholder whose results are not
+    /// computed by the model
+    NotImplemented(String),
+    /// Parameter value is outside of the range accepted by the algorithm
+    ParamOutOfRange(String, f32),
+    /// Algorithm has already been initialized
+    AlreadyInitialized(String),
+    /// Interval period of an algorithm run must be above zero
+    InvalidInterval(u32),
+    /// Coefficient table of a ZHL model failed validation
+    InvalidCoefficients(String),
+    /// USN table file failed validation
+    InvalidTables(String),
+    /// Dive or surface interval is not covered by the USN tables
+    OutsideTables(String),
+    /// Input file could not be parsed
+    Parse(String),
+    /// Depth range of a table is empty or its step is not above zero,
+    /// (min, max, step)
+    InvalidDepthRange(f32, f32, f32),
+    /// Dive profile has a negative depth, a travel rate not above zero or
+    /// steps out of order
+    InvalidProfile(String),
+    /// Dive plan has invalid environment, cylinders or gas consumption
+    InvalidPlan(String),
+    /// Invalid gas mixture
+    Gas(GasError),
+    /// Home directory of the current user could not be found
+    HomeDirNotFound,
+    /// System clock is set before the UNIX epoch
+    InvalidSystemTime,
+    Io(io::Error),
+    Csv(csv::Error),
+}
+
+impl fmt::Display for DivesyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DivesyncError::UnknownAlgorithm(name) => write!(f, "unknown algorithm: {name}"),
+            DivesyncError::DuplicateAlgorithm(name) => {
+                write!(f, "algorithm already registered: {name}")
+            }
+            DivesyncError::UnknownParam(algo, param) => {
+                write!(f, "unknown parameter for {algo}: {param}")
+            }
+            DivesyncError::NotImplemented(name) => {
+                write!(f, "algorithm {name} is not implemented")
+            }
+            DivesyncError::ParamOutOfRange(param, value) => {
+                write!(f, "parameter {param} out of range: {value}")
+            }
+            DivesyncError::AlreadyInitialized(name) => {
+                write!(f, "algorithm {name} has already been initialized")
+            }
+            DivesyncError::InvalidInterval(interval) => {
+                write!(f, "invalid interval period: {interval}, must be above zero")
+            }
+            DivesyncError::InvalidCoefficients(msg) => {
+                write!(f, "invalid coefficient table: {msg}")
+            }
+            DivesyncError::InvalidTables(msg) => write!(f, "invalid USN tables: {msg}"),
+            DivesyncError::OutsideTables(msg) => write!(f, "outside the USN tables: {msg}"),
+            DivesyncError::Parse(msg) => write!(f, "parse error: {msg}"),
+            DivesyncError::InvalidDepthRange(min, max, step) => {
+                write!(
+                    f,
+                    "invalid depth range: {min}m to {max}m in steps of {step}m"
+                )
+            }
+            DivesyncError::InvalidProfile(msg) => write!(f, "invalid dive profile: {msg}"),
+            DivesyncError::InvalidPlan(msg) => write!(f, "invalid dive plan: {msg}"),
+            DivesyncError::Gas(err) => write!(f, "{err}"),
+            DivesyncError::HomeDirNotFound => write!(f, "unable to find home directory"),
+            DivesyncError::InvalidSystemTime => write!(f, "system time is before UNIX epoch"),
+            DivesyncError::Io(err) => write!(f, "{err}"),
+            DivesyncError::Csv(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for DivesyncError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DivesyncError::Gas(err) => Some(err),
+            DivesyncError::Io(err) => Some(err),
+            DivesyncError::Csv(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<GasError> for DivesyncError {
+    fn from(err: GasError) -> Self {
+        DivesyncError::Gas(err)
+    }
+}
+
+impl From<io::Error> for DivesyncError {
+    fn from(err: io::Error) -> Self {
+        DivesyncError::Io(err)
+    }
+}
+
+impl From<csv::Error> for DivesyncError {
+    fn from(err: csv::Error) -> Self {
+        DivesyncError::Csv(err)
+    }
+}
@@ -0,0 +1,506 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use csv::{ReaderBuilder, StringRecord};
+
+use crate::error::DivesyncError;
+use crate::formats::{parse_duration, parse_value, LoggedDive, Sample};
+use crate::gas::GasMix;
+use crate::plan::Cylinder;
+
+const METERS_PER_FOOT: f32 = 0.3048;
+const BAR_PER_PSI: f32 = 0.0689476;
+
+/// Units of depths, temperatures and pressures in a log
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Units {
+    /// Meters, degrees Celsius and bar
+    #[default]
+    Metric,
+    /// Feet, degrees Fahrenheit and psi
+    Imperial,
+}
+
+impl Units {
+    /// Depth in meters
+    pub fn depth(&self, depth: f32) -> f32 {
+        match self {
+            Units::Metric => depth,
+            Units::Imperial => depth * METERS_PER_FOOT,
+        }
+    }
+
+    /// Temperature in degrees Celsius
+    pub fn temperature(&self, temperature: f32) -> f32 {
+        match self {
+            Units::Metric => temperature,
+            Units::Imperial => (temperature - 32.0) * 5.0 / 9.0,
+        }
+    }
+
+    /// Pressure in bar
+    pub fn pressure(&self, pressure: f32) -> f32 {
+        match self {
+            Units::Metric => pressure,
+            Units::Imperial => pressure * BAR_PER_PSI,
+        }
+    }
+
+    /// Units named by the suffix of a column header, eg. `Depth (ft)`
+    fn from_header(header: &str) -> Option<Self> {
+        let (_, unit) = split_header(header);
+
+        match unit?.to_lowercase().as_str() {
+            "m" | "meters" | "°c" | "c" | "bar" => Some(Units::Metric),
+            "ft" | "feet" | "°f" | "f" | "psi" => Some(Units::Imperial),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Units {
+    type Err = DivesyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "metric" => Ok(Units::Metric),
+            "imperial" => Ok(Units::Imperial),
+            _ => Err(DivesyncError::Parse(format!("unknown units: {s}"))),
+        }
+    }
+}
+
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Units::Metric => write!(f, "metric"),
+            Units::Imperial => write!(f, "imperial"),
+        }
+    }
+}
+
+/// Unit of plain numbers in the time column, `[h:]m:ss` times are always
+/// accepted
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimeUnit {
+    #[default]
+    Seconds,
+    Minutes,
+}
+
+impl TimeUnit {
+    /// Time unit named by the suffix of a column header, eg. `Time (min)`
+    fn from_header(header: &str) -> Option<Self> {
+        let (_, unit) = split_header(header);
+
+        match unit?.to_lowercase().as_str() {
+            "s" | "sec" | "seconds" => Some(TimeUnit::Seconds),
+            "min" | "minutes" => Some(TimeUnit::Minutes),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for TimeUnit {
+    type Err = DivesyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "seconds" | "s" => Ok(TimeUnit::Seconds),
+            "minutes" | "min" => Ok(TimeUnit::Minutes),
+            _ => Err(DivesyncError::Parse(format!("unknown time unit: {s}"))),
+        }
+    }
+}
+
+/// Column layouts of dive computer vendor exports
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvPreset {
+    Shearwater,
+    Garmin,
+    Suunto,
+}
+
+impl CsvPreset {
+    pub fn mapping(&self) -> ColumnMapping {
+        let mut mapping = match self {
+            CsvPreset::Shearwater => {
+                let mut mapping = ColumnMapping::new("Time", "Depth");
+                mapping.set_temperature("Water Temp");
+                mapping.set_ppo2("Average PPO2");
+                mapping.set_tank_pressure("Tank Pressure 1");
+                mapping.set_ndl("Current NDL");
+                mapping.set_ceiling("First Stop Depth");
+                mapping
+            }
+            CsvPreset::Garmin => {
+                let mut mapping = ColumnMapping::new("Elapsed Time", "Depth");
+                mapping.set_temperature("Temperature");
+                mapping.set_ppo2("PO2");
+                mapping.set_tank_pressure("Tank Pressure");
+                mapping.set_ndl("NDL");
+                mapping.set_ceiling("Next Stop Depth");
+                mapping
+            }
+            CsvPreset::Suunto => {
+                let mut mapping = ColumnMapping::new("Time", "Depth");
+                mapping.set_temperature("Temperature");
+                mapping.set_ppo2("PO2");
+                mapping.set_tank_pressure("Cylinder pressure");
+                mapping.set_ndl("NDL");
+                mapping.set_ceiling("Ceiling");
+                mapping
+            }
+        };
+
+        mapping.set_tts("Time To Surface");
+        mapping
+    }
+}
+
+impl FromStr for CsvPreset {
+    type Err = DivesyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "shearwater" => Ok(CsvPreset::Shearwater),
+            "garmin" => Ok(CsvPreset::Garmin),
+            "suunto" => Ok(CsvPreset::Suunto),
+            _ => Err(DivesyncError::Parse(format!("unknown CSV preset: {s}"))),
+        }
+    }
+}
+
+impl fmt::Display for CsvPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvPreset::Shearwater => write!(f, "shearwater"),
+            CsvPreset::Garmin => write!(f, "garmin"),
+            CsvPreset::Suunto => write!(f, "suunto"),
+        }
+    }
+}
+
+/// Columns of a CSV log holding each value, names are matched ignoring
+/// case and a unit suffix in parentheses, which also overrides `units`
+/// for that column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMapping {
+    pub time: String,
+    pub time_unit: TimeUnit,
+    pub depth: String,
+    pub temperature: Option<String>,
+    pub ppo2: Option<String>,
+    pub tank_pressure: Option<String>,
+    pub ndl: Option<String>,
+    pub ceiling: Option<String>,
+    pub tts: Option<String>,
+    pub units: Units,
+    pub delimiter: u8,
+    /// Gas breathed throughout the dive
+    pub gas_mix: GasMix,
+}
+
+impl ColumnMapping {
+    pub fn new(time: &str, depth: &str) -> Self {
+        Self {
+            time: time.to_string(),
+            time_unit: TimeUnit::default(),
+            depth: depth.to_string(),
+            temperature: None,
+            ppo2: None,
+            tank_pressure: None,
+            ndl: None,
+            ceiling: None,
+            tts: None,
+            units: Units::default(),
+            delimiter: b',',
+            gas_mix: GasMix::air(),
+        }
+    }
+
+    pub fn set_time_unit(&mut self, time_unit: TimeUnit) {
+        self.time_unit = time_unit;
+    }
+
+    pub fn set_temperature(&mut self, column: &str) {
+        self.temperature = Some(column.to_string());
+    }
+
+    pub fn set_ppo2(&mut self, column: &str) {
+        self.ppo2 = Some(column.to_string());
+    }
+
+    pub fn set_tank_pressure(&mut self, column: &str) {
+        self.tank_pressure = Some(column.to_string());
+    }
+
+    pub fn set_ndl(&mut self, column: &str) {
+        self.ndl = Some(column.to_string());
+    }
+
+    pub fn set_ceiling(&mut self, column: &str) {
+        self.ceiling = Some(column.to_string());
+    }
+
+    pub fn set_tts(&mut self, column: &str) {
+        self.tts = Some(column.to_string());
+    }
+
+    pub fn set_units(&mut self, units: Units) {
+        self.units = units;
+    }
+
+    pub fn set_delimiter(&mut self, delimiter: u8) {
+        self.delimiter = delimiter;
+    }
+
+    pub fn set_gas_mix(&mut self, gas_mix: GasMix) {
+        self.gas_mix = gas_mix;
+    }
+}
+
+/// Column index and units of a mapped value, units in the header take
+/// precedence
+struct Column {
+    index: usize,
+    units: Units,
+    time_unit: Option<TimeUnit>,
+}
+
+impl Column {
+    fn find(header: &StringRecord, name: &str, units: Units) -> Option<Self> {
+        header
+            .iter()
+            .position(|h| split_header(h).0.eq_ignore_ascii_case(name.trim()))
+            .map(|index| Self {
+                index,
+                units: Units::from_header(&header[index]).unwrap_or(units),
+                time_unit: TimeUnit::from_header(&header[index]),
+            })
+    }
+
+    /// Time in seconds, plain numbers are in `time_unit`
+    fn seconds(
+        &self,
+        record: &StringRecord,
+        time_unit: TimeUnit,
+    ) -> Result<Option<u32>, DivesyncError> {
+        let text = match record.get(self.index).map(str::trim) {
+            None | Some("") => return Ok(None),
+            Some(text) => text,
+        };
+
+        let seconds = match (text.contains(':'), self.time_unit.unwrap_or(time_unit)) {
+            (true, _) => parse_duration(text)?,
+            (false, TimeUnit::Seconds) => parse_value(text)?.round() as u32,
+            (false, TimeUnit::Minutes) => (parse_value(text)? * 60.0).round() as u32,
+        };
+        Ok(Some(seconds))
+    }
+
+    fn value(&self, record: &StringRecord) -> Result<Option<f32>, DivesyncError> {
+        match record.get(self.index).map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(value) => parse_value(value).map(Some),
+        }
+    }
+}
+
+/// Parse a CSV log into a dive with a sample per row, lines before the
+/// header row holding the time column are skipped as are rows without a
+/// time or depth. Missing optional columns are left empty, plain NDL and
+/// TTS values are in minutes.
+pub fn parse(data: &str, mapping: &ColumnMapping) -> Result<LoggedDive, DivesyncError> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(mapping.delimiter)
+        .from_reader(data.as_bytes());
+
+    let mut records = reader.records();
+
+    let header = loop {
+        match records.next() {
+            Some(record) => {
+                let record = record?;
+                if Column::find(&record, &mapping.time, mapping.units).is_some() {
+                    break record;
+                }
+            }
+            None => {
+                return Err(DivesyncError::Parse(format!(
+                    "missing time column: {}",
+                    mapping.time
+                )))
+            }
+        }
+    };
+
+    let time = Column::find(&header, &mapping.time, mapping.units).expect("header has time");
+    let depth = Column::find(&header, &mapping.depth, mapping.units)
+        .ok_or_else(|| DivesyncError::Parse(format!("missing depth column: {}", mapping.depth)))?;
+
+    let optional = |name: &Option<String>| {
+        name.as_ref()
+            .and_then(|n| Column::find(&header, n, mapping.units))
+    };
+    let temperature = optional(&mapping.temperature);
+    let ppo2 = optional(&mapping.ppo2);
+    let tank_pressure = optional(&mapping.tank_pressure);
+    let ndl = optional(&mapping.ndl);
+    let ceiling = optional(&mapping.ceiling);
+    let tts = optional(&mapping.tts);
+
+    let mut dive = LoggedDive::default();
+
+    for record in records {
+        let record = record?;
+
+        let Some(seconds) = time.seconds(&record, mapping.time_unit)? else {
+            continue;
+        };
+        let Some(depth_value) = depth.value(&record)? else {
+            continue;
+        };
+
+        let mut sample = Sample::new(seconds, depth.units.depth(depth_value));
+        if let Some(column) = &temperature {
+            sample.temperature = column.value(&record)?.map(|t| column.units.temperature(t));
+        }
+        if let Some(column) = &ppo2 {
+            sample.ppo2 = column.value(&record)?;
+        }
+        if let Some(column) = &tank_pressure {
+            sample.tank_pressure = column.value(&record)?.map(|p| column.units.pressure(p));
+        }
+        if let Some(column) = &ndl {
+            sample.ndl = column.seconds(&record, TimeUnit::Minutes)?.map(|s| s / 60);
+        }
+        if let Some(column) = &ceiling {
+            sample.ceiling = column.value(&record)?.map(|d| column.units.depth(d));
+        }
+        if let Some(column) = &tts {
+            sample.tts = column.seconds(&record, TimeUnit::Minutes)?.map(|s| s / 60);
+        }
+
+        dive.samples.push(sample);
+    }
+
+    dive.samples.sort_by_key(|s| s.time);
+
+    let start_pressure = dive
+        .samples
+        .iter()
+        .find_map(|s| s.tank_pressure)
+        .unwrap_or_default();
+    dive.cylinders
+        .push(Cylinder::new(mapping.gas_mix.clone(), 0.0, start_pressure));
+
+    Ok(dive)
+}
+
+pub fn from_file<P: AsRef<Path>>(
+    path: P,
+    mapping: &ColumnMapping,
+) -> Result<LoggedDive, DivesyncError> {
+    parse(&fs::read_to_string(path)?, mapping)
+}
+
+/// Column name and the unit in parentheses after it, if any
+fn split_header(header: &str) -> (&str, Option<&str>) {
+    let header = header.trim();
+
+    match header.strip_suffix(')').and_then(|h| h.rsplit_once('(')) {
+        Some((name, unit)) => (name.trim(), Some(unit.trim())),
+        None => (header, None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SHEARWATER: &str = "\
+Dive Number,Start Date,Max Depth
+12,2024-07-04 10:02:00,24.3
+Time (sec),Depth,First Stop Depth,Time To Surface (min),Average PPO2,Water Temp,Tank Pressure 1
+0,0.0,0,0,0.21,27,207
+30,9.8,0,1,0.41,26,205
+120,24.3,0,3,0.72,25,198
+900,24.0,0,5,0.71,24,142
+1080,5.0,0,1,0.31,25,118
+1260,5.0,0,1,0.31,25,110
+1320,0.0,0,0,0.21,26,
+";
+
+    #[test]
+    fn test_parse_preset() {
+        let dive = parse(SHEARWATER, &CsvPreset::Shearwater.mapping()).unwrap();
+
+        assert_eq!(dive.samples.len(), 7);
+        assert_eq!(dive.duration(), 1320);
+        assert_eq!(dive.max_depth(), 24.3);
+        assert_eq!(dive.samples[2].ppo2, Some(0.72));
+        assert_eq!(dive.samples[2].temperature, Some(25.0));
+        assert_eq!(dive.samples[6].tank_pressure, None);
+        assert_eq!(dive.samples[3].tts, Some(5));
+        assert_eq!(dive.samples[3].ceiling, Some(0.0));
+        assert_eq!(dive.cylinders[0].pressure, 207.0);
+
+        let profile = dive.to_profile().unwrap();
+        assert_eq!(profile.duration(), 1320);
+        assert_eq!(profile.max_depth(), 24.3);
+    }
+
+    #[test]
+    fn test_parse_imperial() {
+        let data = "\
+Time;Depth (ft);Temperature (°F);Cylinder pressure (psi)
+0:00;0;80.6;3000
+1:30;66;77;2900
+20:00;66;75.2;1800
+25:00;0;;1500
+";
+        let mut mapping = CsvPreset::Suunto.mapping();
+        mapping.set_delimiter(b';');
+        mapping.set_gas_mix(GasMix::new_nitrox(0.32).unwrap());
+        let dive = parse(data, &mapping).unwrap();
+
+        assert_eq!(dive.duration(), 1500);
+        assert!((dive.max_depth() - 20.117).abs() < 1e-3);
+        assert!((dive.samples[0].temperature.unwrap() - 27.0).abs() < 1e-3);
+        assert!((dive.cylinders[0].pressure - 206.84).abs() < 0.01);
+        assert_eq!(dive.start_mix(), GasMix::new_nitrox(0.32).unwrap());
+
+        // header suffixes take precedence over the mapping units
+        mapping.set_units(Units::Metric);
+        let metric = parse(data, &mapping).unwrap();
+        assert_eq!(metric.max_depth(), dive.max_depth());
+    }
+
+    #[test]
+    fn test_parse_custom_mapping() {
+        let data = "t,d\n0.5,10\n1.5,10\n2,\n3,0\n";
+        let mut mapping = ColumnMapping::new("T", "D");
+        mapping.set_time_unit(TimeUnit::Minutes);
+        mapping.set_units(Units::Imperial);
+        let dive = parse(data, &mapping).unwrap();
+
+        assert_eq!(dive.samples.len(), 3);
+        assert_eq!(dive.samples[0], Sample::new(30, 10.0 * METERS_PER_FOOT));
+        assert_eq!(dive.duration(), 180);
+
+        assert!(matches!(
+            parse(data, &ColumnMapping::new("time", "d")),
+            Err(DivesyncError::Parse(_))
+        ));
+        assert!(matches!(
+            parse(data, &ColumnMapping::new("t", "depth")),
+            Err(DivesyncError::Parse(_))
+        ));
+        assert!(parse("t,d\nx,1\n", &ColumnMapping::new("t", "d")).is_err());
+    }
+}
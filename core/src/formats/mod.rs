@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use quick_xml::events::BytesStart;
+
+use crate::environment::{Environment, Water, BAR_PER_ATA};
+use crate::error::DivesyncError;
+use crate::gas::GasMix;
+use crate::plan::{Cylinder, DivePlan, RuntimeTable};
+use crate::profile::{DiveProfile, Segment};
+use crate::runner::AlgorithmRunResult;
+use crate::utils::round_f32;
+
+pub mod csv_log;
+pub mod preprocess;
+pub mod subsurface;
+pub mod uddf;
+
+/// Depth reading of a dive computer, `time` in seconds from the start of
+/// the dive, `ppo2` and `tank_pressure` in bar. `ndl`, `ceiling` and `tts`
+/// are the computer's own decompression status, times in minutes and a
+/// ceiling of 0 when no stop is required.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Sample {
+    pub time: u32,
+    pub depth: f32,
+    pub temperature: Option<f32>,
+    pub ppo2: Option<f32>,
+    pub tank_pressure: Option<f32>,
+    pub ndl: Option<u32>,
+    pub ceiling: Option<f32>,
+    pub tts: Option<u32>,
+}
+
+impl Sample {
+    pub fn new(time: u32, depth: f32) -> Self {
+        Self {
+            time,
+            depth,
+            ..Default::default()
+        }
+    }
+}
+
+/// Switch to `gas_mix` at `time` seconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasChange {
+    pub time: u32,
+    pub gas_mix: GasMix,
+}
+
+/// Dive read from a logbook, pressures in bar and salinity in grams per
+/// liter as written by dive computers
+#[derive(Debug, Clone, Default)]
+pub struct LoggedDive {
+    pub number: Option<u32>,
+    pub date: Option<String>,
+    pub time: Option<String>,
+    pub cylinders: Vec<Cylinder>,
+    pub samples: Vec<Sample>,
+    pub gas_changes: Vec<GasChange>,
+    pub surface_pressure: Option<f32>,
+    pub salinity: Option<f32>,
+}
+
+impl LoggedDive {
+    /// Dive following the `runtime` table of `plan`, with a sample at the
+    /// end of every row and cylinders added for mixes `plan` has none of
+    pub fn from_runtime(plan: &DivePlan, runtime: &RuntimeTable) -> Self {
+        let mut dive = LoggedDive {
+            number: Some(1),
+            cylinders: plan.cylinders.clone(),
+            samples: vec![Sample::new(0, 0.0)],
+            surface_pressure: Some(plan.environment.surface_pressure() * BAR_PER_ATA),
+            salinity: Some(salinity(plan.environment.water)),
+            ..Default::default()
+        };
+
+        for row in &runtime.rows {
+            if !dive.cylinders.iter().any(|c| c.gas_mix == row.gas_mix) {
+                dive.cylinders
+                    .push(Cylinder::new(row.gas_mix.clone(), 0.0, 0.0));
+            }
+        }
+
+        let mut mix = dive.start_mix();
+        for row in &runtime.rows {
+            if row.gas_mix != mix {
+                mix = row.gas_mix.clone();
+                dive.gas_changes.push(GasChange {
+                    time: row.run_time - row.duration,
+                    gas_mix: mix.clone(),
+                });
+            }
+            dive.samples.push(Sample::new(row.run_time, row.depth));
+        }
+
+        dive
+    }
+
+    /// Dive following the snapshots of an algorithm run, with a sample at
+    /// the end of every step at the depth reached
+    pub fn from_run_result(result: &AlgorithmRunResult) -> Result<Self, DivesyncError> {
+        let mut dive = LoggedDive {
+            number: Some(1),
+            samples: vec![Sample::new(0, 0.0)],
+            ..Default::default()
+        };
+
+        let mut mix: Option<GasMix> = None;
+        let steps = result.snapshots.iter().zip(&result.depths);
+        for (snapshot, depth) in steps.filter_map(|(s, d)| Some((s.first()?, *d))) {
+            let time = (snapshot.elapsed_time * 60.0).round() as u32;
+            let gas_mix = GasMix::new_trimix(
+                round_f32(snapshot.he_percent / 100.0, 3),
+                round_f32(snapshot.o2_percent / 100.0, 3),
+            )?;
+
+            if mix.as_ref() != Some(&gas_mix) {
+                if !dive.cylinders.iter().any(|c| c.gas_mix == gas_mix) {
+                    dive.cylinders
+                        .push(Cylinder::new(gas_mix.clone(), 0.0, 0.0));
+                }
+                if mix.is_some() {
+                    dive.gas_changes.push(GasChange {
+                        time: dive.duration(),
+                        gas_mix: gas_mix.clone(),
+                    });
+                }
+                mix = Some(gas_mix);
+            }
+
+            if time > dive.duration() {
+                dive.samples.push(Sample::new(time, depth.max(0.0)));
+            }
+        }
+
+        Ok(dive)
+    }
+
+    /// Gas breathed at the start of the dive, the first cylinder or air
+    pub fn start_mix(&self) -> GasMix {
+        self.cylinders
+            .first()
+            .map_or(GasMix::air(), |c| c.gas_mix.clone())
+    }
+
+    /// Duration in seconds up to the last sample
+    pub fn duration(&self) -> u32 {
+        self.samples.last().map_or(0, |s| s.time)
+    }
+
+    pub fn max_depth(&self) -> f32 {
+        self.samples.iter().map(|s| s.depth).fold(0.0, f32::max)
+    }
+
+    /// Altitude and water type from the logged surface pressure and salinity
+    pub fn environment(&self) -> Environment {
+        let water = self.salinity.map_or(Water::Salt, Water::from_salinity);
+
+        match self.surface_pressure {
+            Some(pressure) => Environment::from_surface_pressure(pressure / BAR_PER_ATA, water),
+            None => Environment::new(0.0, water),
+        }
+    }
+
+    /// Profile starting at the surface and following the samples in a
+    /// straight line, a gas change applies from the start of the segment
+    /// it falls in. Cylinders after the first are deco mixes.
+    pub fn to_profile(&self) -> Result<DiveProfile, DivesyncError> {
+        let mut profile = DiveProfile::new();
+        let mut mix = self.start_mix();
+
+        for cylinder in self.cylinders.iter().skip(1) {
+            if cylinder.gas_mix != mix && !profile.deco_mixes.contains(&cylinder.gas_mix) {
+                profile.add_deco_mix(cylinder.gas_mix.clone());
+            }
+        }
+        let mut changes = self.gas_changes.iter().peekable();
+
+        let mut time = 0;
+        let mut depth = 0.0;
+
+        for sample in &self.samples {
+            if sample.time <= time {
+                continue;
+            }
+
+            while let Some(change) = changes.next_if(|c| c.time < sample.time) {
+                if change.gas_mix != mix {
+                    mix = change.gas_mix.clone();
+                    profile.add_segment(Segment::GasSwitch {
+                        gas_mix: mix.clone(),
+                        duration: 0,
+                    });
+                }
+            }
+
+            let duration = sample.time - time;
+            let sample_depth = sample.depth.max(0.0);
+
+            let segment = match sample_depth == depth {
+                true => Segment::Hold {
+                    depth,
+                    duration,
+                    gas_mix: mix.clone(),
+                },
+                false => Segment::Travel {
+                    depth: sample_depth,
+                    rate: (sample_depth - depth).abs() * 60.0 / duration as f32,
+                    gas_mix: mix.clone(),
+                },
+            };
+            profile.add_segment(segment);
+
+            time = sample.time;
+            depth = sample_depth;
+        }
+
+        profile.validate()?;
+        Ok(profile)
+    }
+}
+
+impl fmt::Display for LoggedDive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Dive")?;
+        if let Some(number) = self.number {
+            write!(f, " #{number}")?;
+        }
+        for text in [&self.date, &self.time].into_iter().flatten() {
+            write!(f, " {text}")?;
+        }
+        write!(
+            f,
+            ": {}, max {:.1}m, {}, {} samples",
+            fmt_duration(self.duration()),
+            self.max_depth(),
+            self.start_mix(),
+            self.samples.len()
+        )
+    }
+}
+
+/// Attributes of an XML element, errors name the `format` being read
+fn attributes(
+    element: &BytesStart,
+    format: &str,
+) -> Result<HashMap<String, String>, DivesyncError> {
+    let invalid =
+        |err: &dyn std::fmt::Display| DivesyncError::Parse(format!("invalid {format}: {err}"));
+    let mut attrs = HashMap::new();
+
+    for attr in element.attributes() {
+        let attr = attr.map_err(|e| invalid(&e))?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        let value = attr.unescape_value().map_err(|e| invalid(&e))?;
+        attrs.insert(key, value.to_string());
+    }
+
+    Ok(attrs)
+}
+
+/// Salinity in grams per liter written for `water`
+fn salinity(water: Water) -> f32 {
+    match water {
+        Water::Salt => 1030.0,
+        Water::Fresh => 1000.0,
+    }
+}
+
+/// Leading number of a value with a unit, eg. `12.0 l` or `32.0%`
+fn parse_value(s: &str) -> Result<f32, DivesyncError> {
+    let number: String = s
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+'))
+        .collect();
+
+    number
+        .parse()
+        .map_err(|_| DivesyncError::Parse(format!("invalid value: {s}")))
+}
+
+/// Duration as `[h:]m:ss min` or plain seconds, in seconds
+fn parse_duration(s: &str) -> Result<u32, DivesyncError> {
+    let invalid = || DivesyncError::Parse(format!("invalid duration: {s}"));
+    let text = s
+        .trim()
+        .trim_end_matches("min")
+        .trim_end_matches("sec")
+        .trim();
+
+    let mut seconds = 0;
+    for part in text.split(':') {
+        let value: u32 = part.trim().parse().map_err(|_| invalid())?;
+        seconds = seconds * 60 + value;
+    }
+
+    Ok(seconds)
+}
+
+/// Duration in seconds as `m:ss min`
+fn fmt_duration(seconds: u32) -> String {
+    format!("{}:{:02} min", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_value("12.0 l").unwrap(), 12.0);
+        assert_eq!(parse_value("32.5%").unwrap(), 32.5);
+        assert!(parse_value("bar").is_err());
+
+        assert_eq!(parse_duration("45:30 min").unwrap(), 2730);
+        assert_eq!(parse_duration("1:02:03 min").unwrap(), 3723);
+        assert_eq!(parse_duration("90").unwrap(), 90);
+        assert_eq!(fmt_duration(2730), "45:30 min");
+    }
+
+    #[test]
+    fn test_logged_dive_to_profile() {
+        let ean50 = GasMix::new_nitrox(0.5).unwrap();
+        let dive = LoggedDive {
+            samples: vec![
+                Sample::new(60, 20.0),
+                Sample::new(300, 20.0),
+                Sample::new(390, 6.0),
+                Sample::new(390, 6.0),
+                Sample::new(600, 0.0),
+            ],
+            gas_changes: vec![GasChange {
+                time: 330,
+                gas_mix: ean50.clone(),
+            }],
+            ..Default::default()
+        };
+
+        let profile = dive.to_profile().unwrap();
+
+        assert_eq!(profile.segments.len(), 5);
+        assert_eq!(profile.duration(), 600);
+        assert_eq!(profile.max_depth(), 20.0);
+        assert_eq!(
+            profile.segments[2],
+            Segment::GasSwitch {
+                gas_mix: ean50.clone(),
+                duration: 0
+            }
+        );
+        assert_eq!(profile.last_exposure().unwrap().gas_mix, ean50);
+    }
+}
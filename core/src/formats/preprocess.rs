@@ -0,0 +1,521 @@
+use std::str::FromStr;
+
+use crate::error::DivesyncError;
+use crate::formats::{LoggedDive, Sample};
+
+/// Depth in meters above which a dive computer is considered at the surface
+pub const DEFAULT_SURFACE_DEPTH: f32 = 1.0;
+
+/// Shortest time in seconds at the surface that ends a dive
+pub const DEFAULT_SURFACE_INTERVAL: u32 = 300;
+
+/// Default timestep in seconds of resampled logs
+pub const DEFAULT_TIMESTEP: u32 = 10;
+
+/// Filter applied to logged depths, windows are numbers of samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Mean of the samples in a window centered on each sample
+    MovingAverage(usize),
+    /// Median of the samples in a window centered on each sample, removes
+    /// single spikes
+    Median(usize),
+    /// Exponential smoothing, `alpha` between 0 and 1 is the weight of the
+    /// new sample
+    Exponential(f32),
+}
+
+impl Filter {
+    fn validate(&self) -> Result<(), DivesyncError> {
+        match *self {
+            Filter::MovingAverage(0) => {
+                Err(DivesyncError::ParamOutOfRange("window".to_string(), 0.0))
+            }
+            Filter::Median(0) => Err(DivesyncError::ParamOutOfRange("window".to_string(), 0.0)),
+            Filter::Exponential(alpha) if !(alpha > 0.0 && alpha <= 1.0) => {
+                Err(DivesyncError::ParamOutOfRange("alpha".to_string(), alpha))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn apply(&self, depths: &[f32]) -> Vec<f32> {
+        // windows are cut short at the start and end of the log
+        let window = |i: usize, size: usize| {
+            let start = i.saturating_sub(size / 2);
+            let end = (i + size - size / 2).min(depths.len());
+            &depths[start..end]
+        };
+
+        match *self {
+            Filter::MovingAverage(size) => (0..depths.len())
+                .map(|i| {
+                    let values = window(i, size);
+                    values.iter().sum::<f32>() / values.len() as f32
+                })
+                .collect(),
+            Filter::Median(size) => (0..depths.len())
+                .map(|i| {
+                    let mut values = window(i, size).to_vec();
+                    values.sort_by(f32::total_cmp);
+                    let mid = values.len() / 2;
+                    match values.len() % 2 {
+                        0 => (values[mid - 1] + values[mid]) / 2.0,
+                        _ => values[mid],
+                    }
+                })
+                .collect(),
+            Filter::Exponential(alpha) => {
+                let mut smoothed = Vec::with_capacity(depths.len());
+                for &depth in depths {
+                    let previous = smoothed.last().copied().unwrap_or(depth);
+                    smoothed.push(previous + alpha * (depth - previous));
+                }
+                smoothed
+            }
+        }
+    }
+}
+
+/// Parse `median:N`, `average:N` or `exponential:ALPHA`
+impl FromStr for Filter {
+    type Err = DivesyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            DivesyncError::Parse(format!(
+                "invalid filter: {s}, expected median:N, average:N or exponential:ALPHA"
+            ))
+        };
+        let (name, value) = s.split_once(':').ok_or_else(invalid)?;
+        let value = value.trim();
+
+        let filter = match name.trim().to_lowercase().as_str() {
+            "median" => Filter::Median(value.parse().map_err(|_| invalid())?),
+            "average" => Filter::MovingAverage(value.parse().map_err(|_| invalid())?),
+            "exponential" => Filter::Exponential(value.parse().map_err(|_| invalid())?),
+            _ => return Err(invalid()),
+        };
+
+        filter.validate()?;
+        Ok(filter)
+    }
+}
+
+/// Copy of `dive` with a sample every `step` seconds from the first to the
+/// last sample, values are interpolated linearly and the logged
+/// decompression status is carried over from the previous sample
+pub fn resample(dive: &LoggedDive, step: u32) -> Result<LoggedDive, DivesyncError> {
+    if step == 0 {
+        return Err(DivesyncError::InvalidInterval(step));
+    }
+
+    let mut samples = dive.samples.clone();
+    samples.sort_by_key(|s| s.time);
+    samples.dedup_by_key(|s| s.time);
+
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return Ok(dive.clone());
+    };
+
+    let mut times: Vec<u32> = (first.time.div_ceil(step)..=last.time / step)
+        .map(|i| i * step)
+        .collect();
+    if times.first() != Some(&first.time) {
+        times.insert(0, first.time);
+    }
+    if times.last() != Some(&last.time) {
+        times.push(last.time);
+    }
+
+    let mut resampled = Vec::with_capacity(times.len());
+    let mut next = 0;
+    for time in times {
+        while samples[next].time < time {
+            next += 1;
+        }
+
+        let after = &samples[next];
+        let sample = match (after.time == time, next.checked_sub(1)) {
+            (false, Some(previous)) => interpolate(&samples[previous], after, time),
+            _ => after.clone(),
+        };
+        resampled.push(sample);
+    }
+
+    Ok(LoggedDive {
+        samples: resampled,
+        ..dive.clone()
+    })
+}
+
+/// Copy of `dive` with depths smoothed by `filter`
+pub fn smooth(dive: &LoggedDive, filter: Filter) -> Result<LoggedDive, DivesyncError> {
+    filter.validate()?;
+
+    let depths: Vec<f32> = dive.samples.iter().map(|s| s.depth).collect();
+    let mut smoothed = dive.clone();
+    for (sample, depth) in smoothed.samples.iter_mut().zip(filter.apply(&depths)) {
+        sample.depth = depth;
+    }
+
+    Ok(smoothed)
+}
+
+/// Copy of `dive` with depths shallower than `surface_depth` set to 0
+pub fn clamp_surface(dive: &LoggedDive, surface_depth: f32) -> LoggedDive {
+    let mut clamped = dive.clone();
+    for sample in &mut clamped.samples {
+        if sample.depth < surface_depth {
+            sample.depth = 0.0;
+        }
+    }
+
+    clamped
+}
+
+/// Split a continuous log at every stay of at least `min_interval` seconds
+/// at the surface, each dive keeps the surface samples around it and its
+/// times start at 0. Time of day and number of later dives are advanced.
+pub fn split(dive: &LoggedDive, min_interval: u32) -> Vec<LoggedDive> {
+    let samples = &dive.samples;
+
+    // index ranges of the samples below the surface
+    let mut underwater: Vec<(usize, usize)> = vec![];
+    for (i, sample) in samples.iter().enumerate() {
+        if sample.depth <= 0.0 {
+            continue;
+        }
+
+        match underwater.last_mut() {
+            Some((_, end)) if is_continuous(samples, *end, i, min_interval) => *end = i,
+            _ => underwater.push((i, i)),
+        }
+    }
+
+    let mut dives = vec![];
+    for (n, (start, end)) in underwater.into_iter().enumerate() {
+        let start = start.saturating_sub(1);
+        let end = (end + 1).min(samples.len() - 1);
+        let offset = samples[start].time;
+
+        let (time, days) = match dive.time.as_deref() {
+            Some(time) => {
+                let (time, days) = advance_time_of_day(time, offset);
+                (Some(time), days)
+            }
+            None => (None, 0),
+        };
+
+        let mut part = LoggedDive {
+            number: dive.number.map(|number| number + n as u32),
+            date: dive.date.as_deref().map(|date| advance_date(date, days)),
+            time,
+            samples: samples[start..=end]
+                .iter()
+                .map(|s| Sample {
+                    time: s.time - offset,
+                    ..s.clone()
+                })
+                .collect(),
+            gas_changes: dive
+                .gas_changes
+                .iter()
+                .filter(|c| c.time >= offset && c.time <= samples[end].time)
+                .map(|c| {
+                    let mut change = c.clone();
+                    change.time -= offset;
+                    change
+                })
+                .collect(),
+            ..dive.clone()
+        };
+
+        // gas breathed at the start is the last switch before the dive
+        if let Some(change) = dive.gas_changes.iter().rev().find(|c| c.time < offset) {
+            let mut start_change = change.clone();
+            start_change.time = 0;
+            part.gas_changes.insert(0, start_change);
+        }
+
+        dives.push(part);
+    }
+
+    dives
+}
+
+/// Preprocessing of dive computer logs before they are run, surface depths
+/// are clamped before and after smoothing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preprocessor {
+    timestep: u32,
+    filter: Option<Filter>,
+    surface_depth: f32,
+    min_surface_interval: u32,
+}
+
+impl Preprocessor {
+    pub fn new() -> Self {
+        Self {
+            timestep: DEFAULT_TIMESTEP,
+            filter: None,
+            surface_depth: DEFAULT_SURFACE_DEPTH,
+            min_surface_interval: DEFAULT_SURFACE_INTERVAL,
+        }
+    }
+
+    pub fn set_timestep(&mut self, timestep: u32) {
+        self.timestep = timestep;
+    }
+
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = Some(filter);
+    }
+
+    pub fn set_surface_depth(&mut self, surface_depth: f32) {
+        self.surface_depth = surface_depth;
+    }
+
+    pub fn set_min_surface_interval(&mut self, min_surface_interval: u32) {
+        self.min_surface_interval = min_surface_interval;
+    }
+
+    /// Clamp, resample, smooth and split `dive` into separate dives
+    pub fn process(&self, dive: &LoggedDive) -> Result<Vec<LoggedDive>, DivesyncError> {
+        let mut processed = resample(&clamp_surface(dive, self.surface_depth), self.timestep)?;
+
+        if let Some(filter) = self.filter {
+            processed = clamp_surface(&smooth(&processed, filter)?, self.surface_depth);
+        }
+
+        Ok(split(&processed, self.min_surface_interval))
+    }
+}
+
+impl Default for Preprocessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether samples `end` and `next` below the surface belong to the same
+/// dive, ie. the surface was left again within `min_interval`
+fn is_continuous(samples: &[Sample], end: usize, next: usize, min_interval: u32) -> bool {
+    if next == end + 1 {
+        return true;
+    }
+
+    // time between the first and last surface sample in between
+    samples[next - 1].time - samples[end + 1].time < min_interval
+}
+
+fn interpolate(before: &Sample, after: &Sample, time: u32) -> Sample {
+    let fraction = (time - before.time) as f32 / (after.time - before.time) as f32;
+    let lerp = |a: Option<f32>, b: Option<f32>| match (a, b) {
+        (Some(a), Some(b)) => Some(a + (b - a) * fraction),
+        _ => None,
+    };
+
+    Sample {
+        time,
+        depth: before.depth + (after.depth - before.depth) * fraction,
+        temperature: lerp(before.temperature, after.temperature),
+        ppo2: lerp(before.ppo2, after.ppo2),
+        tank_pressure: lerp(before.tank_pressure, after.tank_pressure),
+        ..before.clone()
+    }
+}
+
+/// `hh:mm:ss` time of day `seconds` later and the number of midnights
+/// passed, other text is kept as is
+fn advance_time_of_day(time: &str, seconds: u32) -> (String, u32) {
+    let parts: Option<Vec<u32>> = time.split(':').map(|p| p.trim().parse().ok()).collect();
+
+    match parts.as_deref() {
+        Some([h, m, s]) => {
+            let total = h * 3600 + m * 60 + s + seconds;
+            let of_day = total % 86400;
+            let time = format!(
+                "{:02}:{:02}:{:02}",
+                of_day / 3600,
+                of_day / 60 % 60,
+                of_day % 60
+            );
+
+            (time, total / 86400)
+        }
+        _ => (time.to_string(), 0),
+    }
+}
+
+/// `yyyy-mm-dd` date `days` later, other text is kept as is
+fn advance_date(date: &str, days: u32) -> String {
+    let parts: Option<Vec<u32>> = date.split('-').map(|p| p.trim().parse().ok()).collect();
+
+    let (mut year, mut month, mut day) = match parts.as_deref() {
+        Some(&[y, m, d]) if (1..=12).contains(&m) && d >= 1 => (y, m, d),
+        _ => return date.to_string(),
+    };
+
+    for _ in 0..days {
+        day += 1;
+        if day > days_in_month(year, month) {
+            day = 1;
+            month += 1;
+        }
+        if month > 12 {
+            month = 1;
+            year += 1;
+        }
+    }
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::formats::GasChange;
+    use crate::gas::GasMix;
+    use crate::runner::AlgorithmRunner;
+    use crate::zhl16::{algorithm::ZHL16Algorithm, tissue::ZHL16Variant};
+
+    fn dive(samples: &[(u32, f32)]) -> LoggedDive {
+        LoggedDive {
+            samples: samples.iter().map(|&(t, d)| Sample::new(t, d)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resample() {
+        let mut logged = dive(&[(0, 0.0), (7, 3.5), (13, 6.5), (25, 6.5), (28, 5.0)]);
+        logged.samples[1].temperature = Some(20.0);
+        logged.samples[2].temperature = Some(18.0);
+
+        let resampled = resample(&logged, 5).unwrap();
+        let times: Vec<u32> = resampled.samples.iter().map(|s| s.time).collect();
+
+        assert_eq!(times, vec![0, 5, 10, 15, 20, 25, 28]);
+        assert_eq!(resampled.samples[1].depth, 2.5);
+        assert_eq!(resampled.samples[2].depth, 5.0);
+        assert!((resampled.samples[2].temperature.unwrap() - 19.0).abs() < 1e-4);
+        assert_eq!(resampled.samples[3].temperature, None);
+        assert_eq!(resampled.samples[6].depth, 5.0);
+        assert!(matches!(
+            resample(&logged, 0),
+            Err(DivesyncError::InvalidInterval(0))
+        ));
+    }
+
+    #[test]
+    fn test_smooth_filters() {
+        let logged = dive(&[(0, 10.0), (10, 10.0), (20, 16.0), (30, 10.0), (40, 10.0)]);
+
+        let median = smooth(&logged, Filter::Median(3)).unwrap();
+        assert!(median.samples.iter().all(|s| s.depth == 10.0));
+
+        let average = smooth(&logged, Filter::MovingAverage(3)).unwrap();
+        assert_eq!(average.samples[2].depth, 12.0);
+        assert_eq!(average.samples[0].depth, 10.0);
+
+        let exponential = smooth(&logged, Filter::Exponential(0.5)).unwrap();
+        assert_eq!(exponential.samples[2].depth, 13.0);
+        assert_eq!(exponential.samples[3].depth, 11.5);
+
+        assert!(smooth(&logged, Filter::Median(0)).is_err());
+        assert_eq!("median:3".parse::<Filter>().unwrap(), Filter::Median(3));
+        assert!("average:0".parse::<Filter>().is_err());
+        assert!("gaussian:2".parse::<Filter>().is_err());
+        assert!(smooth(&logged, Filter::Exponential(1.5)).is_err());
+    }
+
+    #[test]
+    fn test_split_at_surface_intervals() {
+        let ean32 = GasMix::new_nitrox(0.32).unwrap();
+        let mut logged = dive(&[
+            (0, 0.0),
+            (60, 12.0),
+            (600, 0.4),
+            (660, 3.0),
+            (700, 0.0),
+            (2000, 0.0),
+            (2060, 8.0),
+            (2400, 0.0),
+        ]);
+        logged.number = Some(7);
+        logged.date = Some("2023-12-31".to_string());
+        logged.time = Some("23:50:00".to_string());
+        logged.gas_changes = vec![GasChange {
+            time: 30,
+            gas_mix: ean32.clone(),
+        }];
+
+        let dives = split(&clamp_surface(&logged, DEFAULT_SURFACE_DEPTH), 300);
+
+        assert_eq!(dives.len(), 2);
+        assert_eq!(dives[0].duration(), 700);
+        assert_eq!(dives[0].gas_changes[0].time, 30);
+        assert_eq!(dives[1].number, Some(8));
+        assert_eq!(dives[0].date.as_deref(), Some("2023-12-31"));
+        assert_eq!(dives[1].date.as_deref(), Some("2024-01-01"));
+        assert_eq!(dives[1].time.as_deref(), Some("00:23:20"));
+        assert_eq!(dives[1].samples[0], Sample::new(0, 0.0));
+        assert_eq!(dives[1].duration(), 400);
+        assert_eq!(dives[1].gas_changes[0].gas_mix, ean32);
+    }
+
+    #[test]
+    fn test_advance_date() {
+        assert_eq!(advance_date("2024-02-28", 1), "2024-02-29");
+        assert_eq!(advance_date("2023-02-28", 1), "2023-03-01");
+        assert_eq!(advance_date("2024-04-30", 0), "2024-04-30");
+        assert_eq!(advance_date("30/04/2024", 1), "30/04/2024");
+    }
+
+    #[test]
+    fn test_preprocessor_feeds_runner() {
+        let logged = dive(&[
+            (0, 0.3),
+            (4, 2.1),
+            (13, 6.2),
+            (21, 9.8),
+            (27, 10.3),
+            (36, 9.6),
+            (120, 10.1),
+            (700, 10.0),
+            (760, 0.6),
+            (3000, 0.2),
+            (3010, 5.0),
+            (3600, 0.1),
+        ]);
+
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.set_filter(Filter::Median(3));
+        let dives = preprocessor.process(&logged).unwrap();
+
+        assert_eq!(dives.len(), 2);
+        assert!(dives[0]
+            .samples
+            .windows(2)
+            .all(|w| w[1].time - w[0].time == DEFAULT_TIMESTEP));
+        assert_eq!(dives[0].samples.last().unwrap().depth, 0.0);
+
+        let profile = dives[0].to_profile().unwrap();
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        let result = runner.run(60, profile).unwrap();
+
+        assert_eq!(result.snapshots.len(), dives[0].samples.len() - 1);
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::error::DivesyncError;
+use crate::formats::{
+    attributes, fmt_duration, parse_duration, parse_value, GasChange, LoggedDive, Sample,
+};
+use crate::gas::GasMix;
+use crate::plan::{Cylinder, DivePlan};
+
+/// Dive computer model Subsurface gives dives made in its planner
+pub const PLANNED_DIVE_MODEL: &str = "planned dive";
+
+/// Event type of a gas change
+const GASCHANGE_EVENT: u32 = 25;
+
+/// Parse all dives of a Subsurface `.ssrf` or XML logbook, dives inside
+/// trips are included and only the first dive computer of a dive is read
+pub fn parse(xml: &str) -> Result<Vec<LoggedDive>, DivesyncError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut dives = vec![];
+    let mut dive: Option<LoggedDive> = None;
+    let mut gas_events: Vec<(u32, GasEvent)> = vec![];
+    let mut computers = 0;
+    let mut in_computer = false;
+    let mut status = DecoStatus::default();
+
+    loop {
+        let event = reader.read_event().map_err(parse_error)?;
+
+        let (element, is_empty) = match &event {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) => {
+                match e.name().as_ref() {
+                    b"divecomputer" => in_computer = false,
+                    b"dive" => {
+                        if let Some(mut finished) = dive.take() {
+                            resolve_gas_changes(&mut finished, &gas_events)?;
+                            dives.push(finished);
+                        }
+                        gas_events.clear();
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let name = element.name();
+        let attrs = attributes(element, "Subsurface XML")?;
+
+        match name.as_ref() {
+            b"dive" if !is_empty => {
+                dive = Some(LoggedDive {
+                    number: attrs.get("number").and_then(|n| n.parse().ok()),
+                    date: attrs.get("date").cloned(),
+                    time: attrs.get("time").cloned(),
+                    ..Default::default()
+                });
+                computers = 0;
+            }
+            b"divecomputer" => {
+                computers += 1;
+                in_computer = computers == 1 && !is_empty;
+                status = DecoStatus::default();
+            }
+            _ => {}
+        }
+
+        let Some(dive) = dive.as_mut() else {
+            continue;
+        };
+
+        match name.as_ref() {
+            b"cylinder" if !in_computer => dive.cylinders.push(parse_cylinder(&attrs)?),
+            b"sample" if in_computer => {
+                status.update(&attrs)?;
+                let (Some(time), Some(depth)) = (attrs.get("time"), attrs.get("depth")) else {
+                    continue;
+                };
+
+                dive.samples.push(Sample {
+                    time: parse_duration(time)?,
+                    depth: parse_value(depth)?,
+                    temperature: attrs.get("temp").map(|t| parse_value(t)).transpose()?,
+                    ppo2: attrs.get("po2").map(|p| parse_value(p)).transpose()?,
+                    tank_pressure: attrs.get("pressure").map(|p| parse_value(p)).transpose()?,
+                    ndl: status.ndl(),
+                    ceiling: status.ceiling(),
+                    tts: status.tts,
+                });
+            }
+            b"surface" if in_computer => {
+                if let Some(pressure) = attrs.get("pressure") {
+                    dive.surface_pressure = Some(parse_value(pressure)?);
+                }
+            }
+            b"water" if in_computer => {
+                if let Some(salinity) = attrs.get("salinity") {
+                    dive.salinity = Some(parse_value(salinity)?);
+                }
+            }
+            b"event" if in_computer && is_gas_change(&attrs) => {
+                let time = parse_duration(attrs.get("time").map_or("0", |t| t.as_str()))?;
+                gas_events.push((time, parse_gas_event(&attrs)?));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(dives)
+}
+
+pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Vec<LoggedDive>, DivesyncError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Subsurface logbook holding `plan` as a planned dive, with a sample at
+/// the end of every row of its runtime table
+pub fn export_plan(plan: &DivePlan) -> Result<String, DivesyncError> {
+    let runtime = plan.runtime()?;
+    let dive = LoggedDive::from_runtime(plan, &runtime);
+
+    let cylinder_index = |mix: &GasMix| dive.cylinders.iter().position(|c| &c.gas_mix == mix);
+
+    let mut xml = String::new();
+    let mut out = |line: String| {
+        xml.push_str(&line);
+        xml.push('\n');
+    };
+
+    out("<divelog program='divesync' version='3'>".to_string());
+    out("<dives>".to_string());
+    out(format!(
+        "<dive number='1' duration='{}'>",
+        fmt_duration(dive.duration())
+    ));
+    out(format!(
+        "  <notes>{}</notes>",
+        escape(format!("Planned with divesync, {}", runtime.algorithm))
+    ));
+
+    for cylinder in &dive.cylinders {
+        let mut line = "  <cylinder".to_string();
+        if cylinder.volume > 0.0 {
+            write!(line, " size='{:.1} l'", cylinder.volume).ok();
+        }
+        if cylinder.pressure > 0.0 {
+            write!(line, " start='{:.1} bar'", cylinder.pressure).ok();
+        }
+        write!(line, " o2='{:.1}%'", cylinder.gas_mix.fo2() * 100.0).ok();
+        if cylinder.gas_mix.fhe() > 0.0 {
+            write!(line, " he='{:.1}%'", cylinder.gas_mix.fhe() * 100.0).ok();
+        }
+        line.push_str(" />");
+        out(line);
+    }
+
+    out(format!("  <divecomputer model='{PLANNED_DIVE_MODEL}'>"));
+    out(format!("  <depth max='{:.1} m' />", dive.max_depth()));
+    if let Some(pressure) = dive.surface_pressure {
+        out(format!("  <surface pressure='{pressure:.3} bar' />"));
+    }
+    if let Some(salinity) = dive.salinity {
+        out(format!("  <water salinity='{salinity:.0} g/l' />"));
+    }
+
+    for change in &dive.gas_changes {
+        let index = cylinder_index(&change.gas_mix).unwrap_or_default();
+        out(format!(
+            "  <event time='{}' type='{GASCHANGE_EVENT}' name='gaschange' cylinder='{index}' value='{}' />",
+            fmt_duration(change.time),
+            gas_event_value(&change.gas_mix)
+        ));
+    }
+
+    for sample in &dive.samples {
+        out(format!(
+            "  <sample time='{}' depth='{:.1} m' />",
+            fmt_duration(sample.time),
+            sample.depth
+        ));
+    }
+
+    out("  </divecomputer>".to_string());
+    out("</dive>".to_string());
+    out("</dives>".to_string());
+    out("</divelog>".to_string());
+
+    Ok(xml)
+}
+
+/// Decompression status of the dive computer, Subsurface only writes
+/// sample attributes when they change so values carry over to later
+/// samples
+#[derive(Default)]
+struct DecoStatus {
+    ndl: Option<u32>,
+    tts: Option<u32>,
+    stop_depth: Option<f32>,
+    in_deco: Option<bool>,
+}
+
+impl DecoStatus {
+    fn update(&mut self, attrs: &HashMap<String, String>) -> Result<(), DivesyncError> {
+        if let Some(ndl) = attrs.get("ndl") {
+            self.ndl = Some(parse_duration(ndl)? / 60);
+        }
+        if let Some(tts) = attrs.get("tts") {
+            self.tts = Some(parse_duration(tts)? / 60);
+        }
+        if let Some(depth) = attrs.get("stopdepth") {
+            self.stop_depth = Some(parse_value(depth)?);
+        }
+        if let Some(in_deco) = attrs.get("in_deco") {
+            self.in_deco = Some(in_deco.trim() == "1");
+        }
+        Ok(())
+    }
+
+    fn ndl(&self) -> Option<u32> {
+        match self.in_deco {
+            Some(true) => Some(0),
+            _ => self.ndl,
+        }
+    }
+
+    /// Stop depth while in deco, 0 once the computer logged a no-stop time
+    fn ceiling(&self) -> Option<f32> {
+        match self.in_deco {
+            Some(true) => self.stop_depth,
+            _ => self.ndl.map(|_| 0.0),
+        }
+    }
+}
+
+/// Gas of a gas change event, by cylinder index or by the legacy value
+/// holding O2 percent in the low and He percent in the high 16 bits
+enum GasEvent {
+    Cylinder(usize),
+    Mix(GasMix),
+}
+
+fn is_gas_change(attrs: &HashMap<String, String>) -> bool {
+    attrs.get("name").is_some_and(|n| n == "gaschange")
+        || attrs
+            .get("type")
+            .is_some_and(|t| t.parse() == Ok(GASCHANGE_EVENT))
+}
+
+fn parse_gas_event(attrs: &HashMap<String, String>) -> Result<GasEvent, DivesyncError> {
+    if let Some(index) = attrs.get("cylinder") {
+        let index = index
+            .parse()
+            .map_err(|_| DivesyncError::Parse(format!("invalid cylinder index: {index}")))?;
+        return Ok(GasEvent::Cylinder(index));
+    }
+
+    let value = attrs.get("value").map_or(Ok(0.0), |v| parse_value(v))? as u32;
+    let o2 = (value & 0xffff) as f32 / 100.0;
+    let he = (value >> 16) as f32 / 100.0;
+
+    Ok(GasEvent::Mix(GasMix::new_trimix(he, o2)?))
+}
+
+fn gas_event_value(mix: &GasMix) -> u32 {
+    let o2 = (mix.fo2() * 100.0).round() as u32;
+    let he = (mix.fhe() * 100.0).round() as u32;
+    o2 | (he << 16)
+}
+
+fn resolve_gas_changes(
+    dive: &mut LoggedDive,
+    events: &[(u32, GasEvent)],
+) -> Result<(), DivesyncError> {
+    for (time, event) in events {
+        let gas_mix = match event {
+            GasEvent::Mix(mix) => mix.clone(),
+            GasEvent::Cylinder(index) => match dive.cylinders.get(*index) {
+                Some(cylinder) => cylinder.gas_mix.clone(),
+                None => {
+                    return Err(DivesyncError::Parse(format!(
+                        "gas change to missing cylinder {index}"
+                    )))
+                }
+            },
+        };
+
+        dive.gas_changes.push(GasChange {
+            time: *time,
+            gas_mix,
+        });
+    }
+
+    dive.gas_changes.sort_by_key(|c| c.time);
+    Ok(())
+}
+
+/// Cylinder without `o2` holds air, `start` falls back to `workpressure`
+fn parse_cylinder(attrs: &HashMap<String, String>) -> Result<Cylinder, DivesyncError> {
+    let value = |key: &str| attrs.get(key).map(|v| parse_value(v)).transpose();
+
+    let o2 = value("o2")?.map_or(0.21, |o2| o2 / 100.0);
+    let he = value("he")?.map_or(0.0, |he| he / 100.0);
+    let pressure = match value("start")? {
+        Some(start) => start,
+        None => value("workpressure")?.unwrap_or_default(),
+    };
+
+    Ok(Cylinder::new(
+        GasMix::new_trimix(he, o2)?,
+        value("size")?.unwrap_or_default(),
+        pressure,
+    ))
+}
+
+fn parse_error<E: std::fmt::Display>(err: E) -> DivesyncError {
+    DivesyncError::Parse(format!("invalid Subsurface XML: {err}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compare::AlgorithmConfig;
+    use crate::environment::Water;
+    use crate::plan::RuntimeAction;
+    use crate::replay::rank;
+
+    const LOGBOOK: &str = include_str!("../../tests/data/subsurface.ssrf");
+
+    #[test]
+    fn test_parse_logbook() {
+        let dives = parse(LOGBOOK).unwrap();
+
+        assert_eq!(dives.len(), 4);
+        assert_eq!(dives[0].number, Some(41));
+        assert_eq!(dives[0].date.as_deref(), Some("2024-03-09"));
+        assert_eq!(dives[0].samples.len(), 10);
+        assert_eq!(dives[0].samples[1].temperature, Some(25.0));
+        assert_eq!(dives[0].max_depth(), 18.2);
+        assert_eq!(dives[0].duration(), 480);
+        assert_eq!(
+            dives[0].cylinders[0],
+            Cylinder::new(GasMix::air(), 12.0, 210.0)
+        );
+        assert_eq!(dives[0].surface_pressure, Some(1.013));
+        assert_eq!(dives[0].environment().water, Water::Salt);
+    }
+
+    #[test]
+    fn test_parse_gas_changes() {
+        let dives = parse(LOGBOOK).unwrap();
+        let dive = &dives[1];
+        let ean50 = GasMix::new_nitrox(0.5).unwrap();
+
+        assert_eq!(dive.start_mix(), GasMix::new_nitrox(0.32).unwrap());
+        assert_eq!(
+            dive.gas_changes,
+            vec![GasChange {
+                time: 370,
+                gas_mix: ean50.clone()
+            }]
+        );
+
+        let profile = dive.to_profile().unwrap();
+        assert_eq!(profile.duration(), 600);
+        assert_eq!(profile.max_depth(), 30.0);
+        assert_eq!(profile.last_exposure().unwrap().gas_mix, ean50);
+    }
+
+    #[test]
+    fn test_parse_altitude_dive() {
+        let dives = parse(LOGBOOK).unwrap();
+        let dive = &dives[2];
+        let env = dive.environment();
+
+        assert!(dive.cylinders.is_empty());
+        assert_eq!(dive.start_mix(), GasMix::air());
+        assert_eq!(env.water, Water::Fresh);
+        assert!(env.altitude > 1400.0 && env.altitude < 1600.0);
+
+        // profile starts at the surface before the first sample
+        let profile = dive.to_profile().unwrap();
+        assert_eq!(profile.duration(), 240);
+        assert_eq!(profile.exposures()[0].start_depth, 0.0);
+    }
+
+    #[test]
+    fn test_parse_deco_status() {
+        let dives = parse(LOGBOOK).unwrap();
+        let samples = &dives[3].samples;
+
+        // no-stop time before the computer goes into deco
+        assert_eq!(samples[1].ndl, Some(6));
+        assert_eq!(samples[1].ceiling, Some(0.0));
+        assert_eq!(samples[1].tts, Some(5));
+
+        // stop depth and TTS carry over to samples without them
+        assert_eq!(samples[2].ndl, Some(0));
+        assert_eq!(samples[2].ceiling, Some(9.0));
+        assert_eq!(samples[4].ceiling, Some(15.0));
+        assert_eq!(samples[5].tts, Some(15));
+        assert_eq!(samples[12].tts, Some(3));
+
+        let surfaced = samples.last().unwrap();
+        assert_eq!(surfaced.ndl, Some(99));
+        assert_eq!(surfaced.ceiling, Some(0.0));
+        assert_eq!(surfaced.tts, Some(0));
+        assert!(dives[0].samples.iter().all(|s| s.ndl.is_none()));
+    }
+
+    #[test]
+    fn test_replay_logged_deco() {
+        let dives = parse(LOGBOOK).unwrap();
+        let configs: Vec<AlgorithmConfig> = ["zhl16-c", "zhl16-c:gf_low=40,gf_high=85"]
+            .iter()
+            .map(|c| c.parse().unwrap())
+            .collect();
+
+        let reports = rank(&dives[3], &configs).unwrap();
+        let computer = &reports[0];
+
+        assert_eq!(computer.algorithm, configs[1].label);
+        assert!(!computer.points.iter().any(|p| p.disagrees()));
+        assert_eq!(computer.ceiling_deviation().unwrap().samples, 13);
+        assert_eq!(computer.tts_deviation().unwrap().max_abs, 1.0);
+        assert!(reports[1].score().unwrap() > computer.score().unwrap());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let bad_time = "<dive><divecomputer><sample time='x' depth='1 m'/></divecomputer></dive>";
+        assert!(matches!(parse(bad_time), Err(DivesyncError::Parse(_))));
+        assert!(parse("<dive><cylinder o2='32%'></dive>").is_err());
+
+        let missing_cylinder = "<dive><divecomputer>\
+            <event time='1:00 min' name='gaschange' cylinder='2' />\
+            </divecomputer></dive>";
+        assert!(matches!(
+            parse(missing_cylinder),
+            Err(DivesyncError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_plan_round_trip() {
+        let plan = DivePlan::from_toml(
+            r#"
+deco_mixes = ["EAN50"]
+
+[environment]
+altitude = 1000.0
+water = "fresh"
+
+[[cylinders]]
+gas_mix = "Air"
+volume = 12.0
+pressure = 232.0
+
+[[segments]]
+type = "travel"
+depth = 30.0
+rate = 20.0
+gas_mix = "Air"
+
+[[segments]]
+type = "hold"
+depth = 30.0
+duration = 1500
+gas_mix = "Air"
+"#,
+        )
+        .unwrap();
+
+        let xml = export_plan(&plan).unwrap();
+        assert!(xml.contains("model='planned dive'"));
+
+        let dives = parse(&xml).unwrap();
+        let dive = &dives[0];
+        let runtime = plan.runtime().unwrap();
+
+        assert_eq!(dives.len(), 1);
+        assert_eq!(dive.samples.len(), runtime.rows.len() + 1);
+        assert_eq!(dive.duration(), runtime.run_time());
+        assert_eq!(dive.cylinders.len(), 2);
+        assert_eq!(dive.cylinders[0], plan.cylinders[0]);
+        assert_eq!(dive.environment().water, Water::Fresh);
+        assert!((dive.environment().altitude - 1000.0).abs() < 10.0);
+
+        let switch = runtime
+            .rows
+            .iter()
+            .find(|r| r.gas_mix != GasMix::air() && r.action == RuntimeAction::Stop)
+            .unwrap();
+        assert_eq!(dive.gas_changes[0].gas_mix, switch.gas_mix);
+        assert_eq!(dive.to_profile().unwrap().duration(), runtime.run_time());
+    }
+}
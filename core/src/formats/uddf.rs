@@ -0,0 +1,736 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::environment::FRESH_WATER_SALINITY;
+use crate::error::DivesyncError;
+use crate::formats::{attributes, GasChange, LoggedDive, Sample};
+use crate::gas::GasMix;
+use crate::plan::{Cylinder, DivePlan};
+use crate::runner::AlgorithmRunResult;
+
+/// UDDF version of written documents
+pub const UDDF_VERSION: &str = "3.2.0";
+
+/// UDDF values are SI units, pressures in Pa, volumes in cubic meters and
+/// temperatures in Kelvin
+const PASCAL_PER_BAR: f32 = 1e5;
+const LITERS_PER_CUBIC_METER: f32 = 1000.0;
+const ZERO_CELSIUS: f32 = 273.15;
+
+/// Site environments of fresh water dives
+const FRESH_WATER_ENVIRONMENTS: [&str; 4] = ["lake-quarry", "river-spring", "pool", "under-ice"];
+
+#[derive(Default)]
+struct Mix {
+    id: String,
+    o2: f32,
+    he: f32,
+}
+
+#[derive(Default)]
+struct Tank {
+    mix_ref: Option<String>,
+    volume: f32,
+    pressure: f32,
+}
+
+#[derive(Default)]
+struct Waypoint {
+    time: Option<u32>,
+    depth: Option<f32>,
+    temperature: Option<f32>,
+    tank_pressure: Option<f32>,
+    ndl: Option<u32>,
+    ceiling: Option<f32>,
+    switch_ref: Option<String>,
+}
+
+/// Dive with references to mixes and sites resolved once the document is read
+#[derive(Default)]
+struct PendingDive {
+    dive: LoggedDive,
+    site_ref: Option<String>,
+    tanks: Vec<Tank>,
+    switches: Vec<(u32, String)>,
+}
+
+/// Elements read so far, `path` holds the names of open elements
+#[derive(Default)]
+struct UddfReader {
+    path: Vec<String>,
+    mixes: HashMap<String, GasMix>,
+    sites: HashMap<String, f32>,
+    dives: Vec<PendingDive>,
+    mix: Option<Mix>,
+    site: Option<String>,
+    dive: Option<PendingDive>,
+    tank: Option<Tank>,
+    waypoint: Option<Waypoint>,
+}
+
+impl UddfReader {
+    fn open(&mut self, element: &BytesStart) -> Result<(), DivesyncError> {
+        let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+        let attrs = attributes(element, "UDDF")?;
+        let reference = attrs.get("ref").cloned();
+
+        match name.as_str() {
+            "mix" => {
+                self.mix = Some(Mix {
+                    id: attrs.get("id").cloned().unwrap_or_default(),
+                    ..Default::default()
+                })
+            }
+            "site" => self.site = attrs.get("id").cloned(),
+            "dive" => self.dive = Some(PendingDive::default()),
+            "tankdata" => self.tank = Some(Tank::default()),
+            "waypoint" => self.waypoint = Some(Waypoint::default()),
+            "link" => match self.path.last().map(|p| p.as_str()) {
+                Some("tankdata") => {
+                    if let Some(tank) = self.tank.as_mut() {
+                        tank.mix_ref = reference;
+                    }
+                }
+                Some("informationbeforedive") => {
+                    if let Some(dive) = self.dive.as_mut() {
+                        dive.site_ref = reference;
+                    }
+                }
+                _ => {}
+            },
+            "switchmix" => {
+                if let Some(waypoint) = self.waypoint.as_mut() {
+                    waypoint.switch_ref = reference;
+                }
+            }
+            "decostop" => {
+                let depth = attrs
+                    .get("decodepth")
+                    .map(|d| parse_number(d))
+                    .transpose()?;
+                if let Some(waypoint) = self.waypoint.as_mut() {
+                    let mandatory = attrs.get("kind").is_none_or(|k| k == "mandatory");
+                    waypoint.ceiling = match mandatory {
+                        true => depth,
+                        false => Some(0.0),
+                    };
+                    if mandatory {
+                        waypoint.ndl = Some(0);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        self.path.push(name);
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) -> Result<(), DivesyncError> {
+        let Some((name, parents)) = self.path.split_last() else {
+            return Ok(());
+        };
+        let parent = parents.last().map_or("", |p| p.as_str());
+
+        match (parent, name.as_str()) {
+            ("mix", "o2") => {
+                let o2 = parse_number(text)?;
+                self.mix.iter_mut().for_each(|m| m.o2 = o2);
+            }
+            ("mix", "he") => {
+                let he = parse_number(text)?;
+                self.mix.iter_mut().for_each(|m| m.he = he);
+            }
+            ("site", "environment") => {
+                if let Some(site) = &self.site {
+                    let salinity = match FRESH_WATER_ENVIRONMENTS.contains(&text) {
+                        true => 1000.0,
+                        false => 1030.0,
+                    };
+                    self.sites.insert(site.clone(), salinity);
+                }
+            }
+            ("waypoint", "divetime") => {
+                let time = parse_number(text)?.round() as u32;
+                self.waypoint.iter_mut().for_each(|w| w.time = Some(time));
+            }
+            ("waypoint", "depth") => {
+                let depth = parse_number(text)?;
+                self.waypoint.iter_mut().for_each(|w| w.depth = Some(depth));
+            }
+            ("waypoint", "temperature") => {
+                let temperature = parse_number(text)? - ZERO_CELSIUS;
+                self.waypoint
+                    .iter_mut()
+                    .for_each(|w| w.temperature = Some(temperature));
+            }
+            ("waypoint", "nodecotime") => {
+                let ndl = parse_number(text)? as u32 / 60;
+                for waypoint in self.waypoint.iter_mut() {
+                    waypoint.ndl = Some(ndl);
+                    waypoint.ceiling.get_or_insert(0.0);
+                }
+            }
+            ("waypoint", "tankpressure") => {
+                let pressure = parse_number(text)? / PASCAL_PER_BAR;
+                self.waypoint
+                    .iter_mut()
+                    .for_each(|w| w.tank_pressure = Some(pressure));
+            }
+            ("tankdata", "tankvolume") => {
+                let volume = parse_number(text)? * LITERS_PER_CUBIC_METER;
+                self.tank.iter_mut().for_each(|t| t.volume = volume);
+            }
+            ("tankdata", "tankpressurebegin") => {
+                let pressure = parse_number(text)? / PASCAL_PER_BAR;
+                self.tank.iter_mut().for_each(|t| t.pressure = pressure);
+            }
+            ("informationbeforedive", "divenumber") => {
+                if let Some(dive) = self.dive.as_mut() {
+                    dive.dive.number = text.parse().ok();
+                }
+            }
+            ("informationbeforedive", "datetime") => {
+                if let Some(dive) = self.dive.as_mut() {
+                    let (date, time) = text.split_once('T').unwrap_or((text, ""));
+                    dive.dive.date = Some(date.to_string());
+                    dive.dive.time = (!time.is_empty()).then(|| time.to_string());
+                }
+            }
+            ("informationbeforedive", "surfacepressure") => {
+                let pressure = parse_number(text)? / PASCAL_PER_BAR;
+                if let Some(dive) = self.dive.as_mut() {
+                    dive.dive.surface_pressure = Some(pressure);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), DivesyncError> {
+        let Some(name) = self.path.pop() else {
+            return Ok(());
+        };
+
+        match name.as_str() {
+            "mix" => {
+                if let Some(mix) = self.mix.take() {
+                    self.mixes
+                        .insert(mix.id, GasMix::new_trimix(mix.he, mix.o2)?);
+                }
+            }
+            "site" => self.site = None,
+            "tankdata" => {
+                if let (Some(tank), Some(dive)) = (self.tank.take(), self.dive.as_mut()) {
+                    dive.tanks.push(tank);
+                }
+            }
+            "waypoint" => {
+                let (Some(waypoint), Some(dive)) = (self.waypoint.take(), self.dive.as_mut())
+                else {
+                    return Ok(());
+                };
+                let (Some(time), Some(depth)) = (waypoint.time, waypoint.depth) else {
+                    return Ok(());
+                };
+
+                if let Some(switch_ref) = waypoint.switch_ref {
+                    dive.switches.push((time, switch_ref));
+                }
+                dive.dive.samples.push(Sample {
+                    time,
+                    depth,
+                    temperature: waypoint.temperature,
+                    tank_pressure: waypoint.tank_pressure,
+                    ndl: waypoint.ndl,
+                    ceiling: waypoint.ceiling,
+                    ..Default::default()
+                });
+            }
+            "dive" => {
+                if let Some(dive) = self.dive.take() {
+                    self.dives.push(dive);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Dives with cylinders and gas changes looked up in the gas
+    /// definitions and salinity in the dive sites, tanks without a mix
+    /// hold air
+    fn finish(self) -> Result<Vec<LoggedDive>, DivesyncError> {
+        let lookup = |id: &str| {
+            self.mixes
+                .get(id)
+                .cloned()
+                .ok_or_else(|| DivesyncError::Parse(format!("reference to missing mix {id}")))
+        };
+
+        let mut dives = vec![];
+        for pending in self.dives {
+            let mut dive = pending.dive;
+            dive.salinity = pending.site_ref.and_then(|s| self.sites.get(&s).copied());
+
+            for tank in pending.tanks {
+                let gas_mix = match tank.mix_ref {
+                    Some(id) => lookup(&id)?,
+                    None => GasMix::air(),
+                };
+                dive.cylinders
+                    .push(Cylinder::new(gas_mix, tank.volume, tank.pressure));
+            }
+
+            for (time, id) in pending.switches {
+                dive.gas_changes.push(GasChange {
+                    time,
+                    gas_mix: lookup(&id)?,
+                });
+            }
+            dive.gas_changes.sort_by_key(|c| c.time);
+
+            dives.push(dive);
+        }
+
+        Ok(dives)
+    }
+}
+
+/// Parse all dives of the profile data of a UDDF document, mixes are
+/// taken from the gas definitions and water type from the dive site
+pub fn parse(xml: &str) -> Result<Vec<LoggedDive>, DivesyncError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut uddf = UddfReader::default();
+
+    loop {
+        match reader.read_event().map_err(parse_error)? {
+            Event::Start(e) => uddf.open(&e)?,
+            Event::Empty(e) => {
+                uddf.open(&e)?;
+                uddf.close()?;
+            }
+            Event::Text(e) => uddf.text(e.unescape().map_err(parse_error)?.trim())?,
+            Event::End(_) => uddf.close()?,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    uddf.finish()
+}
+
+pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Vec<LoggedDive>, DivesyncError> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// UDDF document holding `plan`, with a waypoint at the end of every row
+/// of its runtime table
+pub fn export_plan(plan: &DivePlan) -> Result<String, DivesyncError> {
+    let runtime = plan.runtime()?;
+    Ok(write(&[LoggedDive::from_runtime(plan, &runtime)]))
+}
+
+/// UDDF document holding the profile of an algorithm run, with a waypoint
+/// at the end of every interval
+pub fn export_run(result: &AlgorithmRunResult) -> Result<String, DivesyncError> {
+    Ok(write(&[LoggedDive::from_run_result(result)?]))
+}
+
+/// UDDF document holding `dives` in a single repetition group
+pub fn write(dives: &[LoggedDive]) -> String {
+    let mut mixes: Vec<GasMix> = vec![];
+    for dive in dives {
+        let start_mix = dive.start_mix();
+        let used = dive
+            .cylinders
+            .iter()
+            .map(|c| &c.gas_mix)
+            .chain(dive.gas_changes.iter().map(|c| &c.gas_mix))
+            .chain(std::iter::once(&start_mix));
+
+        for gas_mix in used {
+            if !mixes.contains(gas_mix) {
+                mixes.push(gas_mix.clone());
+            }
+        }
+    }
+    let mix_id = |gas_mix: &GasMix| {
+        let index = mixes.iter().position(|m| m == gas_mix).unwrap_or_default();
+        format!("mix{}", index + 1)
+    };
+
+    let mut xml = String::new();
+    let mut out = |line: String| {
+        xml.push_str(&line);
+        xml.push('\n');
+    };
+
+    out("<?xml version=\"1.0\" encoding=\"utf-8\"?>".to_string());
+    out(format!("<uddf version=\"{UDDF_VERSION}\">"));
+    out("  <generator>".to_string());
+    out("    <name>divesync</name>".to_string());
+    out(format!(
+        "    <version>{}</version>",
+        env!("CARGO_PKG_VERSION")
+    ));
+    out("  </generator>".to_string());
+
+    if dives.iter().any(|d| d.salinity.is_some()) {
+        out("  <divesite>".to_string());
+        out("    <site id=\"site-salt\"><name>Sea</name><environment>ocean-sea</environment></site>".to_string());
+        out("    <site id=\"site-fresh\"><name>Lake</name><environment>lake-quarry</environment></site>".to_string());
+        out("  </divesite>".to_string());
+    }
+
+    out("  <gasdefinitions>".to_string());
+    for gas_mix in &mixes {
+        out(format!("    <mix id=\"{}\">", mix_id(gas_mix)));
+        out(format!(
+            "      <name>{}</name>",
+            escape(gas_mix.to_string())
+        ));
+        out(format!("      <o2>{:.3}</o2>", gas_mix.fo2()));
+        out(format!("      <n2>{:.3}</n2>", gas_mix.fn2()));
+        out(format!("      <he>{:.3}</he>", gas_mix.fhe()));
+        out("    </mix>".to_string());
+    }
+    out("  </gasdefinitions>".to_string());
+
+    out("  <profiledata>".to_string());
+    out("    <repetitiongroup id=\"group1\">".to_string());
+
+    for (i, dive) in dives.iter().enumerate() {
+        out(format!("      <dive id=\"dive{}\">", i + 1));
+
+        out("        <informationbeforedive>".to_string());
+        if let Some(salinity) = dive.salinity {
+            let site = match salinity < FRESH_WATER_SALINITY {
+                true => "site-fresh",
+                false => "site-salt",
+            };
+            out(format!("          <link ref=\"{site}\" />"));
+        }
+        if let Some(number) = dive.number {
+            out(format!("          <divenumber>{number}</divenumber>"));
+        }
+        if let Some(date) = &dive.date {
+            let time = dive.time.as_deref().unwrap_or("00:00:00");
+            out(format!(
+                "          <datetime>{}T{}</datetime>",
+                escape(date),
+                escape(time)
+            ));
+        }
+        if let Some(pressure) = dive.surface_pressure {
+            out(format!(
+                "          <surfacepressure>{:.0}</surfacepressure>",
+                pressure * PASCAL_PER_BAR
+            ));
+        }
+        out("        </informationbeforedive>".to_string());
+
+        out("        <samples>".to_string());
+        let mut changes = dive.gas_changes.iter().peekable();
+        for (j, sample) in dive.samples.iter().enumerate() {
+            // a switch is written at the last waypoint at or before it
+            let next_time = dive.samples.get(j + 1).map(|s| s.time);
+            let mut switch = (j == 0).then(|| dive.start_mix());
+            while let Some(change) = changes.next_if(|c| next_time.is_none_or(|next| c.time < next))
+            {
+                switch = Some(change.gas_mix.clone());
+            }
+
+            let mut line = "          <waypoint>".to_string();
+            write!(line, "<depth>{:.2}</depth>", sample.depth).ok();
+            write!(line, "<divetime>{}</divetime>", sample.time).ok();
+            if let Some(gas_mix) = switch {
+                write!(line, "<switchmix ref=\"{}\" />", mix_id(&gas_mix)).ok();
+            }
+            if let Some(pressure) = sample.tank_pressure {
+                write!(
+                    line,
+                    "<tankpressure>{:.0}</tankpressure>",
+                    pressure * PASCAL_PER_BAR
+                )
+                .ok();
+            }
+            if let Some(temperature) = sample.temperature {
+                write!(
+                    line,
+                    "<temperature>{:.2}</temperature>",
+                    temperature + ZERO_CELSIUS
+                )
+                .ok();
+            }
+            line.push_str("</waypoint>");
+            out(line);
+        }
+        out("        </samples>".to_string());
+
+        for (j, cylinder) in dive.cylinders.iter().enumerate() {
+            out(format!(
+                "        <tankdata id=\"dive{}-tank{}\">",
+                i + 1,
+                j + 1
+            ));
+            out(format!(
+                "          <link ref=\"{}\" />",
+                mix_id(&cylinder.gas_mix)
+            ));
+            if cylinder.volume > 0.0 {
+                out(format!(
+                    "          <tankvolume>{:.4}</tankvolume>",
+                    cylinder.volume / LITERS_PER_CUBIC_METER
+                ));
+            }
+            if cylinder.pressure > 0.0 {
+                out(format!(
+                    "          <tankpressurebegin>{:.0}</tankpressurebegin>",
+                    cylinder.pressure * PASCAL_PER_BAR
+                ));
+            }
+            out("        </tankdata>".to_string());
+        }
+
+        out("        <informationafterdive>".to_string());
+        out(format!(
+            "          <greatestdepth>{:.2}</greatestdepth>",
+            dive.max_depth()
+        ));
+        out(format!(
+            "          <diveduration>{}</diveduration>",
+            dive.duration()
+        ));
+        out("        </informationafterdive>".to_string());
+
+        out("      </dive>".to_string());
+    }
+
+    out("    </repetitiongroup>".to_string());
+    out("  </profiledata>".to_string());
+    out("</uddf>".to_string());
+
+    xml
+}
+
+fn parse_number(s: &str) -> Result<f32, DivesyncError> {
+    s.parse()
+        .map_err(|_| DivesyncError::Parse(format!("invalid number: {s}")))
+}
+
+fn parse_error<E: std::fmt::Display>(err: E) -> DivesyncError {
+    DivesyncError::Parse(format!("invalid UDDF: {err}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compare::AlgorithmConfig;
+    use crate::environment::Water;
+    use crate::profile::{DiveProfile, DiveProfileBuilder};
+    use crate::replay::rank;
+    use crate::runner::AlgorithmRunner;
+    use crate::zhl16::{algorithm::ZHL16Algorithm, tissue::ZHL16Variant};
+
+    const DOCUMENT: &str = include_str!("../../tests/data/sample.uddf");
+
+    #[test]
+    fn test_parse_document() {
+        let dives = parse(DOCUMENT).unwrap();
+        let dive = &dives[0];
+        let ean32 = GasMix::new_nitrox(0.32).unwrap();
+        let ean50 = GasMix::new_nitrox(0.5).unwrap();
+
+        assert_eq!(dives.len(), 3);
+        assert_eq!(dive.number, Some(58));
+        assert_eq!(dive.date.as_deref(), Some("2024-05-18"));
+        assert_eq!(dive.time.as_deref(), Some("09:12:00"));
+        assert_eq!(dive.samples.len(), 9);
+        assert_eq!(dive.duration(), 1740);
+        assert!((dive.samples[1].temperature.unwrap() - 25.0).abs() < 1e-3);
+        assert_eq!(dive.cylinders[0], Cylinder::new(ean32.clone(), 12.0, 210.0));
+        assert_eq!(dive.environment().water, Water::Salt);
+        assert_eq!(
+            dive.gas_changes,
+            vec![
+                GasChange {
+                    time: 0,
+                    gas_mix: ean32
+                },
+                GasChange {
+                    time: 1200,
+                    gas_mix: ean50.clone()
+                }
+            ]
+        );
+
+        let profile = dive.to_profile().unwrap();
+        assert_eq!(profile.duration(), 1740);
+        assert_eq!(profile.max_depth(), 30.0);
+        assert_eq!(profile.last_exposure().unwrap().gas_mix, ean50);
+    }
+
+    #[test]
+    fn test_parse_lake_dive() {
+        let dives = parse(DOCUMENT).unwrap();
+        let dive = &dives[1];
+        let env = dive.environment();
+
+        assert!(dive.cylinders.is_empty());
+        assert_eq!(dive.start_mix(), GasMix::air());
+        assert_eq!(env.water, Water::Fresh);
+        assert!(env.altitude > 800.0 && env.altitude < 1000.0);
+    }
+
+    #[test]
+    fn test_parse_deco_status() {
+        let dives = parse(DOCUMENT).unwrap();
+        let samples = &dives[2].samples;
+
+        assert_eq!(samples[0].ndl, None);
+        assert_eq!(samples[1].ndl, Some(6));
+        assert_eq!(samples[1].ceiling, Some(0.0));
+        assert_eq!(samples[2].ndl, Some(0));
+        assert_eq!(samples[2].ceiling, Some(9.0));
+        assert_eq!(samples[3].ceiling, Some(15.0));
+        assert_eq!(samples[11].ceiling, Some(3.0));
+        assert_eq!(samples[13].ndl, Some(99));
+        assert_eq!(samples[13].ceiling, Some(0.0));
+        assert!(samples.iter().all(|s| s.tts.is_none()));
+    }
+
+    #[test]
+    fn test_replay_logged_deco() {
+        let dives = parse(DOCUMENT).unwrap();
+        let configs: Vec<AlgorithmConfig> = ["zhl16-c", "zhl16-c:gf_low=40,gf_high=85"]
+            .iter()
+            .map(|c| c.parse().unwrap())
+            .collect();
+
+        let reports = rank(&dives[2], &configs).unwrap();
+        let computer = &reports[0];
+
+        assert_eq!(computer.algorithm, configs[1].label);
+        assert!(!computer.points.iter().any(|p| p.disagrees()));
+        assert_eq!(computer.ndl_deviation().unwrap().samples, 13);
+        assert!(computer.tts_deviation().is_none());
+        assert!(reports[1].score().unwrap() > computer.score().unwrap());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let bad_depth = "<uddf><profiledata><dive><samples>\
+            <waypoint><depth>deep</depth><divetime>10</divetime></waypoint>\
+            </samples></dive></profiledata></uddf>";
+        assert!(matches!(parse(bad_depth), Err(DivesyncError::Parse(_))));
+
+        let missing_mix = "<uddf><profiledata><dive><samples>\
+            <waypoint><depth>3</depth><divetime>10</divetime><switchmix ref='tx' /></waypoint>\
+            </samples></dive></profiledata></uddf>";
+        assert!(matches!(parse(missing_mix), Err(DivesyncError::Parse(_))));
+    }
+
+    #[test]
+    fn test_export_plan_round_trip() {
+        let plan = DivePlan::from_toml(
+            r#"
+deco_mixes = ["EAN50"]
+
+[environment]
+water = "fresh"
+
+[[cylinders]]
+gas_mix = "EAN50"
+volume = 7.0
+pressure = 200.0
+
+[[cylinders]]
+gas_mix = "Air"
+volume = 12.0
+pressure = 232.0
+
+[[segments]]
+type = "travel"
+depth = 30.0
+rate = 20.0
+gas_mix = "Air"
+
+[[segments]]
+type = "hold"
+depth = 30.0
+duration = 1500
+gas_mix = "Air"
+"#,
+        )
+        .unwrap();
+
+        let xml = export_plan(&plan).unwrap();
+        let dives = parse(&xml).unwrap();
+        let dive = &dives[0];
+        let runtime = plan.runtime().unwrap();
+
+        assert_eq!(dive.cylinders, plan.cylinders);
+        assert_eq!(dive.samples.len(), runtime.rows.len() + 1);
+        assert_eq!(dive.environment().water, Water::Fresh);
+        assert_eq!(dive.environment().altitude, 0.0);
+
+        // air is breathed first although the deco cylinder is listed first
+        let profile = dive.to_profile().unwrap();
+        assert_eq!(profile.exposures()[0].gas_mix, GasMix::air());
+        assert_eq!(
+            profile.last_exposure().unwrap().gas_mix,
+            GasMix::new_nitrox(0.5).unwrap()
+        );
+        assert_eq!(profile.duration(), runtime.run_time());
+    }
+
+    #[test]
+    fn test_export_run() {
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        let mut profile = DiveProfile::new();
+        profile.add_level(20.0, 10, GasMix::new_nitrox(0.32).unwrap());
+        let result = runner.run(60, profile).unwrap();
+
+        let dives = parse(&export_run(&result).unwrap()).unwrap();
+        let dive = &dives[0];
+
+        assert_eq!(dive.samples.len(), 11);
+        assert_eq!(dive.duration(), 600);
+        assert_eq!(dive.max_depth(), 20.0);
+        assert_eq!(dive.start_mix(), GasMix::new_nitrox(0.32).unwrap());
+    }
+
+    #[test]
+    fn test_export_run_ramps() {
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        let profile = DiveProfileBuilder::new(GasMix::air())
+            .descend(30.0, 20.0)
+            .hold(10 * 60)
+            .ascend(0.0, 10.0)
+            .build()
+            .unwrap();
+        let result = runner.run(60, profile).unwrap();
+
+        let dives = parse(&export_run(&result).unwrap()).unwrap();
+        let samples = &dives[0].samples;
+
+        // samples at the depth reached, not the depth the tissues were run at
+        assert_eq!(samples[1].time, 60);
+        assert_eq!(samples[1].depth, 20.0);
+        assert_eq!(samples[2].depth, 30.0);
+        assert_eq!(samples.last().unwrap().time, 870);
+        assert_eq!(samples.last().unwrap().depth, 0.0);
+    }
+}
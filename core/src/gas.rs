@@ -1,16 +1,32 @@
-use std::{
-    collections::HashMap,
-    fmt::{self, Display},
-};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
 
-pub const PPN2: f32 = 0.78;
-pub const PPO2: f32 = 0.21;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::utils::calc_depth;
+
+/// Fraction of oxygen in air
+pub const AIR_O2: f32 = 0.21;
+
+/// Fraction of nitrogen in air, argon and trace gases are counted as nitrogen
+pub const AIR_N2: f32 = 0.79;
+
+/// Lowest fraction of oxygen which can be breathed at the surface
+pub const MIN_SURFACE_O2: f32 = 0.16;
+
+/// Maximum partial pressure of oxygen of a bottom gas, used for MOD
+pub const MAX_BOTTOM_PPO2: f32 = 1.4;
+
+/// Tolerance used when checking that fractions of a mix sum to 1
+pub const FRACTION_TOLERANCE: f32 = 1e-4;
 
 #[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub enum GasSymbol {
     Oxygen,
     Helium,
     Nitrogen,
+    Argon,
 }
 
 impl Display for GasSymbol {
@@ -19,98 +35,178 @@ impl Display for GasSymbol {
             GasSymbol::Oxygen => write!(f, "O2"),
             GasSymbol::Helium => write!(f, "He"),
             GasSymbol::Nitrogen => write!(f, "N2"),
+            GasSymbol::Argon => write!(f, "Ar"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GasError {
+    /// Fraction of a gas is below zero
+    NegativeFraction(GasSymbol, f32),
+    /// Fractions of all gases sum to more than 1
+    Oversum(f32),
+    /// Fractions of all gases sum to less than 1
+    Undersum(f32),
+    /// Oxygen fraction is too low to be breathed at the surface
+    HypoxicAtSurface(f32),
+    /// Gas name could not be parsed
+    InvalidName(String),
+}
+
+impl Display for GasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GasError::NegativeFraction(symbol, fraction) => {
+                write!(f, "negative {symbol} fraction: {fraction}")
+            }
+            GasError::Oversum(sum) => write!(f, "gas fractions sum to {sum}, more than 1"),
+            GasError::Undersum(sum) => write!(f, "gas fractions sum to {sum}, less than 1"),
+            GasError::HypoxicAtSurface(o2) => write!(
+                f,
+                "oxygen fraction {o2} is hypoxic at the surface, minimum is {MIN_SURFACE_O2}"
+            ),
+            GasError::InvalidName(name) => write!(f, "invalid gas name: {name}"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+impl Error for GasError {}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Gas {
-    pub base_pp: f32,
+    pub fraction: f32,
     pub symbol: GasSymbol,
 }
 
 impl Display for Gas {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Gas: {} (Base Partial Pressure: {})",
-            self.symbol, self.base_pp
-        )
+        write!(f, "Gas: {} (Fraction: {})", self.symbol, self.fraction)
     }
 }
 
 impl Gas {
-    pub fn new(base_pp: f32, symbol: GasSymbol) -> Self {
-        Self { base_pp, symbol }
+    pub fn new(fraction: f32, symbol: GasSymbol) -> Self {
+        Self { fraction, symbol }
     }
 
     pub fn get_pp(&self, ata: f32) -> f32 {
-        ata * self.base_pp
+        ata * self.fraction
     }
 
     pub fn is_some(&self) -> bool {
-        return self.base_pp > 0.0;
+        self.fraction > 0.0
     }
 
     pub fn is_none(&self) -> bool {
-        return self.base_pp == 0.0;
+        self.fraction == 0.0
     }
 }
 
-// pub type GasMix = HashMap<GasSymbol, Gas>;
-
-#[derive(Debug, Clone)]
+/// Breathing gas, fractions of all gases always sum to 1
+#[derive(Debug, Clone, PartialEq)]
 pub struct GasMix {
     oxygen: Gas,
     nitrogen: Gas,
     helium: Gas,
+    argon: Gas,
 }
 
 impl GasMix {
-    pub fn new(oxygen: Gas, nitrogen: Gas, helium: Gas) -> Self {
-        Self {
-            oxygen,
-            nitrogen,
-            helium,
+    /// Create mix from fractions of each gas, fractions must sum to 1
+    pub fn new(oxygen: f32, nitrogen: f32, helium: f32, argon: f32) -> Result<Self, GasError> {
+        let gases = [
+            (oxygen, GasSymbol::Oxygen),
+            (nitrogen, GasSymbol::Nitrogen),
+            (helium, GasSymbol::Helium),
+            (argon, GasSymbol::Argon),
+        ];
+
+        for (fraction, symbol) in &gases {
+            if *fraction < 0.0 {
+                return Err(GasError::NegativeFraction(symbol.clone(), *fraction));
+            }
         }
-    }
 
-    pub fn new_nitrox(oxygen: f32) -> Self {
-        // TODO:
-        // error handling on wrong base values
-        let pp_n2 = if oxygen <= PPO2 {
-            PPN2
-        } else {
-            PPN2 - (oxygen - PPO2)
-        };
-        let nitrogen = Gas::new(pp_n2, GasSymbol::Nitrogen);
-        let oxygen = Gas::new(oxygen, GasSymbol::Oxygen);
-        let helium = Gas::new(0.0, GasSymbol::Helium);
+        let sum = oxygen + nitrogen + helium + argon;
+        if sum > 1.0 + FRACTION_TOLERANCE {
+            return Err(GasError::Oversum(sum));
+        }
+        if sum < 1.0 - FRACTION_TOLERANCE {
+            return Err(GasError::Undersum(sum));
+        }
 
-        Self {
+        if oxygen < MIN_SURFACE_O2 {
+            return Err(GasError::HypoxicAtSurface(oxygen));
+        }
+
+        let [oxygen, nitrogen, helium, argon] = gases.map(|(f, s)| Gas::new(f, s));
+
+        Ok(Self {
             oxygen,
             nitrogen,
             helium,
+            argon,
+        })
+    }
+
+    /// Air, argon and trace gases are counted as nitrogen
+    pub fn air() -> Self {
+        Self {
+            oxygen: Gas::new(AIR_O2, GasSymbol::Oxygen),
+            nitrogen: Gas::new(AIR_N2, GasSymbol::Nitrogen),
+            helium: Gas::new(0.0, GasSymbol::Helium),
+            argon: Gas::new(0.0, GasSymbol::Argon),
         }
     }
 
-    pub fn new_trimix(helium: f32, oxygen: f32) -> Self {
-        // TODO:
-        // error handling on wrong base values
-        let pp_n2 = if oxygen <= PPO2 {
-            PPN2 - helium
+    /// Oxygen and nitrogen mix, remainder of oxygen is nitrogen
+    pub fn new_nitrox(oxygen: f32) -> Result<Self, GasError> {
+        Self::new_trimix(0.0, oxygen)
+    }
+
+    /// Oxygen, helium and nitrogen mix, remainder of oxygen and helium is nitrogen
+    pub fn new_trimix(helium: f32, oxygen: f32) -> Result<Self, GasError> {
+        let sum = oxygen + helium;
+        if sum > 1.0 + FRACTION_TOLERANCE {
+            return Err(GasError::Oversum(sum));
+        }
+
+        // snap rounding errors to zero so eg. 21/79 is pure heliox
+        let nitrogen = (1.0 - sum).max(0.0);
+        let nitrogen = if nitrogen < FRACTION_TOLERANCE {
+            0.0
         } else {
-            PPN2 - (helium + (oxygen - PPO2))
+            nitrogen
         };
-        let nitrogen = Gas::new(pp_n2, GasSymbol::Nitrogen);
-        let oxygen = Gas::new(oxygen, GasSymbol::Oxygen);
-        let helium = Gas::new(helium, GasSymbol::Helium);
 
-        Self {
-            oxygen,
-            nitrogen,
-            helium,
-        }
+        Self::new(oxygen, nitrogen, helium, 0.0)
+    }
+
+    /// Fraction of oxygen
+    pub fn fo2(&self) -> f32 {
+        self.oxygen.fraction
+    }
+
+    /// Fraction of nitrogen
+    pub fn fn2(&self) -> f32 {
+        self.nitrogen.fraction
+    }
+
+    /// Fraction of helium
+    pub fn fhe(&self) -> f32 {
+        self.helium.fraction
+    }
+
+    /// Fraction of argon
+    pub fn far(&self) -> f32 {
+        self.argon.fraction
+    }
+
+    /// Maximum operating depth in meters at which the partial pressure
+    /// of oxygen reaches `max_ppo2`
+    pub fn max_operating_depth(&self, max_ppo2: f32) -> f32 {
+        calc_depth(max_ppo2 / self.fo2())
     }
 
     pub fn pp_o2(&self, ata: f32) -> f32 {
@@ -125,13 +221,17 @@ impl GasMix {
         self.helium.get_pp(ata)
     }
 
+    pub fn pp_ar(&self, ata: f32) -> f32 {
+        self.argon.get_pp(ata)
+    }
+
     pub fn mix_type(&self) -> GasType {
         if self.helium.is_some() && self.nitrogen.is_none() {
-            return GasType::Heliox;
+            GasType::Heliox
         } else if self.helium.is_some() && self.nitrogen.is_some() {
-            return GasType::Trimix;
+            GasType::Trimix
         } else {
-            return GasType::Nitrox;
+            GasType::Nitrox
         }
     }
 }
@@ -153,29 +253,166 @@ impl Display for GasType {
     }
 }
 
+/// Names divers use for mixes, eg. Air, O2, EAN32, TX18/45, HeliOx21/79
+/// Mixes with argon are named by their full composition so the name
+/// parses back to the same mix, eg. `Mix20.95/78.08/0/0.97`
 impl Display for GasMix {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Implement the Display trait for GasMix
-        write!(
-            f,
-            "Gas Mix:\n
-               (Oxygen: {}, Nitrogen: {}, Helium: {})",
-            self.oxygen, self.nitrogen, self.helium
-        )
+        if self.far() >= FRACTION_TOLERANCE {
+            let fractions = [self.fo2(), self.fn2(), self.fhe(), self.far()];
+            let parts: Vec<String> = fractions.iter().map(|f| precise_percent(*f)).collect();
+            return write!(f, "Mix{}", parts.join("/"));
+        }
+
+        let o2 = percent(self.fo2());
+        let he = percent(self.fhe());
+
+        match self.mix_type() {
+            GasType::Nitrox if (self.fo2() - 1.0).abs() < FRACTION_TOLERANCE => write!(f, "O2"),
+            GasType::Nitrox if (self.fo2() - AIR_O2).abs() < FRACTION_TOLERANCE => {
+                write!(f, "Air")
+            }
+            GasType::Nitrox => write!(f, "EAN{o2}"),
+            GasType::Trimix => write!(f, "TX{o2}/{he}"),
+            GasType::Heliox => write!(f, "HeliOx{o2}/{he}"),
+        }
+    }
+}
+
+/// Mixes are stored by name in plan files, eg. `gas_mix = "EAN32"`
+impl Serialize for GasMix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for GasMix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Parse standard gas names, case and whitespace are ignored:
+/// - `Air`, `O2`, `EAN32`, `Nx32` or a plain oxygen percentage `32`
+/// - `TX18/45`, `Trimix 18/45` or plain `18/45`, oxygen first then helium
+/// - `HeliOx21/79`
+impl FromStr for GasMix {
+    type Err = GasError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+
+        if name == "air" {
+            return Ok(Self::air());
+        }
+        if name == "o2" || name == "oxygen" {
+            return Self::new_nitrox(1.0);
+        }
+
+        let invalid = || GasError::InvalidName(s.to_string());
+
+        for prefix in ["ean", "nx"] {
+            if let Some(rest) = name.strip_prefix(prefix) {
+                let o2 = parse_percent(rest).ok_or_else(invalid)?;
+                return Self::new_nitrox(o2);
+            }
+        }
+
+        for prefix in ["trimix", "tx"] {
+            if let Some(rest) = name.strip_prefix(prefix) {
+                let (o2, he) = parse_pair(rest).ok_or_else(invalid)?;
+                return Self::new_trimix(he, o2);
+            }
+        }
+
+        if let Some(rest) = name.strip_prefix("mix") {
+            let fractions: Vec<f32> = rest
+                .split('/')
+                .map(parse_percent)
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?;
+            let [o2, n2, he, ar] = fractions[..] else {
+                return Err(invalid());
+            };
+            return Self::new(o2, n2, he, ar);
+        }
+
+        if let Some(rest) = name.strip_prefix("heliox") {
+            let (o2, he) = parse_pair(rest).ok_or_else(invalid)?;
+            return Self::new(o2, 0.0, he, 0.0);
+        }
+
+        if let Some((o2, he)) = parse_pair(&name) {
+            return Self::new_trimix(he, o2);
+        }
+
+        let o2 = parse_percent(&name).ok_or_else(invalid)?;
+        Self::new_nitrox(o2)
+    }
+}
+
+/// Format fraction as percentage, decimals are only shown when needed
+fn percent(fraction: f32) -> String {
+    let value = (fraction * 1000.0).round() / 10.0;
+
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.1}")
     }
 }
 
+/// Format fraction as percentage with up to two decimals
+fn precise_percent(fraction: f32) -> String {
+    let hundredths = (fraction * 10000.0).round() as u32;
+
+    match (hundredths % 100, hundredths % 10) {
+        (0, _) => format!("{}", hundredths / 100),
+        (_, 0) => format!("{}.{}", hundredths / 100, hundredths % 100 / 10),
+        _ => format!("{}.{:02}", hundredths / 100, hundredths % 100),
+    }
+}
+
+/// Parse percentage into fraction, eg. `32` into 0.32
+fn parse_percent(s: &str) -> Option<f32> {
+    s.parse::<f32>().ok().map(|v| v / 100.0)
+}
+
+/// Parse oxygen and helium percentages separated by `/` or `,`
+fn parse_pair(s: &str) -> Option<(f32, f32)> {
+    let (o2, he) = s.split_once('/').or_else(|| s.split_once(','))?;
+
+    Some((parse_percent(o2)?, parse_percent(he)?))
+}
+
+#[cfg(test)]
 mod test {
     use crate::utils::round_f32;
 
     use super::*;
+    #[test]
+    fn test_max_operating_depth() {
+        let ean32 = GasMix::new_nitrox(0.32).unwrap();
+
+        assert_eq!(
+            round_f32(ean32.max_operating_depth(MAX_BOTTOM_PPO2), 1),
+            33.8
+        );
+        assert_eq!(round_f32(GasMix::air().max_operating_depth(1.4), 1), 56.7);
+    }
+
     #[test]
     fn test_gas_pp() {
-        let gas = Gas::new(PPN2, GasSymbol::Nitrogen);
+        let gas = Gas::new(AIR_N2, GasSymbol::Nitrogen);
 
         let pp = gas.get_pp(2.0);
 
-        assert_eq!(PPN2 * 2.0, pp);
+        assert_eq!(AIR_N2 * 2.0, pp);
     }
     #[test]
     fn test_gas_is_none() {
@@ -185,70 +422,220 @@ mod test {
     }
     #[test]
     fn test_gas_is_some() {
-        let gas = Gas::new(PPN2, GasSymbol::Nitrogen);
+        let gas = Gas::new(AIR_N2, GasSymbol::Nitrogen);
 
         assert!(gas.is_some());
     }
 
+    #[test]
+    fn test_mix_air() {
+        let mix = GasMix::air();
+
+        assert_eq!(AIR_O2, mix.fo2());
+        assert_eq!(AIR_N2, mix.fn2());
+        assert_eq!(1.0, mix.fo2() + mix.fn2() + mix.fhe() + mix.far());
+    }
+
     #[test]
     fn test_mix_new_nitrox() {
-        let mix = GasMix::new_nitrox(0.21);
+        let mix = GasMix::new_nitrox(0.21).unwrap();
 
         let pp_o2 = mix.pp_o2(1.0);
         let pp_n2 = mix.pp_n2(1.0);
 
-        assert_eq!(PPO2, pp_o2);
-        assert_eq!(PPN2, pp_n2);
+        assert_eq!(AIR_O2, pp_o2);
+        assert_eq!(AIR_N2, pp_n2);
+    }
+
+    #[test]
+    fn test_mix_new_nitrox_ean32() {
+        let mix = GasMix::new_nitrox(0.32).unwrap();
+
+        assert_eq!(0.32, mix.fo2());
+        assert_eq!(0.68, round_f32(mix.fn2(), 3));
     }
 
     #[test]
     fn test_mix_new_trimix() {
-        let mix = GasMix::new_trimix(0.30, 0.16);
+        let mix = GasMix::new_trimix(0.30, 0.16).unwrap();
 
         let pp_o2 = mix.pp_o2(1.0);
         let pp_n2 = mix.pp_n2(1.0);
         let pp_he = mix.pp_he(1.0);
 
         assert_eq!(0.16, pp_o2);
-        assert_eq!(0.48, round_f32(pp_n2, 3));
+        assert_eq!(0.54, round_f32(pp_n2, 3));
         assert_eq!(0.30, pp_he);
     }
 
     #[test]
     fn test_mix_new_trimix_2() {
-        let mix = GasMix::new_trimix(0.50, 0.12);
+        let mix = GasMix::new_trimix(0.45, 0.18).unwrap();
 
         let pp_o2 = mix.pp_o2(1.0);
         let pp_n2 = mix.pp_n2(1.0);
         let pp_he = mix.pp_he(1.0);
 
-        assert_eq!(0.12, pp_o2);
-        assert_eq!(0.28, round_f32(pp_n2, 3));
-        assert_eq!(0.50, pp_he);
+        assert_eq!(0.18, pp_o2);
+        assert_eq!(0.37, round_f32(pp_n2, 3));
+        assert_eq!(0.45, pp_he);
     }
 
     #[test]
     fn test_mix_new_trimix_3() {
-        let mix = GasMix::new_trimix(0.10, 0.30);
+        let mix = GasMix::new_trimix(0.10, 0.30).unwrap();
 
         let pp_o2 = mix.pp_o2(1.0);
         let pp_n2 = mix.pp_n2(1.0);
         let pp_he = mix.pp_he(1.0);
 
         assert_eq!(0.30, pp_o2);
-        assert_eq!(0.59, round_f32(pp_n2, 3));
+        assert_eq!(0.60, round_f32(pp_n2, 3));
         assert_eq!(0.10, pp_he);
     }
 
+    #[test]
+    fn test_mix_new_with_argon() {
+        let mix = GasMix::new(0.2095, 0.7808, 0.0, 0.0097).unwrap();
+
+        assert_eq!(0.0097, mix.far());
+        assert_eq!(mix.mix_type(), GasType::Nitrox);
+    }
+
+    #[test]
+    fn test_mix_negative_fraction() {
+        let err = GasMix::new_trimix(-0.1, 0.21).unwrap_err();
+        assert_eq!(err, GasError::NegativeFraction(GasSymbol::Helium, -0.1));
+
+        let err = GasMix::new(0.5, -0.1, 0.6, 0.0).unwrap_err();
+        assert_eq!(err, GasError::NegativeFraction(GasSymbol::Nitrogen, -0.1));
+    }
+
+    #[test]
+    fn test_mix_oversum() {
+        let err = GasMix::new(0.5, 0.5, 0.2, 0.0).unwrap_err();
+        assert!(matches!(err, GasError::Oversum(_)));
+
+        let err = GasMix::new_nitrox(1.2).unwrap_err();
+        assert_eq!(err, GasError::Oversum(1.2));
+    }
+
+    #[test]
+    fn test_mix_undersum() {
+        let err = GasMix::new(0.21, 0.5, 0.0, 0.0).unwrap_err();
+        assert!(matches!(err, GasError::Undersum(_)));
+    }
+
+    #[test]
+    fn test_mix_hypoxic_at_surface() {
+        let err = GasMix::new_trimix(0.50, 0.12).unwrap_err();
+        assert_eq!(err, GasError::HypoxicAtSurface(0.12));
+    }
+
     #[test]
     fn test_mix_type() {
-        let mix = GasMix::new_nitrox(0.21);
+        let mix = GasMix::new_nitrox(0.21).unwrap();
         assert_eq!(mix.mix_type(), GasType::Nitrox);
 
-        let mix = GasMix::new_trimix(0.30, 0.21);
+        let mix = GasMix::new_trimix(0.30, 0.21).unwrap();
         assert_eq!(mix.mix_type(), GasType::Trimix);
 
-        let mix = GasMix::new_trimix(0.78, 0.21);
+        let mix = GasMix::new_trimix(0.79, 0.21).unwrap();
         assert_eq!(mix.mix_type(), GasType::Heliox);
     }
+
+    #[test]
+    fn test_mix_display() {
+        assert_eq!(GasMix::air().to_string(), "Air");
+        assert_eq!(GasMix::new_nitrox(1.0).unwrap().to_string(), "O2");
+        assert_eq!(GasMix::new_nitrox(0.32).unwrap().to_string(), "EAN32");
+        assert_eq!(
+            GasMix::new_trimix(0.45, 0.18).unwrap().to_string(),
+            "TX18/45"
+        );
+        assert_eq!(
+            GasMix::new_trimix(0.79, 0.21).unwrap().to_string(),
+            "HeliOx21/79"
+        );
+        assert_eq!(GasMix::new_nitrox(0.325).unwrap().to_string(), "EAN32.5");
+    }
+
+    #[test]
+    fn test_mix_from_str() {
+        let parse = |s: &str| s.parse::<GasMix>().unwrap();
+
+        assert_eq!(parse("air").fo2(), AIR_O2);
+        assert_eq!(parse("O2").fo2(), 1.0);
+        assert_eq!(parse("EAN32").fo2(), 0.32);
+        assert_eq!(parse("ean 36").fo2(), 0.36);
+        assert_eq!(parse("Nx50").fo2(), 0.5);
+        assert_eq!(parse("32").fo2(), 0.32);
+
+        let tx = parse("TX 18/45");
+        assert_eq!((tx.fo2(), tx.fhe()), (0.18, 0.45));
+        let tx = parse("Trimix 21/35");
+        assert_eq!((tx.fo2(), tx.fhe()), (0.21, 0.35));
+        let tx = parse("21,35");
+        assert_eq!((tx.fo2(), tx.fhe()), (0.21, 0.35));
+
+        let hx = parse("HeliOx 21/79");
+        assert_eq!(hx.mix_type(), GasType::Heliox);
+        assert_eq!(hx.fhe(), 0.79);
+    }
+
+    #[test]
+    fn test_mix_from_str_round_trip() {
+        for name in ["Air", "O2", "EAN32", "TX18/45", "HeliOx21/79"] {
+            assert_eq!(name.parse::<GasMix>().unwrap().to_string(), name);
+        }
+    }
+
+    #[test]
+    fn test_mix_with_argon_round_trip() {
+        let mix = GasMix::new(0.2095, 0.7808, 0.0, 0.0097).unwrap();
+        assert_eq!(mix.to_string(), "Mix20.95/78.08/0/0.97");
+
+        let json = serde_json::to_string(&mix).unwrap();
+        let parsed: GasMix = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.to_string(), mix.to_string());
+        assert!((parsed.far() - mix.far()).abs() < FRACTION_TOLERANCE);
+        assert!((parsed.fn2() - mix.fn2()).abs() < FRACTION_TOLERANCE);
+
+        let argox: GasMix = "mix 30/0/0/70".parse().unwrap();
+        assert_eq!(argox.far(), 0.7);
+        assert!("Mix21/79".parse::<GasMix>().is_err());
+    }
+
+    #[test]
+    fn test_mix_serde() {
+        let mix: GasMix = serde_json::from_str("\"TX18/45\"").unwrap();
+
+        assert_eq!(mix, GasMix::new_trimix(0.45, 0.18).unwrap());
+        assert_eq!(serde_json::to_string(&mix).unwrap(), "\"TX18/45\"");
+        assert!(serde_json::from_str::<GasMix>("\"EANxx\"").is_err());
+    }
+
+    #[test]
+    fn test_mix_from_str_errors() {
+        assert_eq!(
+            "nitrogen".parse::<GasMix>().unwrap_err(),
+            GasError::InvalidName("nitrogen".to_string())
+        );
+        assert!(matches!(
+            "EANxx".parse::<GasMix>(),
+            Err(GasError::InvalidName(_))
+        ));
+        assert!(matches!(
+            "TX18".parse::<GasMix>(),
+            Err(GasError::InvalidName(_))
+        ));
+        assert!(matches!(
+            "TX10/70".parse::<GasMix>(),
+            Err(GasError::HypoxicAtSurface(_))
+        ));
+        assert!(matches!(
+            "HeliOx21/50".parse::<GasMix>(),
+            Err(GasError::Undersum(_))
+        ));
+    }
 }
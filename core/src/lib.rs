@@ -1,12 +1,25 @@
 #[allow(clippy::new_without_default)]
 pub mod algorithm;
+pub mod compare;
+pub mod contingency;
 pub mod deco;
 pub mod dsat;
+pub mod environment;
+pub mod error;
+pub mod formats;
 pub mod gas;
+pub mod mvalue;
+pub mod plan;
 pub mod profile;
+pub mod registry;
+pub mod repetitive;
+pub mod replay;
+pub mod rgbm;
 pub mod runner;
+pub mod tables;
 pub mod tissue;
 pub mod utils;
+pub mod violations;
 pub mod zhl16;
 
 pub fn main() {
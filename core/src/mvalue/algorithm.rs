@@ -0,0 +1,233 @@
+use crate::{
+    algorithm::{DecoAlgorithm, DecoAlgorithmVariant},
+    deco::{schedule_stops, DecoStop, DecoTissues, GradientFactors},
+    error::DivesyncError,
+    gas::GasMix,
+    profile::DiveProfile,
+    tissue::{CompartmentSnapshot, TissueCompartment},
+    utils::{calc_ata, calc_depth},
+    zhl16::algorithm::MAX_NDL,
+};
+
+use super::tissue::{MValueCompartment, MValueModel};
+
+/// Historical M-value models, stops are scheduled on the same 3m grid
+/// as ZHL16 so schedules can be compared
+#[derive(Clone)]
+pub struct MValueAlgorithm {
+    tissues: Vec<MValueCompartment>,
+    model: MValueModel,
+}
+
+impl DecoAlgorithm for MValueAlgorithm {
+    fn variant(&self) -> DecoAlgorithmVariant {
+        match self.model {
+            MValueModel::Haldane => DecoAlgorithmVariant::Haldane,
+            MValueModel::Workman => DecoAlgorithmVariant::Workman,
+        }
+    }
+
+    fn compute_deco_stops(&self, dive_profile: DiveProfile) -> Vec<DecoStop> {
+        let Some(last) = dive_profile.last_exposure() else {
+            return vec![];
+        };
+
+        // no gradient factors, the model's own M-values are used as is
+        let mut tissues = self.load_profile(&dive_profile);
+        schedule_stops(
+            &mut tissues,
+            last.end_depth,
+            &last.gas_mix,
+            &dive_profile.deco_mixes,
+            GradientFactors::default(),
+        )
+    }
+
+    fn compute_ndl(&self, dive_profile: DiveProfile) -> u32 {
+        let Some(last) = dive_profile.last_exposure() else {
+            return MAX_NDL;
+        };
+
+        let mut tissues = self.load_profile(&dive_profile);
+
+        for minute in 0..MAX_NDL {
+            if tissues.ceiling(1.0) > 0.0 {
+                return minute;
+            }
+
+            tissues.expose(&last.gas_mix, last.end_depth, 1.0);
+        }
+
+        MAX_NDL
+    }
+
+    fn snapshot(&self) -> Vec<CompartmentSnapshot> {
+        self.tissues
+            .iter()
+            .map(|t| {
+                let (pp_n2, pp_he) = t.n2_he_pp();
+                CompartmentSnapshot {
+                    elapsed_time: t.elapsed_time,
+                    pp_n2,
+                    pp_he,
+                    m_val: t.m_value(),
+                    o2_percent: t.gas_mix.pp_o2(1.0) * 100.0,
+                    n2_percent: t.gas_mix.pp_n2(1.0) * 100.0,
+                    he_percent: t.gas_mix.pp_he(1.0) * 100.0,
+                    gas_type: t.gas_mix.mix_type().to_string(),
+                    half_time: t.half_time(),
+                    cpt_num: t.cpt_num,
+                    variant: self.model.to_string(),
+                    last_depth: t.last_depth,
+                }
+            })
+            .collect()
+    }
+
+    fn run(&mut self, mix: GasMix, ata: f32, time: f32) {
+        if self.tissues.is_empty() {
+            self.tissues = build_tissues(mix.clone(), &self.model);
+        }
+
+        for t in &mut self.tissues {
+            t.set_gas_mix(mix.clone());
+            t.update_pressure(ata, time);
+        }
+    }
+
+    /// Exact loading of a depth change by the Schreiner equation
+    fn run_travel(&mut self, mix: GasMix, start_ata: f32, end_ata: f32, time: f32) {
+        if self.tissues.is_empty() {
+            self.tissues = build_tissues(mix.clone(), &self.model);
+        }
+
+        for t in &mut self.tissues {
+            t.set_gas_mix(mix.clone());
+            t.update_pressure_linear(start_ata, end_ata, time);
+        }
+    }
+}
+
+impl MValueAlgorithm {
+    pub fn new(model: MValueModel) -> Self {
+        Self {
+            tissues: vec![],
+            model,
+        }
+    }
+
+    pub fn init(&mut self, mix: GasMix) -> Result<(), DivesyncError> {
+        if !self.tissues.is_empty() {
+            return Err(DivesyncError::AlreadyInitialized(self.model.to_string()));
+        }
+
+        self.tissues = build_tissues(mix, &self.model);
+        Ok(())
+    }
+
+    fn load_profile(&self, dive_profile: &DiveProfile) -> Vec<MValueCompartment> {
+        let mut tissues = if self.tissues.is_empty() {
+            build_tissues(GasMix::air(), &self.model)
+        } else {
+            self.tissues.clone()
+        };
+
+        for exposure in dive_profile.exposures() {
+            tissues.expose(&exposure.gas_mix, exposure.mean_depth(), exposure.time);
+        }
+
+        tissues
+    }
+}
+
+fn build_tissues(mix: GasMix, model: &MValueModel) -> Vec<MValueCompartment> {
+    (0..model.compartment_count())
+        .map(|i| MValueCompartment::new(i, mix.clone(), model))
+        .collect()
+}
+
+impl DecoTissues for Vec<MValueCompartment> {
+    /// Gradient factor is ignored, the M-values are not scaled
+    fn ceiling(&self, _gf: f32) -> f32 {
+        let tolerated = self.iter().map(|t| t.tolerated_ata()).fold(0.0, f32::max);
+
+        calc_depth(tolerated).max(0.0)
+    }
+
+    fn expose(&mut self, mix: &GasMix, depth: f32, time: f32) {
+        for t in self {
+            t.set_gas_mix(mix.clone());
+            t.update_pressure(calc_ata(depth), time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deco::total_stop_time;
+
+    #[test]
+    fn test_ndl_decreases_with_depth() {
+        for model in [MValueModel::Haldane, MValueModel::Workman] {
+            let algo = MValueAlgorithm::new(model);
+
+            let ndl_18 = algo.compute_ndl(DiveProfile::square(18.0, 0, GasMix::air()));
+            let ndl_30 = algo.compute_ndl(DiveProfile::square(30.0, 0, GasMix::air()));
+
+            assert!(ndl_18 > ndl_30);
+            assert!(ndl_30 > 0);
+        }
+    }
+
+    #[test]
+    fn test_haldane_shallow_is_unlimited() {
+        // 2:1 ratio allows direct ascent from 10m after any bottom time
+        let algo = MValueAlgorithm::new(MValueModel::Haldane);
+
+        assert_eq!(
+            algo.compute_ndl(DiveProfile::square(9.0, 0, GasMix::air())),
+            MAX_NDL
+        );
+    }
+
+    #[test]
+    fn test_deco_stops() {
+        for model in [MValueModel::Haldane, MValueModel::Workman] {
+            let algo = MValueAlgorithm::new(model);
+            let ndl = algo.compute_ndl(DiveProfile::square(30.0, 0, GasMix::air()));
+
+            assert!(algo
+                .compute_deco_stops(DiveProfile::square(30.0, ndl - 1, GasMix::air()))
+                .is_empty());
+
+            let stops = algo.compute_deco_stops(DiveProfile::square(45.0, 30, GasMix::air()));
+            assert!(!stops.is_empty());
+            for pair in stops.windows(2) {
+                assert!(pair[0].depth > pair[1].depth);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deco_mix_shortens_deco() {
+        let algo = MValueAlgorithm::new(MValueModel::Workman);
+        let mut profile = DiveProfile::square(45.0, 30, GasMix::air());
+        let air_stops = algo.compute_deco_stops(profile.clone());
+
+        profile.add_deco_mix(GasMix::new_nitrox(0.5).unwrap());
+        let ean50_stops = algo.compute_deco_stops(profile);
+
+        assert!(total_stop_time(&ean50_stops) < total_stop_time(&air_stops));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut algo = MValueAlgorithm::new(MValueModel::Haldane);
+        algo.init(GasMix::air()).unwrap();
+
+        assert_eq!(algo.snapshot().len(), 5);
+        assert!(algo.init(GasMix::air()).is_err());
+        assert_eq!(algo.variant().to_string(), "Haldane");
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+
+use crate::gas::GasMix;
+use crate::tissue::{schreiner, TissueCompartment};
+use crate::utils::n_root;
+
+/// Haldane's 2:1 ratio of absolute pressures, applied to Nitrogen of air
+pub const HALDANE_RATIO: f32 = 2.0 * 0.79;
+
+/// Compartment of a model with M-values linear in ambient pressure,
+/// `M = m0 + delta_m * (ambient - 1.0)` with pressures in ata
+#[derive(Debug, Clone)]
+pub struct MValueCompartment {
+    pp_n2: f32,
+    pp_he: f32,
+    pub cpt_num: usize,
+    pub gas_mix: GasMix,
+    pub n2_half_time: f32,
+    /// Tolerated inert gas tension at the surface in ata
+    pub m0: f32,
+    /// Increase of tolerated tension per ata of ambient pressure
+    pub delta_m: f32,
+    pub elapsed_time: f32,
+    pub last_depth: f32,
+}
+
+impl TissueCompartment for MValueCompartment {
+    fn update_pressure(&mut self, ata: f32, time: f32) {
+        // argon is loaded as nitrogen
+        let exp: f32 = 2_f32.powf(-(time / self.n2_half_time));
+        let gas_pp = self.gas_mix.pp_n2(ata) + self.gas_mix.pp_ar(ata);
+        self.pp_n2 += (gas_pp - self.pp_n2) * (1.0 - exp);
+
+        let exp: f32 = 2_f32.powf(-(time / self.he_half_time()));
+        let gas_pp = self.gas_mix.pp_he(ata);
+        self.pp_he += (gas_pp - self.pp_he) * (1.0 - exp);
+
+        self.elapsed_time += time;
+        self.last_depth = (ata - 1.0) * 10.0
+    }
+
+    fn update_pressure_linear(&mut self, start_ata: f32, end_ata: f32, time: f32) {
+        // argon is loaded as nitrogen
+        let inert_n2 = |ata: f32| self.gas_mix.pp_n2(ata) + self.gas_mix.pp_ar(ata);
+        let (start_n2, end_n2) = (inert_n2(start_ata), inert_n2(end_ata));
+        self.pp_n2 = schreiner(self.pp_n2, start_n2, end_n2, self.n2_half_time, time);
+
+        let (start_he, end_he) = (self.gas_mix.pp_he(start_ata), self.gas_mix.pp_he(end_ata));
+        self.pp_he = schreiner(self.pp_he, start_he, end_he, self.he_half_time(), time);
+
+        self.elapsed_time += time;
+        self.last_depth = (end_ata - 1.0) * 10.0
+    }
+
+    fn half_time(&self) -> f32 {
+        self.n2_half_time
+    }
+
+    /// M-value at the surface
+    fn m_value(&self) -> f32 {
+        self.m0
+    }
+
+    fn n2_he_pp(&self) -> (f32, f32) {
+        (self.pp_n2, self.pp_he)
+    }
+
+    fn gas_mix(&self) -> GasMix {
+        self.gas_mix.clone()
+    }
+}
+
+impl MValueCompartment {
+    pub fn new(cpt_num: usize, gas_mix: GasMix, model: &MValueModel) -> Self {
+        let (n2_half_time, m0, delta_m) = model.coefficients(cpt_num);
+
+        Self {
+            pp_n2: gas_mix.pp_n2(1.0) + gas_mix.pp_ar(1.0),
+            pp_he: gas_mix.pp_he(1.0),
+            cpt_num,
+            gas_mix,
+            n2_half_time,
+            m0,
+            delta_m,
+            elapsed_time: 0.0,
+            last_depth: 0.0,
+        }
+    }
+
+    /// Helium half time scaled from Nitrogen by Graham's law, neither model
+    /// was published with helium values
+    pub fn he_half_time(&self) -> f32 {
+        self.n2_half_time / n_root(28.0184 / 4.0026, 2)
+    }
+
+    /// Tolerated inert gas tension at ambient pressure `ata`
+    pub fn m_value_at(&self, ata: f32) -> f32 {
+        self.m0 + self.delta_m * (ata - 1.0)
+    }
+
+    /// Lowest ambient pressure in ata tolerated by the current tissue tension
+    pub fn tolerated_ata(&self) -> f32 {
+        1.0 + (self.pp_n2 + self.pp_he - self.m0) / self.delta_m
+    }
+
+    pub fn set_pp(&mut self, nitrogen: f32, helium: f32) {
+        self.pp_n2 = nitrogen;
+        self.pp_he = helium;
+    }
+
+    pub fn set_gas_mix(&mut self, mix: GasMix) {
+        self.gas_mix = mix;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MValueModel {
+    /// Haldane 1908, 5 compartments, tension must stay within 2:1 of
+    /// ambient pressure
+    Haldane,
+    /// Workman 1965 US Navy M-values, 9 compartments
+    Workman,
+}
+
+impl MValueModel {
+    pub const HALDANE_HALF_TIMES: [f32; 5] = [5.0, 10.0, 20.0, 40.0, 75.0];
+
+    pub const WORKMAN_HALF_TIMES: [f32; 9] =
+        [5.0, 10.0, 20.0, 40.0, 80.0, 120.0, 160.0, 200.0, 240.0];
+
+    /// Workman surface M-values in msw
+    pub const WORKMAN_M0: [f32; 9] = [31.7, 26.8, 21.9, 17.0, 16.4, 15.8, 15.5, 15.5, 15.2];
+
+    /// Workman M-value slopes
+    pub const WORKMAN_DELTA_M: [f32; 9] = [1.8, 1.6, 1.5, 1.4, 1.3, 1.2, 1.15, 1.1, 1.1];
+
+    pub fn compartment_count(&self) -> usize {
+        match self {
+            MValueModel::Haldane => Self::HALDANE_HALF_TIMES.len(),
+            MValueModel::Workman => Self::WORKMAN_HALF_TIMES.len(),
+        }
+    }
+
+    /// Half time, surface M-value in ata and slope of compartment `cpt_num`
+    pub fn coefficients(&self, cpt_num: usize) -> (f32, f32, f32) {
+        match self {
+            MValueModel::Haldane => (
+                Self::HALDANE_HALF_TIMES[cpt_num],
+                HALDANE_RATIO,
+                HALDANE_RATIO,
+            ),
+            MValueModel::Workman => (
+                Self::WORKMAN_HALF_TIMES[cpt_num],
+                Self::WORKMAN_M0[cpt_num] / 10.0,
+                Self::WORKMAN_DELTA_M[cpt_num],
+            ),
+        }
+    }
+}
+
+impl fmt::Display for MValueModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MValueModel::Haldane => write!(f, "Haldane"),
+            MValueModel::Workman => write!(f, "Workman"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::round_f32;
+
+    #[test]
+    fn test_haldane_ratio() {
+        let t = MValueCompartment::new(0, GasMix::air(), &MValueModel::Haldane);
+
+        // 2:1 ratio of ambient pressure
+        assert_eq!(
+            round_f32(t.m_value_at(2.0), 3),
+            round_f32(2.0 * HALDANE_RATIO, 3)
+        );
+        assert!(t.tolerated_ata() < 1.0);
+    }
+
+    #[test]
+    fn test_workman_m_values() {
+        let t = MValueCompartment::new(0, GasMix::air(), &MValueModel::Workman);
+
+        assert_eq!(round_f32(t.m_value(), 3), 3.17);
+        assert_eq!(round_f32(t.m_value_at(2.0), 3), 4.97);
+    }
+
+    #[test]
+    fn test_tolerated_ata_after_exposure() {
+        let mut t = MValueCompartment::new(0, GasMix::air(), &MValueModel::Workman);
+        t.update_pressure(5.0, 60.0);
+
+        let tolerated = t.tolerated_ata();
+        let (n2, _) = t.n2_he_pp();
+        assert!(tolerated > 1.0);
+        assert_eq!(round_f32(t.m_value_at(tolerated), 4), round_f32(n2, 4));
+    }
+}
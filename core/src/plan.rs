@@ -0,0 +1,496 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::get_algo_with_params;
+use crate::compare::AlgorithmConfig;
+use crate::deco::{ASCENT_RATE, DEFAULT_SAC};
+use crate::environment::Environment;
+use crate::error::DivesyncError;
+use crate::gas::GasMix;
+use crate::profile::{DiveProfile, Segment};
+
+/// Algorithm used when a plan does not name one
+pub const DEFAULT_PLAN_ALGORITHM: &str = "zhl16-c";
+
+/// Cylinder of `volume` liters filled to `pressure` bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cylinder {
+    pub gas_mix: GasMix,
+    pub volume: f32,
+    pub pressure: f32,
+}
+
+impl Cylinder {
+    pub fn new(gas_mix: GasMix, volume: f32, pressure: f32) -> Self {
+        Self {
+            gas_mix,
+            volume,
+            pressure,
+        }
+    }
+
+    /// Surface liters of gas in the cylinder
+    pub fn gas_volume(&self) -> f32 {
+        self.volume * self.pressure
+    }
+}
+
+/// Dive plan read from a `.toml`, `.yaml` or `.json` file, segments and
+/// deco mixes sit at the top level of the file, eg.
+///
+/// ```toml
+/// algorithm = "zhl16-c:gf_low=30,gf_high=80"
+/// sac = 18.0
+/// deco_mixes = ["EAN50"]
+///
+/// [environment]
+/// altitude = 0.0
+/// water = "salt"
+///
+/// [[cylinders]]
+/// gas_mix = "Air"
+/// volume = 24.0
+/// pressure = 200.0
+///
+/// [[segments]]
+/// type = "travel"
+/// depth = 30.0
+/// rate = 18.0
+/// gas_mix = "Air"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DivePlan {
+    #[serde(default = "default_algorithm")]
+    pub algorithm: AlgorithmConfig,
+    #[serde(default)]
+    pub environment: Environment,
+    /// Surface air consumption in liters per minute
+    #[serde(default = "default_sac")]
+    pub sac: f32,
+    #[serde(default)]
+    pub cylinders: Vec<Cylinder>,
+    #[serde(flatten)]
+    pub profile: DiveProfile,
+}
+
+fn default_algorithm() -> AlgorithmConfig {
+    AlgorithmConfig::new(DEFAULT_PLAN_ALGORITHM)
+}
+
+fn default_sac() -> f32 {
+    DEFAULT_SAC
+}
+
+impl DivePlan {
+    pub fn new(profile: DiveProfile) -> Self {
+        Self {
+            algorithm: default_algorithm(),
+            environment: Environment::default(),
+            sac: DEFAULT_SAC,
+            cylinders: vec![],
+            profile,
+        }
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, DivesyncError> {
+        let plan: Self = toml::from_str(s).map_err(|e| DivesyncError::Parse(e.to_string()))?;
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    pub fn from_yaml(s: &str) -> Result<Self, DivesyncError> {
+        let plan: Self =
+            serde_yaml::from_str(s).map_err(|e| DivesyncError::Parse(e.to_string()))?;
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, DivesyncError> {
+        let plan: Self =
+            serde_json::from_str(s).map_err(|e| DivesyncError::Parse(e.to_string()))?;
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    /// Load plan from a `.toml`, `.yaml`, `.yml` or `.json` file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, DivesyncError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&content),
+            Some("yaml") | Some("yml") => Self::from_yaml(&content),
+            Some("json") => Self::from_json(&content),
+            _ => Err(DivesyncError::Parse(format!(
+                "unsupported plan file: {}, expected .toml, .yaml or .json",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), DivesyncError> {
+        let invalid = |msg: String| Err(DivesyncError::InvalidPlan(msg));
+
+        self.profile.validate()?;
+        self.environment.validate()?;
+
+        if self.profile.segments.is_empty() {
+            return invalid("plan has no segments".to_string());
+        }
+        if !self.sac.is_finite() || self.sac <= 0.0 {
+            return invalid(format!("sac {} must be above zero", self.sac));
+        }
+        for cylinder in &self.cylinders {
+            if !(cylinder.volume > 0.0 && cylinder.pressure > 0.0) {
+                return invalid(format!(
+                    "{} cylinder volume and pressure must be above zero",
+                    cylinder.gas_mix
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runtime of all segments followed by the deco stops of the ascent,
+    /// depths of the plan are converted to equivalent sea level depths for
+    /// the algorithm
+    pub fn runtime(&self) -> Result<RuntimeTable, DivesyncError> {
+        self.validate()?;
+
+        let algo = get_algo_with_params(&self.algorithm.name, &self.algorithm.params)?;
+        let equivalent = self.environment.equivalent_profile(&self.profile);
+        let stops = algo.compute_deco_stops(equivalent);
+
+        let mut runtime = Runtime::new(self);
+
+        for (segment, exposure) in self.profile.segments.iter().zip(self.profile.exposures()) {
+            let action = match segment {
+                Segment::Travel { .. } if exposure.end_depth > exposure.start_depth => {
+                    RuntimeAction::Descend
+                }
+                Segment::Travel { .. } => RuntimeAction::Ascend,
+                Segment::Hold { .. } => RuntimeAction::Hold,
+                Segment::GasSwitch { .. } => RuntimeAction::GasSwitch,
+                Segment::SurfaceInterval { .. } => RuntimeAction::Surface,
+            };
+
+            runtime.push(
+                action,
+                exposure.start_depth,
+                exposure.end_depth,
+                exposure.time,
+                &exposure.gas_mix,
+            );
+        }
+
+        let last = self.profile.last_exposure();
+        let mut depth = last.as_ref().map_or(0.0, |e| e.end_depth);
+        let mut mix = last.map_or(GasMix::air(), |e| e.gas_mix);
+
+        for stop in &stops {
+            let stop_depth = self.environment.actual_depth(stop.depth);
+            let travel = (depth - stop_depth) / ASCENT_RATE;
+            runtime.push(RuntimeAction::Ascend, depth, stop_depth, travel, &mix);
+
+            mix = stop.gas_mix.clone();
+            let time = stop.time as f32;
+            runtime.push(RuntimeAction::Stop, stop_depth, stop_depth, time, &mix);
+            depth = stop_depth;
+        }
+
+        if depth > 0.0 {
+            runtime.push(RuntimeAction::Ascend, depth, 0.0, depth / ASCENT_RATE, &mix);
+        }
+
+        Ok(RuntimeTable {
+            algorithm: algo.variant().to_string(),
+            environment: self.environment,
+            rows: runtime.rows,
+            gas: runtime.gas,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeAction {
+    Descend,
+    Ascend,
+    Hold,
+    GasSwitch,
+    Stop,
+    Surface,
+}
+
+impl fmt::Display for RuntimeAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeAction::Descend => write!(f, "descend"),
+            RuntimeAction::Ascend => write!(f, "ascend"),
+            RuntimeAction::Hold => write!(f, "hold"),
+            RuntimeAction::GasSwitch => write!(f, "switch"),
+            RuntimeAction::Stop => write!(f, "stop"),
+            RuntimeAction::Surface => write!(f, "surface"),
+        }
+    }
+}
+
+/// Row of a runtime table, `depth` is the depth at the end of the row and
+/// times are in seconds
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeRow {
+    pub action: RuntimeAction,
+    pub depth: f32,
+    pub duration: u32,
+    pub run_time: u32,
+    pub gas_mix: GasMix,
+}
+
+/// Surface liters breathed of a mix, `available` is `None` when no
+/// cylinder holds the mix
+#[derive(Debug, Clone, Serialize)]
+pub struct GasUsage {
+    pub gas_mix: GasMix,
+    pub used: f32,
+    pub available: Option<f32>,
+}
+
+impl GasUsage {
+    pub fn is_short(&self) -> bool {
+        self.available
+            .is_some_and(|available| self.used > available)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeTable {
+    pub algorithm: String,
+    pub environment: Environment,
+    pub rows: Vec<RuntimeRow>,
+    pub gas: Vec<GasUsage>,
+}
+
+impl RuntimeTable {
+    /// Total run time in seconds
+    pub fn run_time(&self) -> u32 {
+        self.rows.last().map_or(0, |r| r.run_time)
+    }
+
+    pub fn to_json(&self) -> Result<String, DivesyncError> {
+        serde_json::to_string_pretty(self).map_err(|e| DivesyncError::Parse(e.to_string()))
+    }
+}
+
+impl fmt::Display for RuntimeTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}, {}", self.algorithm, self.environment)?;
+        writeln!(
+            f,
+            "{:<8} {:>7} {:>7} {:>7}  Gas",
+            "Action", "Depth", "Time", "Run"
+        )?;
+
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{:<8} {:>6.1}m {:>7} {:>7}  {}",
+                row.action.to_string(),
+                row.depth,
+                fmt_seconds(row.duration),
+                fmt_seconds(row.run_time),
+                row.gas_mix
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:<12} {:>8} {:>10}", "Gas", "Used", "Available")?;
+
+        for usage in &self.gas {
+            let available = usage
+                .available
+                .map_or("-".to_string(), |a| format!("{a:.0}L"));
+            let short = if usage.is_short() { "  short" } else { "" };
+            writeln!(
+                f,
+                "{:<12} {:>7.0}L {:>10}{short}",
+                usage.gas_mix.to_string(),
+                usage.used,
+                available
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Time as `m:ss`
+fn fmt_seconds(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Rows and gas consumption collected while walking a plan
+struct Runtime<'a> {
+    plan: &'a DivePlan,
+    rows: Vec<RuntimeRow>,
+    gas: Vec<GasUsage>,
+    elapsed: f32,
+}
+
+impl<'a> Runtime<'a> {
+    fn new(plan: &'a DivePlan) -> Self {
+        Self {
+            plan,
+            rows: vec![],
+            gas: vec![],
+            elapsed: 0.0,
+        }
+    }
+
+    /// Add a row going from `from` to `to` over `time` minutes
+    fn push(&mut self, action: RuntimeAction, from: f32, to: f32, time: f32, mix: &GasMix) {
+        let start = (self.elapsed * 60.0).round() as u32;
+        self.elapsed += time;
+        let run_time = (self.elapsed * 60.0).round() as u32;
+
+        self.rows.push(RuntimeRow {
+            action,
+            depth: to,
+            duration: run_time - start,
+            run_time,
+            gas_mix: mix.clone(),
+        });
+
+        if action == RuntimeAction::Surface {
+            return;
+        }
+
+        let ambient = self.plan.environment.ambient_pressure((from + to) / 2.0);
+        let liters = self.plan.sac * ambient * time;
+
+        match self.gas.iter_mut().find(|g| &g.gas_mix == mix) {
+            Some(usage) => usage.used += liters,
+            None => {
+                let cylinders = self.plan.cylinders.iter().filter(|c| &c.gas_mix == mix);
+                let available = cylinders.map(|c| c.gas_volume()).reduce(|a, b| a + b);
+
+                self.gas.push(GasUsage {
+                    gas_mix: mix.clone(),
+                    used: liters,
+                    available,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::environment::Water;
+
+    const TOML_PLAN: &str = r#"
+algorithm = "zhl16-c:gf_low=30,gf_high=80"
+sac = 18.0
+deco_mixes = ["EAN50"]
+
+[[cylinders]]
+gas_mix = "Air"
+volume = 24.0
+pressure = 200.0
+
+[[segments]]
+type = "travel"
+depth = 40.0
+rate = 20.0
+gas_mix = "Air"
+
+[[segments]]
+type = "hold"
+depth = 40.0
+duration = 1500
+gas_mix = "Air"
+"#;
+
+    #[test]
+    fn test_plan_from_toml() {
+        let plan = DivePlan::from_toml(TOML_PLAN).unwrap();
+
+        assert_eq!(plan.algorithm.name, "zhl16-c");
+        assert_eq!(plan.algorithm.params["gf_low"], 30.0);
+        assert_eq!(plan.sac, 18.0);
+        assert_eq!(plan.environment, Environment::default());
+        assert_eq!(plan.profile.segments.len(), 2);
+        assert_eq!(
+            plan.profile.deco_mixes,
+            vec![GasMix::new_nitrox(0.5).unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_plan_formats_share_schema() {
+        let plan = DivePlan::from_toml(TOML_PLAN).unwrap();
+
+        let json = serde_json::to_string(&plan).unwrap();
+        let yaml = serde_yaml::to_string(&plan).unwrap();
+
+        let from_json = DivePlan::from_json(&json).unwrap();
+        let from_yaml = DivePlan::from_yaml(&yaml).unwrap();
+
+        assert_eq!(from_json.profile.segments, plan.profile.segments);
+        assert_eq!(from_yaml.profile.segments, plan.profile.segments);
+        assert_eq!(from_yaml.algorithm.params, plan.algorithm.params);
+        assert_eq!(from_yaml.cylinders, plan.cylinders);
+    }
+
+    #[test]
+    fn test_plan_validation() {
+        let no_segments = DivePlan::from_json(r#"{"segments": []}"#);
+        assert!(matches!(no_segments, Err(DivesyncError::InvalidPlan(_))));
+
+        let zero_rate = DivePlan::from_json(
+            r#"{"segments": [{"type": "travel", "depth": 10, "rate": 0, "gas_mix": "Air"}]}"#,
+        );
+        assert!(matches!(zero_rate, Err(DivesyncError::InvalidProfile(_))));
+
+        let bad_gas = DivePlan::from_json(
+            r#"{"segments": [{"type": "hold", "depth": 10, "duration": 60, "gas_mix": "EANxx"}]}"#,
+        );
+        assert!(matches!(bad_gas, Err(DivesyncError::Parse(_))));
+    }
+
+    #[test]
+    fn test_plan_runtime() {
+        let plan = DivePlan::from_toml(TOML_PLAN).unwrap();
+        let runtime = plan.runtime().unwrap();
+
+        assert_eq!(runtime.rows[0].action, RuntimeAction::Descend);
+        assert_eq!(runtime.rows[0].duration, 120);
+        assert_eq!(runtime.rows[1].run_time, 27 * 60);
+        assert!(runtime.rows.iter().any(|r| r.action == RuntimeAction::Stop));
+        assert_eq!(runtime.rows.last().unwrap().depth, 0.0);
+
+        let air = &runtime.gas[0];
+        assert_eq!(air.gas_mix, GasMix::air());
+        assert_eq!(air.available, Some(4800.0));
+        assert!(runtime.gas.iter().any(|g| g.available.is_none()));
+
+        let text = runtime.to_string();
+        assert!(text.starts_with("ZHL16-C, altitude 0m, salt water\n"));
+        assert!(text.contains("hold"));
+    }
+
+    #[test]
+    fn test_plan_runtime_altitude() {
+        let mut plan = DivePlan::from_toml(TOML_PLAN).unwrap();
+        let sea_level = plan.runtime().unwrap().run_time();
+
+        plan.environment = Environment::new(2500.0, Water::Fresh);
+        let altitude = plan.runtime().unwrap().run_time();
+
+        assert!(altitude > sea_level);
+    }
+}
@@ -1,29 +1,373 @@
-use crate::gas::{Gas, GasMix};
+use serde::{Deserialize, Serialize};
 
-pub struct DiveProfileLevel {
+use crate::error::DivesyncError;
+use crate::gas::GasMix;
+
+/// Part of a dive, durations are in seconds and rates in meters per minute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Segment {
+    /// Descent or ascent from the current depth to `depth` at `rate`
+    Travel {
+        depth: f32,
+        rate: f32,
+        gas_mix: GasMix,
+    },
+    /// Constant depth, moving there instantly from the current depth
+    Hold {
+        depth: f32,
+        duration: u32,
+        gas_mix: GasMix,
+    },
+    /// Change of breathing gas at the current depth
+    GasSwitch { gas_mix: GasMix, duration: u32 },
+    /// Time spent at the surface breathing air
+    SurfaceInterval { duration: u32 },
+}
+
+/// Constant rate exposure to a gas between two depths, `time` in minutes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exposure {
+    pub start_depth: f32,
+    pub end_depth: f32,
+    pub time: f32,
     pub gas_mix: GasMix,
-    pub depth: f32, // Depth in meters
-    pub time: u32,  // Time in minutes at a given depth
 }
 
+impl Exposure {
+    pub fn mean_depth(&self) -> f32 {
+        (self.start_depth + self.end_depth) / 2.0
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DiveProfile {
-    pub levels: Vec<DiveProfileLevel>,
+    pub segments: Vec<Segment>,
+    #[serde(default)]
+    pub deco_mixes: Vec<GasMix>, // Gases carried for decompression
 }
 
 impl DiveProfile {
     pub fn new() -> Self {
-        Self { levels: vec![] }
+        Self {
+            segments: vec![],
+            deco_mixes: vec![],
+        }
+    }
+
+    /// Square profile holding `depth` for `time` minutes on `mix`
+    pub fn square(depth: f32, time: u32, mix: GasMix) -> Self {
+        let mut profile = Self::new();
+        profile.add_level(depth, time, mix);
+        profile
+    }
+
+    /// Profile of no duration ending at `depth` on `mix` after reaching
+    /// `max_depth`, algorithms whose tissues were advanced with `run`
+    /// compute stops and NDL from it without loading the dive again
+    pub fn status(max_depth: f32, depth: f32, mix: GasMix, deco_mixes: &[GasMix]) -> Self {
+        let mut profile = Self::square(max_depth, 0, mix.clone());
+        profile.add_level(depth, 0, mix);
+        profile.deco_mixes = deco_mixes.to_vec();
+        profile
     }
 
+    /// Hold `depth` for `time` minutes
     pub fn add_level(&mut self, depth: f32, time: u32, mix: GasMix) {
-        self.levels.push(DiveProfileLevel {
-            gas_mix: mix,
+        self.segments.push(Segment::Hold {
             depth,
-            time,
+            duration: time * 60,
+            gas_mix: mix,
         })
     }
+
+    pub fn add_segment(&mut self, segment: Segment) {
+        self.segments.push(segment)
+    }
+
+    pub fn add_deco_mix(&mut self, mix: GasMix) {
+        self.deco_mixes.push(mix)
+    }
+
+    /// Gas exposures of all segments in order, starting at the surface
+    pub fn exposures(&self) -> Vec<Exposure> {
+        let mut exposures = vec![];
+        let mut depth = 0.0;
+
+        for segment in &self.segments {
+            let exposure = match segment {
+                Segment::Travel {
+                    depth: to,
+                    rate,
+                    gas_mix,
+                } => Exposure {
+                    start_depth: depth,
+                    end_depth: *to,
+                    time: (to - depth).abs() / rate,
+                    gas_mix: gas_mix.clone(),
+                },
+                Segment::Hold {
+                    depth: at,
+                    duration,
+                    gas_mix,
+                } => Exposure {
+                    start_depth: *at,
+                    end_depth: *at,
+                    time: *duration as f32 / 60.0,
+                    gas_mix: gas_mix.clone(),
+                },
+                Segment::GasSwitch { gas_mix, duration } => Exposure {
+                    start_depth: depth,
+                    end_depth: depth,
+                    time: *duration as f32 / 60.0,
+                    gas_mix: gas_mix.clone(),
+                },
+                Segment::SurfaceInterval { duration } => Exposure {
+                    start_depth: 0.0,
+                    end_depth: 0.0,
+                    time: *duration as f32 / 60.0,
+                    gas_mix: GasMix::air(),
+                },
+            };
+
+            depth = exposure.end_depth;
+            exposures.push(exposure);
+        }
+
+        exposures
+    }
+
+    /// Depth and gas at the end of the profile, `None` if it has no segments
+    pub fn last_exposure(&self) -> Option<Exposure> {
+        self.exposures().pop()
+    }
+
+    /// Deepest depth reached in meters
+    pub fn max_depth(&self) -> f32 {
+        self.exposures()
+            .iter()
+            .map(|e| e.start_depth.max(e.end_depth))
+            .fold(0.0, f32::max)
+    }
+
+    /// Total time of all segments in seconds
+    pub fn duration(&self) -> u32 {
+        let minutes: f32 = self.exposures().iter().map(|e| e.time).sum();
+        (minutes * 60.0).round() as u32
+    }
+
+    /// Total time of all segments in minutes, rounded up
+    pub fn bottom_time(&self) -> u32 {
+        self.duration().div_ceil(60)
+    }
+
+    /// Check all depths are positive and all travel rates above zero
+    pub fn validate(&self) -> Result<(), DivesyncError> {
+        let mut depth = 0.0;
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Travel {
+                    depth: to, rate, ..
+                } => {
+                    check_depth(i, *to)?;
+                    if !rate.is_finite() || *rate <= 0.0 {
+                        return Err(invalid(i, format!("travel rate {rate} must be above zero")));
+                    }
+                    depth = *to;
+                }
+                Segment::Hold { depth: at, .. } => {
+                    check_depth(i, *at)?;
+                    depth = *at;
+                }
+                Segment::GasSwitch { .. } => {}
+                Segment::SurfaceInterval { .. } => {
+                    if depth > 0.0 {
+                        return Err(invalid(i, format!("surface interval at {depth}m")));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn check_depth(index: usize, depth: f32) -> Result<(), DivesyncError> {
+    match depth.is_finite() && depth >= 0.0 {
+        true => Ok(()),
+        false => Err(invalid(
+            index,
+            format!("depth {depth}m must not be negative"),
+        )),
+    }
+}
+
+fn invalid(index: usize, msg: String) -> DivesyncError {
+    DivesyncError::InvalidProfile(format!("segment {}: {msg}", index + 1))
+}
+
+/// Build a continuous dive profile starting at the surface, invalid steps
+/// are reported by `build`
+#[derive(Debug, Clone)]
+pub struct DiveProfileBuilder {
+    profile: DiveProfile,
+    depth: f32,
+    gas_mix: GasMix,
+    error: Option<String>,
+}
+
+impl DiveProfileBuilder {
+    pub fn new(gas_mix: GasMix) -> Self {
+        Self {
+            profile: DiveProfile::new(),
+            depth: 0.0,
+            gas_mix,
+            error: None,
+        }
+    }
+
+    /// Descend to `depth` at `rate` meters per minute
+    pub fn descend(self, depth: f32, rate: f32) -> Self {
+        if depth < self.depth {
+            let msg = format!("descent to {depth}m from {}m", self.depth);
+            return self.fail(msg);
+        }
+        self.travel(depth, rate)
+    }
+
+    /// Ascend to `depth` at `rate` meters per minute
+    pub fn ascend(self, depth: f32, rate: f32) -> Self {
+        if depth > self.depth {
+            let msg = format!("ascent to {depth}m from {}m", self.depth);
+            return self.fail(msg);
+        }
+        self.travel(depth, rate)
+    }
+
+    /// Stay at the current depth for `duration` seconds
+    pub fn hold(mut self, duration: u32) -> Self {
+        self.profile.add_segment(Segment::Hold {
+            depth: self.depth,
+            duration,
+            gas_mix: self.gas_mix.clone(),
+        });
+        self
+    }
+
+    /// Breathe `gas_mix` from now on, the switch takes `duration` seconds
+    pub fn switch_gas(mut self, gas_mix: GasMix, duration: u32) -> Self {
+        self.gas_mix = gas_mix.clone();
+        self.profile
+            .add_segment(Segment::GasSwitch { gas_mix, duration });
+        self
+    }
+
+    /// Spend `duration` seconds at the surface
+    pub fn surface_interval(mut self, duration: u32) -> Self {
+        self.profile
+            .add_segment(Segment::SurfaceInterval { duration });
+        self
+    }
+
+    /// Carry `gas_mix` for decompression
+    pub fn deco_mix(mut self, gas_mix: GasMix) -> Self {
+        self.profile.add_deco_mix(gas_mix);
+        self
+    }
+
+    pub fn build(self) -> Result<DiveProfile, DivesyncError> {
+        if let Some(msg) = self.error {
+            return Err(DivesyncError::InvalidProfile(msg));
+        }
+
+        self.profile.validate()?;
+        Ok(self.profile)
+    }
+
+    fn travel(mut self, depth: f32, rate: f32) -> Self {
+        self.profile.add_segment(Segment::Travel {
+            depth,
+            rate,
+            gas_mix: self.gas_mix.clone(),
+        });
+        self.depth = depth;
+        self
+    }
+
+    fn fail(mut self, msg: String) -> Self {
+        self.error.get_or_insert(msg);
+        self
+    }
 }
 
+#[cfg(test)]
 mod test {
-    // TODO: Test DiveProfile
+    use super::*;
+
+    #[test]
+    fn test_profile_max_depth() {
+        let mut profile = DiveProfile::new();
+        profile.add_level(18.0, 10, GasMix::air());
+        profile.add_level(30.0, 20, GasMix::air());
+        profile.add_level(12.0, 15, GasMix::air());
+
+        assert_eq!(profile.max_depth(), 30.0);
+        assert_eq!(profile.bottom_time(), 45);
+        assert_eq!(profile.last_exposure().unwrap().end_depth, 12.0);
+    }
+
+    #[test]
+    fn test_builder_exposures() {
+        let ean50 = GasMix::new_nitrox(0.5).unwrap();
+        let profile = DiveProfileBuilder::new(GasMix::air())
+            .descend(30.0, 20.0)
+            .hold(90)
+            .ascend(21.0, 9.0)
+            .switch_gas(ean50.clone(), 30)
+            .ascend(0.0, 9.0)
+            .build()
+            .unwrap();
+
+        let exposures = profile.exposures();
+
+        assert_eq!(exposures.len(), 5);
+        assert_eq!(exposures[0].time, 1.5);
+        assert_eq!(exposures[1].time, 1.5);
+        assert_eq!(exposures[3].start_depth, 21.0);
+        assert_eq!(exposures[3].time, 0.5);
+        assert_eq!(exposures[4].gas_mix, ean50);
+        assert_eq!(profile.duration(), 90 + 90 + 60 + 30 + 140);
+        assert_eq!(profile.max_depth(), 30.0);
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let air = GasMix::air();
+
+        let zero_rate = DiveProfileBuilder::new(air.clone())
+            .descend(20.0, 0.0)
+            .build();
+        assert!(matches!(zero_rate, Err(DivesyncError::InvalidProfile(_))));
+
+        let negative = DiveProfileBuilder::new(air.clone())
+            .ascend(-3.0, 9.0)
+            .build();
+        assert!(matches!(negative, Err(DivesyncError::InvalidProfile(_))));
+
+        let wrong_way = DiveProfileBuilder::new(air.clone())
+            .descend(20.0, 18.0)
+            .descend(10.0, 18.0)
+            .build();
+        assert!(matches!(wrong_way, Err(DivesyncError::InvalidProfile(_))));
+
+        let underwater_interval = DiveProfileBuilder::new(air)
+            .descend(10.0, 18.0)
+            .surface_interval(600)
+            .build();
+        assert!(matches!(
+            underwater_interval,
+            Err(DivesyncError::InvalidProfile(_))
+        ));
+    }
 }
@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock, RwLockReadGuard};
+
+use crate::algorithm::DecoAlgorithm;
+use crate::deco::GradientFactors;
+use crate::dsat::algorithm::DSATAlgorithm;
+use crate::error::DivesyncError;
+use crate::mvalue::{algorithm::MValueAlgorithm, tissue::MValueModel};
+use crate::rgbm::algorithm::RGBMAlgorithm;
+use crate::zhl16::algorithm::ZHL16Algorithm;
+use crate::zhl16::coefficients::{CoefficientTable, HeliumCoefficients};
+use crate::zhl16::tissue::ZHL16Variant;
+
+/// Numeric parameters passed to an algorithm constructor, keyed by `ParamSpec::name`
+pub type AlgorithmParams = HashMap<String, f32>;
+
+pub type AlgorithmConstructor =
+    Box<dyn Fn(&AlgorithmParams) -> Result<Box<dyn DecoAlgorithm>, DivesyncError> + Send + Sync>;
+
+/// Description of a single numeric algorithm parameter
+#[derive(Debug, Clone)]
+pub struct ParamSpec {
+    pub name: String,
+    pub description: String,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl ParamSpec {
+    pub fn new(name: &str, description: &str, default: f32, min: f32, max: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            default,
+            min,
+            max,
+        }
+    }
+}
+
+/// Registered algorithm, constructor is called with params validated
+/// against the schema and completed with defaults
+pub struct AlgorithmEntry {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub description: String,
+    pub params: Vec<ParamSpec>,
+    /// False for placeholders whose results are not computed by the model
+    pub implemented: bool,
+    constructor: AlgorithmConstructor,
+}
+
+impl AlgorithmEntry {
+    pub fn new<F>(name: &str, description: &str, constructor: F) -> Self
+    where
+        F: Fn(&AlgorithmParams) -> Result<Box<dyn DecoAlgorithm>, DivesyncError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            aliases: vec![],
+            description: description.to_string(),
+            params: vec![],
+            implemented: true,
+            constructor: Box::new(constructor),
+        }
+    }
+
+    /// Mark as a placeholder, left out of `implemented_names`
+    pub fn unimplemented(mut self) -> Self {
+        self.implemented = false;
+        self
+    }
+
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+
+    pub fn param(mut self, param: ParamSpec) -> Self {
+        self.params.push(param);
+        self
+    }
+
+    /// Check if name or any alias matches, case insensitive
+    pub fn matches(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    /// Validate params against schema and construct the algorithm
+    pub fn build(&self, params: &AlgorithmParams) -> Result<Box<dyn DecoAlgorithm>, DivesyncError> {
+        let mut resolved = AlgorithmParams::new();
+
+        for (key, value) in params {
+            let Some(spec) = self.params.iter().find(|p| p.name == *key) else {
+                return Err(DivesyncError::UnknownParam(self.name.clone(), key.clone()));
+            };
+
+            if *value < spec.min || *value > spec.max {
+                return Err(DivesyncError::ParamOutOfRange(key.clone(), *value));
+            }
+
+            resolved.insert(key.clone(), *value);
+        }
+
+        for spec in &self.params {
+            resolved.entry(spec.name.clone()).or_insert(spec.default);
+        }
+
+        (self.constructor)(&resolved)
+    }
+}
+
+#[derive(Default)]
+pub struct AlgorithmRegistry {
+    entries: Vec<AlgorithmEntry>,
+}
+
+impl AlgorithmRegistry {
+    pub fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    /// Registry with all algorithms shipped with divesync
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+
+        for entry in builtin_entries() {
+            registry
+                .register(entry)
+                .expect("builtin algorithm names are unique");
+        }
+
+        registry
+    }
+
+    /// Register a new algorithm, name and aliases must not be in use
+    pub fn register(&mut self, entry: AlgorithmEntry) -> Result<(), DivesyncError> {
+        let names = std::iter::once(&entry.name).chain(&entry.aliases);
+
+        for name in names {
+            if self.find(name).is_some() {
+                return Err(DivesyncError::DuplicateAlgorithm(name.clone()));
+            }
+        }
+
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    pub fn find(&self, name: &str) -> Option<&AlgorithmEntry> {
+        self.entries.iter().find(|e| e.matches(name))
+    }
+
+    pub fn build(
+        &self,
+        name: &str,
+        params: &AlgorithmParams,
+    ) -> Result<Box<dyn DecoAlgorithm>, DivesyncError> {
+        match self.find(name) {
+            Some(entry) => entry.build(params),
+            None => Err(DivesyncError::UnknownAlgorithm(name.to_string())),
+        }
+    }
+
+    pub fn entries(&self) -> &[AlgorithmEntry] {
+        &self.entries
+    }
+
+    /// Names of all registered algorithms, in registration order
+    pub fn names(&self) -> Vec<String> {
+        self.entries.iter().map(|e| e.name.clone()).collect()
+    }
+
+    /// Names of registered algorithms which are not placeholders, in
+    /// registration order
+    pub fn implemented_names(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|e| e.implemented)
+            .map(|e| e.name.clone())
+            .collect()
+    }
+}
+
+static REGISTRY: OnceLock<RwLock<AlgorithmRegistry>> = OnceLock::new();
+
+fn global() -> &'static RwLock<AlgorithmRegistry> {
+    REGISTRY.get_or_init(|| RwLock::new(AlgorithmRegistry::with_builtins()))
+}
+
+/// Global registry used by `get_algo`, initialized with builtin algorithms
+pub fn registry() -> RwLockReadGuard<'static, AlgorithmRegistry> {
+    global().read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Register an algorithm in the global registry, used by downstream crates
+/// to add their own models
+pub fn register_algorithm(entry: AlgorithmEntry) -> Result<(), DivesyncError> {
+    global()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .register(entry)
+}
+
+fn gradient_factor_params() -> [ParamSpec; 2] {
+    [
+        ParamSpec::new(
+            "gf_low",
+            "Gradient factor low in percent",
+            100.0,
+            1.0,
+            100.0,
+        ),
+        ParamSpec::new(
+            "gf_high",
+            "Gradient factor high in percent",
+            100.0,
+            1.0,
+            100.0,
+        ),
+    ]
+}
+
+fn with_gradient_factors(
+    mut algo: ZHL16Algorithm,
+    params: &AlgorithmParams,
+) -> Result<Box<dyn DecoAlgorithm>, DivesyncError> {
+    algo.set_gradient_factors(GradientFactors::new(
+        params["gf_low"] / 100.0,
+        params["gf_high"] / 100.0,
+    ));
+    Ok(Box::new(algo))
+}
+
+fn zhl16_entry(name: &str, variant: ZHL16Variant) -> AlgorithmEntry {
+    let [gf_low, gf_high] = gradient_factor_params();
+
+    AlgorithmEntry::new(
+        name,
+        &format!("Bühlmann {name} with gradient factors"),
+        move |params| {
+            let helium = match params["graham_he"] >= 1.0 {
+                true => HeliumCoefficients::Graham,
+                false => HeliumCoefficients::Published,
+            };
+            with_gradient_factors(ZHL16Algorithm::with_helium(variant.clone(), helium), params)
+        },
+    )
+    .param(gf_low)
+    .param(gf_high)
+    .param(ParamSpec::new(
+        "graham_he",
+        "1 to derive helium coefficients by Graham's law instead of Bühlmann's table",
+        0.0,
+        0.0,
+        1.0,
+    ))
+}
+
+fn zhl12_entry() -> AlgorithmEntry {
+    let [gf_low, gf_high] = gradient_factor_params();
+
+    AlgorithmEntry::new(
+        "ZHL12",
+        "Bühlmann ZH-L12, 16 compartments with 12 coefficient pairs",
+        |params| with_gradient_factors(ZHL16Algorithm::new(ZHL16Variant::L12), params),
+    )
+    .alias("zh-l12")
+    .param(gf_low)
+    .param(gf_high)
+}
+
+/// Entry for a user defined ZHL coefficient table, registered under the
+/// table name with gradient factor params
+pub fn zhl16_table_entry(table: CoefficientTable) -> Result<AlgorithmEntry, DivesyncError> {
+    table.validate()?;
+    let [gf_low, gf_high] = gradient_factor_params();
+    let name = table.name.clone();
+
+    let entry = AlgorithmEntry::new(
+        &name,
+        &format!("Custom ZHL table {name} with gradient factors"),
+        move |params| with_gradient_factors(ZHL16Algorithm::from_table(table.clone())?, params),
+    )
+    .param(gf_low)
+    .param(gf_high);
+
+    Ok(entry)
+}
+
+fn builtin_entries() -> Vec<AlgorithmEntry> {
+    vec![
+        zhl16_entry("ZHL16-A", ZHL16Variant::A).alias("zhl16"),
+        zhl16_entry("ZHL16-B", ZHL16Variant::B),
+        zhl16_entry("ZHL16-C", ZHL16Variant::C),
+        zhl12_entry(),
+        AlgorithmEntry::new("DSAT", "DSAT recreational model, not implemented", |_| {
+            Ok(Box::new(DSATAlgorithm::new()))
+        })
+        .unimplemented(),
+        AlgorithmEntry::new(
+            "Haldane",
+            "Haldane 1908, 5 compartments with 2:1 ratio",
+            |_| Ok(Box::new(MValueAlgorithm::new(MValueModel::Haldane))),
+        ),
+        AlgorithmEntry::new("Workman", "Workman 1965 US Navy M-values", |_| {
+            Ok(Box::new(MValueAlgorithm::new(MValueModel::Workman)))
+        }),
+        AlgorithmEntry::new(
+            "RGBM",
+            "Reduced gradient recreational model with bubble factors",
+            |params| {
+                let mut algo = RGBMAlgorithm::new();
+                algo.set_conservatism(params["conservatism"] as u32);
+                Ok(Box::new(algo))
+            },
+        )
+        .param(ParamSpec::new(
+            "conservatism",
+            "Conservatism level, each level reduces the allowed gradient by 5%",
+            0.0,
+            0.0,
+            4.0,
+        )),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algorithm::DecoAlgorithmVariant;
+    use crate::deco::DecoStop;
+    use crate::gas::GasMix;
+    use crate::profile::DiveProfile;
+    use crate::tissue::CompartmentSnapshot;
+
+    #[derive(Clone)]
+    struct FixedNdl(u32);
+
+    impl DecoAlgorithm for FixedNdl {
+        fn variant(&self) -> DecoAlgorithmVariant {
+            DecoAlgorithmVariant::Custom("FIXED".to_string())
+        }
+        fn compute_deco_stops(&self, _dive_profile: DiveProfile) -> Vec<DecoStop> {
+            vec![]
+        }
+        fn compute_ndl(&self, _dive_profile: DiveProfile) -> u32 {
+            self.0
+        }
+        fn snapshot(&self) -> Vec<CompartmentSnapshot> {
+            vec![]
+        }
+        fn run(&mut self, _mix: GasMix, _ata: f32, _time: f32) {}
+    }
+
+    fn fixed_entry() -> AlgorithmEntry {
+        AlgorithmEntry::new("FIXED", "Fixed NDL", |params| {
+            Ok(Box::new(FixedNdl(params["ndl"] as u32)))
+        })
+        .alias("fixed-ndl")
+        .param(ParamSpec::new("ndl", "NDL in minutes", 42.0, 0.0, 999.0))
+    }
+
+    #[test]
+    fn test_registry_builtins() {
+        let registry = AlgorithmRegistry::with_builtins();
+
+        assert_eq!(
+            registry.names(),
+            vec!["ZHL16-A", "ZHL16-B", "ZHL16-C", "ZHL12", "DSAT", "Haldane", "Workman", "RGBM"]
+        );
+        assert_eq!(registry.find("zhl16").unwrap().name, "ZHL16-A");
+        assert_eq!(registry.find("zhl16-c").unwrap().params.len(), 3);
+        assert_eq!(registry.find("zh-l12").unwrap().name, "ZHL12");
+        assert!(!registry.implemented_names().contains(&"DSAT".to_string()));
+        assert_eq!(registry.implemented_names().len(), 7);
+    }
+
+    #[test]
+    fn test_registry_register_custom() {
+        let mut registry = AlgorithmRegistry::with_builtins();
+        registry.register(fixed_entry()).unwrap();
+
+        let algo = registry
+            .build("fixed-ndl", &AlgorithmParams::new())
+            .unwrap();
+        assert_eq!(algo.compute_ndl(DiveProfile::new()), 42);
+        assert_eq!(algo.variant().to_string(), "FIXED");
+
+        let params = AlgorithmParams::from([("ndl".to_string(), 7.0)]);
+        let algo = registry.build("FIXED", &params).unwrap();
+        assert_eq!(algo.compute_ndl(DiveProfile::new()), 7);
+    }
+
+    #[test]
+    fn test_registry_duplicate_name() {
+        let mut registry = AlgorithmRegistry::new();
+        registry.register(fixed_entry()).unwrap();
+
+        let err = registry.register(fixed_entry()).unwrap_err();
+
+        assert!(matches!(err, DivesyncError::DuplicateAlgorithm(name) if name == "FIXED"));
+    }
+
+    #[test]
+    fn test_registry_param_validation() {
+        let registry = AlgorithmRegistry::with_builtins();
+
+        let params = AlgorithmParams::from([("gf_low".to_string(), 130.0)]);
+        let err = registry.build("ZHL16-C", &params).err().unwrap();
+        assert!(matches!(err, DivesyncError::ParamOutOfRange(_, _)));
+
+        let params = AlgorithmParams::from([("conservatism".to_string(), 2.0)]);
+        let err = registry.build("ZHL16-C", &params).err().unwrap();
+        assert!(matches!(err, DivesyncError::UnknownParam(_, _)));
+
+        let err = registry.build("VPM-B", &params).err().unwrap();
+        assert!(matches!(err, DivesyncError::UnknownAlgorithm(_)));
+    }
+
+    #[test]
+    fn test_registry_gradient_factors() {
+        let registry = AlgorithmRegistry::with_builtins();
+        let mut profile = DiveProfile::new();
+        profile.add_level(30.0, 0, GasMix::air());
+
+        let raw = registry.build("ZHL16-C", &AlgorithmParams::new()).unwrap();
+        let params =
+            AlgorithmParams::from([("gf_low".to_string(), 30.0), ("gf_high".to_string(), 70.0)]);
+        let conservative = registry.build("ZHL16-C", &params).unwrap();
+
+        assert!(conservative.compute_ndl(profile.clone()) < raw.compute_ndl(profile));
+    }
+
+    #[test]
+    fn test_registry_coefficient_table() {
+        let mut registry = AlgorithmRegistry::with_builtins();
+        let mut table = CoefficientTable::preset(&ZHL16Variant::B);
+        table.name = "ZHL16-B-TEST".to_string();
+        registry
+            .register(zhl16_table_entry(table).unwrap())
+            .unwrap();
+
+        let params = AlgorithmParams::from([("gf_high".to_string(), 80.0)]);
+        let algo = registry.build("zhl16-b-test", &params).unwrap();
+        assert_eq!(algo.variant().to_string(), "ZHL16-B-TEST");
+
+        // preset names are taken by builtins
+        let table = CoefficientTable::preset(&ZHL16Variant::C);
+        let err = registry.register(zhl16_table_entry(table).unwrap());
+        assert!(matches!(err, Err(DivesyncError::DuplicateAlgorithm(_))));
+    }
+
+    #[test]
+    fn test_register_global() {
+        let entry = AlgorithmEntry::new("GLOBAL-TEST", "Test only", |_| Ok(Box::new(FixedNdl(1))));
+        register_algorithm(entry).unwrap();
+
+        assert!(registry().find("global-test").is_some());
+        assert!(registry().find("ZHL16-B").is_some());
+    }
+}
@@ -0,0 +1,486 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::deco::{DecoStop, ASCENT_RATE};
+use crate::error::DivesyncError;
+use crate::gas::{GasMix, AIR_N2};
+use crate::profile::DiveProfile;
+use crate::tables::usn::UsnTables;
+use crate::utils::calc_ata;
+
+/// Half time in minutes of the compartment controlling repetitive groups
+/// in the US Navy tables
+pub const GROUP_HALF_TIME: f32 = 120.0;
+
+/// Nitrogen tension in ata covered by a single group, 2 fsw
+pub const GROUP_WIDTH: f32 = 2.0 / 33.0;
+
+/// Surface interval in minutes after which rounded mode no longer counts a
+/// dive as repetitive
+pub const ROUNDED_CLEAR_INTERVAL: f32 = 720.0;
+
+/// Repetitive group letters, Z is used for everything above O
+pub const GROUP_LETTERS: [char; 16] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'Z',
+];
+
+/// US Navy style repetitive group, A is the lowest residual nitrogen.
+/// Outside of table mode groups are derived from the 120 minute
+/// compartment, not looked up in the published tables, so letters can
+/// differ from the USN designators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RepetitiveGroup(usize);
+
+impl RepetitiveGroup {
+    pub fn from_letter(letter: char) -> Option<Self> {
+        let letter = letter.to_ascii_uppercase();
+        GROUP_LETTERS.iter().position(|l| *l == letter).map(Self)
+    }
+
+    pub fn letter(&self) -> char {
+        GROUP_LETTERS[self.0]
+    }
+
+    /// Position of the group in `GROUP_LETTERS`, 0 for A
+    pub fn index(&self) -> usize {
+        self.0
+    }
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        (index < GROUP_LETTERS.len()).then_some(Self(index))
+    }
+
+    /// Group of a 120 minute compartment Nitrogen tension in ata, `None`
+    /// if the tissue holds no more than surface saturation
+    pub fn from_tension(pp_n2: f32) -> Option<Self> {
+        let excess = pp_n2 - AIR_N2;
+        if excess <= 1e-4 {
+            return None;
+        }
+
+        let index = ((excess / GROUP_WIDTH).ceil() as usize).max(1) - 1;
+        Some(Self(index.min(GROUP_LETTERS.len() - 1)))
+    }
+
+    /// Group when surfacing from a dive following `profile` and ascending
+    /// with `stops` at `ASCENT_RATE`
+    pub fn after_dive(profile: &DiveProfile, stops: &[DecoStop]) -> Option<Self> {
+        Self::from_tension(surfacing_tension(profile, stops))
+    }
+
+    /// Highest tension in ata covered by the group, used when working from
+    /// the letter alone
+    pub fn max_tension(&self) -> f32 {
+        AIR_N2 + (self.0 + 1) as f32 * GROUP_WIDTH
+    }
+
+    /// New group after breathing air at the surface for `minutes`
+    pub fn after_surface_interval(&self, minutes: f32) -> Option<Self> {
+        Self::from_tension(off_gas(self.max_tension(), minutes))
+    }
+
+    /// Minutes at `depth` on air that load the 120 minute compartment to
+    /// the top of the group, `None` if the depth can never reach it
+    pub fn residual_nitrogen_time(&self, depth: f32) -> Option<u32> {
+        residual_nitrogen_time(self.max_tension(), depth)
+    }
+}
+
+/// Rounded mode rounds the tension up to the top of its group after every
+/// dive and surface interval, as if working from the letter, and clears it
+/// after `ROUNDED_CLEAR_INTERVAL`. Tissue mode follows the exact
+/// compartment tension. Table mode looks groups, surface interval credit
+/// and residual nitrogen time up in the loaded USN tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepetitiveMode {
+    Rounded,
+    Tissue,
+    Table,
+}
+
+impl FromStr for RepetitiveMode {
+    type Err = DivesyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rounded" => Ok(RepetitiveMode::Rounded),
+            "tissue" => Ok(RepetitiveMode::Tissue),
+            "table" => Ok(RepetitiveMode::Table),
+            _ => Err(DivesyncError::Parse(format!(
+                "unknown repetitive mode: {s}"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for RepetitiveMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepetitiveMode::Rounded => write!(f, "rounded"),
+            RepetitiveMode::Tissue => write!(f, "tissue"),
+            RepetitiveMode::Table => write!(f, "table"),
+        }
+    }
+}
+
+/// Residual nitrogen of a series of dives and surface intervals
+#[derive(Debug, Clone)]
+pub struct RepetitiveTracker {
+    mode: RepetitiveMode,
+    tension: f32,
+    tables: Option<UsnTables>,
+    table_group: Option<RepetitiveGroup>,
+}
+
+impl RepetitiveTracker {
+    /// Tracker of a mode without tables, table mode needs `with_tables`
+    pub fn new(mode: RepetitiveMode) -> Self {
+        Self {
+            mode,
+            tension: AIR_N2,
+            tables: None,
+            table_group: None,
+        }
+    }
+
+    /// Table mode tracker looking everything up in `tables`
+    pub fn with_tables(tables: UsnTables) -> Self {
+        Self {
+            tables: Some(tables),
+            ..Self::new(RepetitiveMode::Table)
+        }
+    }
+
+    pub fn mode(&self) -> RepetitiveMode {
+        self.mode
+    }
+
+    /// Nitrogen tension in ata of the 120 minute compartment, not tracked
+    /// in table mode
+    pub fn tension(&self) -> f32 {
+        self.tension
+    }
+
+    pub fn group(&self) -> Option<RepetitiveGroup> {
+        match self.mode {
+            RepetitiveMode::Table => self.table_group,
+            _ => RepetitiveGroup::from_tension(self.tension),
+        }
+    }
+
+    /// Dive following `profile` and ascending with `stops`, starting from
+    /// the current residual nitrogen. In table mode the residual nitrogen
+    /// time is added to the bottom time and dives needing stops are
+    /// outside the tables
+    pub fn add_dive(
+        &mut self,
+        profile: &DiveProfile,
+        stops: &[DecoStop],
+    ) -> Result<Option<RepetitiveGroup>, DivesyncError> {
+        if self.mode == RepetitiveMode::Table {
+            let tables = self.tables()?;
+            if !stops.is_empty() {
+                return Err(DivesyncError::OutsideTables(
+                    "decompression dives are not covered by the no-decompression table".to_string(),
+                ));
+            }
+
+            let depth = profile.max_depth();
+            let residual = match self.table_group {
+                Some(group) => tables.residual_nitrogen_time(group, depth)?,
+                None => 0,
+            };
+            let group = tables.group_after_dive(depth, profile.bottom_time() + residual)?;
+
+            self.table_group = Some(group);
+            return Ok(self.table_group);
+        }
+
+        self.tension = dive_tension(self.tension, profile, stops);
+        self.round_to_group();
+        Ok(self.group())
+    }
+
+    /// Breathe air at the surface for `minutes`
+    pub fn add_surface_interval(
+        &mut self,
+        minutes: f32,
+    ) -> Result<Option<RepetitiveGroup>, DivesyncError> {
+        if self.mode == RepetitiveMode::Table {
+            if let Some(group) = self.table_group {
+                self.table_group = self
+                    .tables()?
+                    .group_after_surface_interval(group, minutes)?;
+            }
+            return Ok(self.table_group);
+        }
+
+        self.tension = match self.mode {
+            RepetitiveMode::Rounded if minutes >= ROUNDED_CLEAR_INTERVAL => AIR_N2,
+            _ => off_gas(self.tension, minutes),
+        };
+        self.round_to_group();
+        Ok(self.group())
+    }
+
+    /// Minutes to add to the bottom time of a dive to `depth` on air, 0 if
+    /// there is no residual nitrogen. `None` if the depth can never reach
+    /// the group, or in table mode if the tables have no time for it
+    pub fn residual_nitrogen_time(&self, depth: f32) -> Option<u32> {
+        match (self.mode, self.group()) {
+            (_, None) => Some(0),
+            (RepetitiveMode::Table, Some(group)) => self
+                .tables
+                .as_ref()
+                .and_then(|tables| tables.residual_nitrogen_time(group, depth).ok()),
+            _ => residual_nitrogen_time(self.tension, depth),
+        }
+    }
+
+    fn tables(&self) -> Result<&UsnTables, DivesyncError> {
+        self.tables.as_ref().ok_or_else(|| {
+            DivesyncError::InvalidTables("table mode has no tables loaded".to_string())
+        })
+    }
+
+    fn round_to_group(&mut self) {
+        if self.mode == RepetitiveMode::Rounded {
+            self.tension = self.group().map_or(AIR_N2, |g| g.max_tension());
+        }
+    }
+}
+
+impl Default for RepetitiveTracker {
+    fn default() -> Self {
+        Self::new(RepetitiveMode::Rounded)
+    }
+}
+
+impl fmt::Display for RepetitiveGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+/// Nitrogen tension in ata of the 120 minute compartment at the surface
+pub fn surfacing_tension(profile: &DiveProfile, stops: &[DecoStop]) -> f32 {
+    dive_tension(AIR_N2, profile, stops)
+}
+
+/// Minutes at `depth` on air for the 120 minute compartment to reach
+/// `tension` starting from surface saturation
+pub fn residual_nitrogen_time(tension: f32, depth: f32) -> Option<u32> {
+    let gas_pp = GasMix::air().pp_n2(calc_ata(depth));
+    if tension <= AIR_N2 {
+        return Some(0);
+    }
+    if tension >= gas_pp {
+        return None;
+    }
+
+    let remaining = (gas_pp - tension) / (gas_pp - AIR_N2);
+    Some((-GROUP_HALF_TIME * remaining.log2()).ceil() as u32)
+}
+
+fn dive_tension(mut tension: f32, profile: &DiveProfile, stops: &[DecoStop]) -> f32 {
+    let exposures = profile.exposures();
+
+    for exposure in &exposures {
+        tension = load(
+            tension,
+            &exposure.gas_mix,
+            exposure.mean_depth(),
+            exposure.time,
+        );
+    }
+
+    if let Some(last) = exposures.last() {
+        let mut depth = last.end_depth;
+        let mut mix = &last.gas_mix;
+
+        for stop in stops {
+            let travel = (depth - stop.depth) / ASCENT_RATE;
+            tension = load(tension, mix, (depth + stop.depth) / 2.0, travel);
+
+            mix = &stop.gas_mix;
+            tension = load(tension, mix, stop.depth, stop.time as f32);
+            depth = stop.depth;
+        }
+
+        tension = load(tension, mix, depth / 2.0, depth / ASCENT_RATE);
+    }
+
+    tension
+}
+
+fn off_gas(tension: f32, minutes: f32) -> f32 {
+    load(tension, &GasMix::air(), 0.0, minutes)
+}
+
+fn load(tension: f32, mix: &GasMix, depth: f32, time: f32) -> f32 {
+    let ata = calc_ata(depth);
+    let gas_pp = mix.pp_n2(ata) + mix.pp_ar(ata);
+
+    tension + (gas_pp - tension) * (1.0 - 2_f32.powf(-time / GROUP_HALF_TIME))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tables::usn::FSW_PER_METER;
+
+    #[test]
+    fn test_group_letters() {
+        let group = RepetitiveGroup::from_letter('f').unwrap();
+
+        assert_eq!(group.letter(), 'F');
+        assert_eq!(group.to_string(), "F");
+        assert!(RepetitiveGroup::from_letter('P').is_none());
+        assert!(RepetitiveGroup::from_letter('A') < RepetitiveGroup::from_letter('Z'));
+    }
+
+    #[test]
+    fn test_group_from_tension() {
+        assert_eq!(RepetitiveGroup::from_tension(AIR_N2), None);
+        assert_eq!(
+            RepetitiveGroup::from_tension(AIR_N2 + 0.01)
+                .unwrap()
+                .letter(),
+            'A'
+        );
+        assert_eq!(
+            RepetitiveGroup::from_tension(AIR_N2 + 1.5 * GROUP_WIDTH)
+                .unwrap()
+                .letter(),
+            'B'
+        );
+        assert_eq!(RepetitiveGroup::from_tension(5.0).unwrap().letter(), 'Z');
+    }
+
+    #[test]
+    fn test_group_after_dive() {
+        let short = RepetitiveGroup::after_dive(&DiveProfile::square(18.0, 20, GasMix::air()), &[])
+            .unwrap();
+        let long = RepetitiveGroup::after_dive(&DiveProfile::square(18.0, 50, GasMix::air()), &[])
+            .unwrap();
+        let deep = RepetitiveGroup::after_dive(&DiveProfile::square(30.0, 20, GasMix::air()), &[])
+            .unwrap();
+
+        assert!(short < long);
+        assert!(short < deep);
+    }
+
+    #[test]
+    fn test_surface_interval_lowers_group() {
+        let group = RepetitiveGroup::from_letter('H').unwrap();
+
+        let short = group.after_surface_interval(30.0).unwrap();
+        let long = group.after_surface_interval(240.0).unwrap();
+
+        assert!(short < group);
+        assert!(long < short);
+        assert_eq!(group.after_surface_interval(2000.0), None);
+    }
+
+    #[test]
+    fn test_residual_nitrogen_time() {
+        let group = RepetitiveGroup::from_letter('E').unwrap();
+
+        let shallow = group.residual_nitrogen_time(12.0).unwrap();
+        let deep = group.residual_nitrogen_time(30.0).unwrap();
+
+        assert!(deep < shallow);
+        assert!(deep > 0);
+        assert_eq!(group.residual_nitrogen_time(0.0), None);
+        assert_eq!(residual_nitrogen_time(AIR_N2, 30.0), Some(0));
+    }
+
+    #[test]
+    fn test_residual_nitrogen_time_reaches_group() {
+        let group = RepetitiveGroup::from_letter('F').unwrap();
+        let rnt = group.residual_nitrogen_time(21.0).unwrap();
+
+        let after =
+            RepetitiveGroup::after_dive(&DiveProfile::square(21.0, rnt, GasMix::air()), &[])
+                .unwrap();
+        assert!(after >= RepetitiveGroup::from_letter('E').unwrap());
+    }
+
+    #[test]
+    fn test_tracker_modes() {
+        let profile = DiveProfile::square(18.0, 40, GasMix::air());
+
+        let mut rounded = RepetitiveTracker::new(RepetitiveMode::Rounded);
+        let mut tissue = RepetitiveTracker::new(RepetitiveMode::Tissue);
+
+        assert_eq!(
+            rounded.add_dive(&profile, &[]).unwrap(),
+            tissue.add_dive(&profile, &[]).unwrap()
+        );
+        assert!(rounded.tension() >= tissue.tension());
+
+        rounded.add_surface_interval(60.0).unwrap();
+        tissue.add_surface_interval(60.0).unwrap();
+        assert!(rounded.group() >= tissue.group());
+        assert!(rounded.residual_nitrogen_time(18.0) >= tissue.residual_nitrogen_time(18.0));
+
+        assert_eq!(
+            rounded
+                .add_surface_interval(ROUNDED_CLEAR_INTERVAL)
+                .unwrap(),
+            None
+        );
+        assert_eq!(rounded.residual_nitrogen_time(18.0), Some(0));
+    }
+
+    #[test]
+    fn test_tracker_repetitive_dive() {
+        let profile = DiveProfile::square(18.0, 30, GasMix::air());
+
+        let mut tracker = RepetitiveTracker::new(RepetitiveMode::Tissue);
+        let first = tracker.add_dive(&profile, &[]).unwrap();
+        tracker.add_surface_interval(60.0).unwrap();
+        let second = tracker.add_dive(&profile, &[]).unwrap();
+
+        assert!(second > first);
+        assert_eq!(
+            "ROUNDED".parse::<RepetitiveMode>().unwrap(),
+            RepetitiveMode::Rounded
+        );
+        assert_eq!(
+            "table".parse::<RepetitiveMode>().unwrap(),
+            RepetitiveMode::Table
+        );
+        assert!("usn".parse::<RepetitiveMode>().is_err());
+    }
+
+    #[test]
+    fn test_tracker_table_mode() {
+        let tables =
+            UsnTables::from_toml(include_str!("../tests/data/usn_tables_fixture.toml")).unwrap();
+        let depth = 40.0 / FSW_PER_METER;
+        let group = |letter| RepetitiveGroup::from_letter(letter);
+
+        let mut tracker = RepetitiveTracker::with_tables(tables);
+        let profile = DiveProfile::square(depth, 30, GasMix::air());
+
+        assert_eq!(tracker.add_dive(&profile, &[]).unwrap(), group('B'));
+        assert_eq!(tracker.add_surface_interval(10.0).unwrap(), group('B'));
+        assert_eq!(tracker.residual_nitrogen_time(depth), Some(25));
+
+        // 15 minutes plus 25 minutes of residual nitrogen time
+        let repetitive = DiveProfile::square(depth, 15, GasMix::air());
+        assert_eq!(tracker.add_dive(&repetitive, &[]).unwrap(), group('C'));
+        assert_eq!(tracker.add_surface_interval(200.0).unwrap(), None);
+        assert_eq!(tracker.residual_nitrogen_time(depth), Some(0));
+
+        let long = DiveProfile::square(depth, 90, GasMix::air());
+        assert!(matches!(
+            tracker.add_dive(&long, &[]),
+            Err(DivesyncError::OutsideTables(_))
+        ));
+        assert!(RepetitiveTracker::new(RepetitiveMode::Table)
+            .add_dive(&profile, &[])
+            .is_err());
+    }
+}
@@ -0,0 +1,421 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::algorithm::get_algo_with_params;
+use crate::compare::AlgorithmConfig;
+use crate::deco::{time_to_surface, STOP_INTERVAL};
+use crate::error::DivesyncError;
+use crate::formats::LoggedDive;
+use crate::gas::GasMix;
+use crate::profile::{DiveProfile, Segment};
+use crate::utils::calc_ata;
+
+/// Longest NDL in minutes dive computers display, longer computed limits
+/// are capped before comparing
+pub const NDL_DISPLAY_LIMIT: u32 = 99;
+
+/// Differences up to these are agreement, the ceiling tolerance allows for
+/// computers showing the ceiling and the algorithm its 3m stop
+const NDL_TOLERANCE: u32 = 1;
+const CEILING_TOLERANCE: f32 = STOP_INTERVAL;
+const TTS_TOLERANCE: u32 = 1;
+
+/// Decompression status of the model and of the dive computer at a sample,
+/// times in minutes and depths in meters
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayPoint {
+    /// Seconds from the start of the dive
+    pub time: u32,
+    pub depth: f32,
+    pub ndl: u32,
+    pub ceiling: f32,
+    pub tts: u32,
+    pub logged_ndl: Option<u32>,
+    pub logged_ceiling: Option<f32>,
+    pub logged_tts: Option<u32>,
+}
+
+impl ReplayPoint {
+    /// Model NDL minus logged NDL
+    pub fn ndl_deviation(&self) -> Option<f32> {
+        self.logged_ndl
+            .map(|logged| self.ndl as f32 - logged.min(NDL_DISPLAY_LIMIT) as f32)
+    }
+
+    /// Model ceiling minus logged ceiling
+    pub fn ceiling_deviation(&self) -> Option<f32> {
+        self.logged_ceiling.map(|logged| self.ceiling - logged)
+    }
+
+    /// Model TTS minus logged TTS
+    pub fn tts_deviation(&self) -> Option<f32> {
+        self.logged_tts
+            .map(|logged| self.tts as f32 - logged as f32)
+    }
+
+    /// Whether any deviation is larger than its tolerance
+    pub fn disagrees(&self) -> bool {
+        let beyond =
+            |deviation: Option<f32>, tolerance: f32| deviation.is_some_and(|d| d.abs() > tolerance);
+
+        beyond(self.ndl_deviation(), NDL_TOLERANCE as f32)
+            || beyond(self.ceiling_deviation(), CEILING_TOLERANCE)
+            || beyond(self.tts_deviation(), TTS_TOLERANCE as f32)
+    }
+}
+
+/// Summary of the deviations of one value over all samples that logged it,
+/// `mean` is signed so a positive value means the model is more liberal
+/// for NDL and more conservative for ceiling and TTS
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Deviation {
+    pub samples: usize,
+    pub mean: f32,
+    pub mean_abs: f32,
+    pub max_abs: f32,
+    /// Seconds from the start of the dive of the largest deviation
+    pub max_time: u32,
+}
+
+impl Deviation {
+    fn from_points(points: &[ReplayPoint], value: fn(&ReplayPoint) -> Option<f32>) -> Option<Self> {
+        let deviations: Vec<(u32, f32)> = points
+            .iter()
+            .filter_map(|p| value(p).map(|d| (p.time, d)))
+            .collect();
+
+        if deviations.is_empty() {
+            return None;
+        }
+
+        let count = deviations.len() as f32;
+        let (max_time, max) =
+            deviations
+                .iter()
+                .copied()
+                .fold((0, 0.0_f32), |(time, max), (t, d)| match d.abs() > max {
+                    true => (t, d.abs()),
+                    false => (time, max),
+                });
+
+        Some(Self {
+            samples: deviations.len(),
+            mean: deviations.iter().map(|(_, d)| d).sum::<f32>() / count,
+            mean_abs: deviations.iter().map(|(_, d)| d.abs()).sum::<f32>() / count,
+            max_abs: max,
+            max_time,
+        })
+    }
+}
+
+/// Model status at every sample of a logged dive
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    pub algorithm: String,
+    pub points: Vec<ReplayPoint>,
+}
+
+impl ReplayReport {
+    pub fn ndl_deviation(&self) -> Option<Deviation> {
+        Deviation::from_points(&self.points, ReplayPoint::ndl_deviation)
+    }
+
+    pub fn ceiling_deviation(&self) -> Option<Deviation> {
+        Deviation::from_points(&self.points, ReplayPoint::ceiling_deviation)
+    }
+
+    pub fn tts_deviation(&self) -> Option<Deviation> {
+        Deviation::from_points(&self.points, ReplayPoint::tts_deviation)
+    }
+
+    /// Sum of the mean absolute deviations, lower is a closer match of the
+    /// computer, `None` when nothing was logged to compare
+    pub fn score(&self) -> Option<f32> {
+        let deviations = [
+            self.ndl_deviation(),
+            self.ceiling_deviation(),
+            self.tts_deviation(),
+        ];
+
+        deviations
+            .iter()
+            .flatten()
+            .map(|d| d.mean_abs)
+            .reduce(|a, b| a + b)
+    }
+
+    pub fn to_csv(&self) -> Result<String, DivesyncError> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for point in &self.points {
+            wtr.serialize(point)?;
+        }
+
+        let data = wtr.into_inner().map_err(|e| e.into_error())?;
+        Ok(String::from_utf8_lossy(&data).to_string())
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.algorithm)?;
+
+        let summaries = [
+            ("NDL", "min", self.ndl_deviation()),
+            ("Ceiling", "m", self.ceiling_deviation()),
+            ("TTS", "min", self.tts_deviation()),
+        ];
+        for (name, unit, deviation) in summaries {
+            match deviation {
+                Some(d) => writeln!(
+                    f,
+                    "{name:<8} mean {:+.1}{unit}, max {:.1}{unit} at {}:{:02} over {} samples",
+                    d.mean,
+                    d.max_abs,
+                    d.max_time / 60,
+                    d.max_time % 60,
+                    d.samples
+                )?,
+                None => writeln!(f, "{name:<8} not logged")?,
+            }
+        }
+
+        let disagreements: Vec<&ReplayPoint> =
+            self.points.iter().filter(|p| p.disagrees()).collect();
+        if disagreements.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>6} {:>5} | {:>9} | {:>11} | {:>9}",
+            "Time", "Depth", "NDL", "Ceiling", "TTS"
+        )?;
+
+        let logged = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        for point in disagreements {
+            writeln!(
+                f,
+                "{:>3}:{:02} {:>5.1} | {:>4}/{:<4} | {:>5.1}/{:<5} | {:>4}/{:<4}",
+                point.time / 60,
+                point.time % 60,
+                point.depth,
+                point.ndl,
+                logged(point.logged_ndl.map(|v| v.to_string())),
+                point.ceiling,
+                logged(point.logged_ceiling.map(|v| format!("{v:.1}"))),
+                point.tts,
+                logged(point.logged_tts.map(|v| v.to_string())),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Run the samples of `dive` through the algorithm of `config` and
+/// compute NDL, ceiling and TTS at every sample, depths are converted to
+/// equivalent sea level depths for the dive's altitude and water. The
+/// tissues are advanced one segment at a time and each sample's status is
+/// computed from them, so the dive is loaded once
+pub fn replay(dive: &LoggedDive, config: &AlgorithmConfig) -> Result<ReplayReport, DivesyncError> {
+    let mut algo = get_algo_with_params(&config.name, &config.params)?;
+    let environment = dive.environment();
+    let profile = environment.equivalent_profile(&dive.to_profile()?);
+
+    // air saturated tissues, as compute_* start from on a fresh algorithm
+    algo.run(GasMix::air(), calc_ata(0.0), 0.0);
+
+    let mut samples = dive.samples.iter();
+    let mut points = vec![];
+    let mut minutes = 0.0;
+    let mut max_depth: f32 = 0.0;
+
+    for (segment, exposure) in profile.segments.iter().zip(profile.exposures()) {
+        algo.run(
+            exposure.gas_mix.clone(),
+            calc_ata(exposure.mean_depth()),
+            exposure.time,
+        );
+        minutes += exposure.time;
+        max_depth = max_depth.max(exposure.start_depth).max(exposure.end_depth);
+
+        if matches!(segment, Segment::GasSwitch { .. }) {
+            continue;
+        }
+
+        let time = (minutes * 60.0).round() as u32;
+        let Some(sample) = samples.find(|s| s.time >= time) else {
+            break;
+        };
+
+        let depth = exposure.end_depth;
+        let status = DiveProfile::status(max_depth, depth, exposure.gas_mix, &profile.deco_mixes);
+
+        let stops = algo.compute_deco_stops(status.clone());
+        let ndl = match stops.is_empty() {
+            true => algo.compute_ndl(status).min(NDL_DISPLAY_LIMIT),
+            false => 0,
+        };
+        let ceiling = stops.iter().map(|s| s.depth).fold(0.0, f32::max);
+
+        points.push(ReplayPoint {
+            time,
+            depth: sample.depth,
+            ndl,
+            ceiling: environment.actual_depth(ceiling),
+            tts: time_to_surface(depth, &stops),
+            logged_ndl: sample.ndl,
+            logged_ceiling: sample.ceiling,
+            logged_tts: sample.tts,
+        });
+    }
+
+    Ok(ReplayReport {
+        algorithm: config.label.clone(),
+        points,
+    })
+}
+
+/// Replay `dive` with every config, closest match to the computer first
+/// and configs without anything logged to compare last
+pub fn rank(
+    dive: &LoggedDive,
+    configs: &[AlgorithmConfig],
+) -> Result<Vec<ReplayReport>, DivesyncError> {
+    let mut reports = configs
+        .iter()
+        .map(|config| replay(dive, config))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    reports.sort_by(|a, b| {
+        let score = |r: &ReplayReport| r.score().unwrap_or(f32::INFINITY);
+        score(a).total_cmp(&score(b))
+    });
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::formats::Sample;
+
+    /// Square dive to 40m with the status logged by a computer running
+    /// `config`
+    fn logged_dive(config: &AlgorithmConfig) -> LoggedDive {
+        let mut dive = LoggedDive {
+            samples: vec![
+                Sample::new(120, 40.0),
+                Sample::new(600, 40.0),
+                Sample::new(1200, 40.0),
+                Sample::new(1440, 6.0),
+            ],
+            ..Default::default()
+        };
+
+        let report = replay(&dive, config).unwrap();
+        for (sample, point) in dive.samples.iter_mut().zip(&report.points) {
+            sample.ndl = Some(point.ndl);
+            sample.ceiling = Some(point.ceiling);
+            sample.tts = Some(point.tts);
+        }
+
+        dive
+    }
+
+    #[test]
+    fn test_replay_points() {
+        let config: AlgorithmConfig = "zhl16-c".parse().unwrap();
+        let dive = LoggedDive {
+            samples: vec![Sample::new(120, 40.0), Sample::new(1500, 40.0)],
+            ..Default::default()
+        };
+
+        let report = replay(&dive, &config).unwrap();
+        let first = &report.points[0];
+        let last = &report.points[1];
+
+        assert_eq!(report.points.len(), 2);
+        assert!(first.ndl > 0 && first.ceiling == 0.0);
+        assert_eq!(last.ndl, 0);
+        assert!(last.ceiling >= STOP_INTERVAL);
+        assert!(last.tts > 4);
+        assert!(report.ndl_deviation().is_none());
+        assert!(report.score().is_none());
+    }
+
+    #[test]
+    fn test_replay_matches_whole_profile() {
+        let config: AlgorithmConfig = "zhl16-c:gf_low=30,gf_high=70".parse().unwrap();
+        let dive = LoggedDive {
+            samples: vec![
+                Sample::new(120, 40.0),
+                Sample::new(1200, 40.0),
+                Sample::new(1320, 12.0),
+            ],
+            ..Default::default()
+        };
+        let algo = get_algo_with_params(&config.name, &config.params).unwrap();
+        let profile = dive.to_profile().unwrap();
+
+        let report = replay(&dive, &config).unwrap();
+
+        // same stops as loading the dive up to each sample from the surface
+        for point in &report.points {
+            let mut prefix = DiveProfile::new();
+            for segment in &profile.segments {
+                if prefix.duration() >= point.time {
+                    break;
+                }
+                prefix.add_segment(segment.clone());
+            }
+
+            let stops = algo.compute_deco_stops(prefix);
+            let ceiling = stops.iter().map(|s| s.depth).fold(0.0, f32::max);
+            assert_eq!(point.ceiling, ceiling);
+        }
+        assert!(report.points.iter().any(|p| p.ceiling > 0.0));
+    }
+
+    #[test]
+    fn test_replay_deviations() {
+        let computer: AlgorithmConfig = "zhl16-c:gf_low=30,gf_high=70".parse().unwrap();
+        let dive = logged_dive(&computer);
+
+        let same = replay(&dive, &computer).unwrap();
+        assert_eq!(same.score(), Some(0.0));
+        assert!(!same.points.iter().any(|p| p.disagrees()));
+
+        let liberal = replay(&dive, &"zhl16-c".parse().unwrap()).unwrap();
+        let tts = liberal.tts_deviation().unwrap();
+        assert!(liberal.score().unwrap() > 0.0);
+        assert!(tts.mean < 0.0);
+        assert!(tts.max_time >= 1200);
+        assert!(liberal.to_string().contains("TTS"));
+        assert_eq!(
+            liberal.to_csv().unwrap().lines().count(),
+            liberal.points.len() + 1
+        );
+    }
+
+    #[test]
+    fn test_rank_configs() {
+        let computer: AlgorithmConfig = "zhl16-c:gf_low=40,gf_high=85".parse().unwrap();
+        let dive = logged_dive(&computer);
+        let configs: Vec<AlgorithmConfig> = [
+            "zhl16-c",
+            "zhl16-c:gf_low=30,gf_high=70",
+            "zhl16-c:gf_low=40,gf_high=85",
+        ]
+        .iter()
+        .map(|c| c.parse().unwrap())
+        .collect();
+
+        let reports = rank(&dive, &configs).unwrap();
+
+        assert_eq!(reports[0].algorithm, computer.label);
+        assert_eq!(reports[0].score(), Some(0.0));
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{
+    algorithm::{DecoAlgorithm, DecoAlgorithmVariant},
+    deco::{time_to_surface, DecoStop, GradientFactors, ASCENT_RATE},
+    gas::GasMix,
+    profile::DiveProfile,
+    tissue::CompartmentSnapshot,
+    utils::calc_ata,
+    zhl16::{algorithm::ZHL16Algorithm, tissue::ZHL16Variant},
+};
+
+/// Largest reduction of the allowed gradient right after a previous dive
+pub const REPETITIVE_REDUCTION: f32 = 0.25;
+
+/// Time constant in minutes at which bubbles of a previous dive dissolve
+pub const REPETITIVE_REGENERATION: f32 = 120.0;
+
+/// Largest reduction for a dive deeper than the previous one
+pub const REVERSE_REDUCTION: f32 = 0.2;
+
+/// Reduction added by every recent dive of a multi-day series
+pub const MULTI_DAY_REDUCTION: f32 = 0.03;
+
+/// Upper bound of the multi-day reduction
+pub const MULTI_DAY_MAX_REDUCTION: f32 = 0.15;
+
+/// Time constant in minutes of the multi-day reduction, one week
+pub const MULTI_DAY_REGENERATION: f32 = 7.0 * 1440.0;
+
+/// Reduction of the allowed gradient per conservatism level
+pub const CONSERVATISM_STEP: f32 = 0.05;
+
+/// Dive done before the current one, `surface_interval` is the time in
+/// minutes spent at the surface after it
+#[derive(Debug, Clone)]
+pub struct PreviousDive {
+    pub max_depth: f32,
+    pub duration: u32,
+    pub surface_interval: u32,
+}
+
+/// Multipliers of the allowed gradient, 1.0 is the unreduced M-value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BubbleFactors {
+    pub repetitive: f32,
+    pub reverse: f32,
+    pub multi_day: f32,
+    pub conservatism: f32,
+}
+
+impl BubbleFactors {
+    pub fn total(&self) -> f32 {
+        self.repetitive * self.reverse * self.multi_day * self.conservatism
+    }
+}
+
+/// Reduced gradient model in the style of RGBM recreational computers,
+/// ZHL16-C tissues with M-values cut by bubble factors derived from the
+/// dive history
+#[derive(Clone)]
+pub struct RGBMAlgorithm {
+    zhl: ZHL16Algorithm,
+    history: Vec<PreviousDive>,
+    conservatism: u32,
+}
+
+impl DecoAlgorithm for RGBMAlgorithm {
+    fn variant(&self) -> DecoAlgorithmVariant {
+        DecoAlgorithmVariant::Rgbm
+    }
+
+    fn compute_deco_stops(&self, dive_profile: DiveProfile) -> Vec<DecoStop> {
+        self.reduced(&dive_profile).compute_deco_stops(dive_profile)
+    }
+
+    fn compute_ndl(&self, dive_profile: DiveProfile) -> u32 {
+        self.reduced(&dive_profile).compute_ndl(dive_profile)
+    }
+
+    fn snapshot(&self) -> Vec<CompartmentSnapshot> {
+        let variant = self.variant().to_string();
+
+        self.zhl
+            .snapshot()
+            .into_iter()
+            .map(|s| CompartmentSnapshot {
+                variant: variant.clone(),
+                ..s
+            })
+            .collect()
+    }
+
+    fn run(&mut self, mix: GasMix, ata: f32, time: f32) {
+        self.zhl.run(mix, ata, time)
+    }
+
+    fn run_travel(&mut self, mix: GasMix, start_ata: f32, end_ata: f32, time: f32) {
+        self.zhl.run_travel(mix, start_ata, end_ata, time)
+    }
+}
+
+impl RGBMAlgorithm {
+    pub fn new() -> Self {
+        Self {
+            zhl: ZHL16Algorithm::new(ZHL16Variant::C),
+            history: vec![],
+            conservatism: 0,
+        }
+    }
+
+    /// Set conservatism level, each level reduces the allowed gradient
+    /// by `CONSERVATISM_STEP`
+    pub fn set_conservatism(&mut self, conservatism: u32) {
+        self.conservatism = conservatism;
+    }
+
+    pub fn history(&self) -> &[PreviousDive] {
+        &self.history
+    }
+
+    /// Add a dive done before the current one, tissues are loaded with the
+    /// dive, its ascent at `ASCENT_RATE` through the stops and the
+    /// following surface interval on air
+    pub fn add_previous_dive(&mut self, profile: DiveProfile, surface_interval: u32) {
+        let stops = self.compute_deco_stops(profile.clone());
+        let exposures = profile.exposures();
+
+        for exposure in &exposures {
+            self.zhl.run(
+                exposure.gas_mix.clone(),
+                calc_ata(exposure.mean_depth()),
+                exposure.time,
+            );
+        }
+
+        let ascent_depth = exposures.last().map_or(0.0, |e| e.end_depth);
+        if let Some(last) = exposures.last() {
+            let mut depth = last.end_depth;
+            let mut mix = last.gas_mix.clone();
+
+            for stop in &stops {
+                let travel = (depth - stop.depth) / ASCENT_RATE;
+                self.zhl
+                    .run(mix, calc_ata((depth + stop.depth) / 2.0), travel);
+
+                mix = stop.gas_mix.clone();
+                self.zhl
+                    .run(mix.clone(), calc_ata(stop.depth), stop.time as f32);
+                depth = stop.depth;
+            }
+
+            self.zhl
+                .run(mix, calc_ata(depth / 2.0), depth / ASCENT_RATE);
+        }
+        self.zhl.run(GasMix::air(), 1.0, surface_interval as f32);
+
+        self.history.push(PreviousDive {
+            max_depth: profile.max_depth(),
+            duration: profile.bottom_time() + time_to_surface(ascent_depth, &stops),
+            surface_interval,
+        });
+    }
+
+    /// Bubble factors applied to a dive following `dive_profile`. Reverse
+    /// profiles are only detected against the previous dive, a deeper
+    /// excursion later in the same dive is not reduced
+    pub fn bubble_factors(&self, dive_profile: &DiveProfile) -> BubbleFactors {
+        let conservatism = (1.0 - CONSERVATISM_STEP * self.conservatism as f32).max(0.0);
+
+        let Some(last) = self.history.last() else {
+            return BubbleFactors {
+                repetitive: 1.0,
+                reverse: 1.0,
+                multi_day: 1.0,
+                conservatism,
+            };
+        };
+
+        let decay = (-(last.surface_interval as f32) / REPETITIVE_REGENERATION).exp();
+        let repetitive = 1.0 - REPETITIVE_REDUCTION * decay;
+
+        let depth = dive_profile.max_depth();
+        let reverse = match depth > last.max_depth {
+            true => 1.0 - REVERSE_REDUCTION * decay * (depth - last.max_depth) / depth,
+            false => 1.0,
+        };
+
+        // minutes since surfacing from each previous dive, most recent first
+        let mut age = 0.0;
+        let mut recent = 0.0;
+        for (i, dive) in self.history.iter().enumerate().rev() {
+            age += dive.surface_interval as f32;
+            recent += (-age / MULTI_DAY_REGENERATION).exp();
+            if i > 0 {
+                age += dive.duration as f32;
+            }
+        }
+        let multi_day = 1.0 - (MULTI_DAY_REDUCTION * recent).min(MULTI_DAY_MAX_REDUCTION);
+
+        BubbleFactors {
+            repetitive,
+            reverse,
+            multi_day,
+            conservatism,
+        }
+    }
+
+    /// Copy of the tissue model with gradient reduced by the bubble factors
+    fn reduced(&self, dive_profile: &DiveProfile) -> ZHL16Algorithm {
+        let factor = self.bubble_factors(dive_profile).total();
+
+        let mut zhl = self.zhl.clone();
+        zhl.set_gradient_factors(GradientFactors::new(factor, factor));
+        zhl
+    }
+}
+
+impl Default for RGBMAlgorithm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_first_dive_matches_zhl16c() {
+        let rgbm = RGBMAlgorithm::new();
+        let zhl = ZHL16Algorithm::new(ZHL16Variant::C);
+        let profile = DiveProfile::square(30.0, 0, GasMix::air());
+
+        assert_eq!(rgbm.bubble_factors(&profile).total(), 1.0);
+        assert_eq!(rgbm.compute_ndl(profile.clone()), zhl.compute_ndl(profile));
+    }
+
+    #[test]
+    fn test_repetitive_dive_reduced() {
+        let profile = DiveProfile::square(25.0, 0, GasMix::air());
+        let mut short_si = RGBMAlgorithm::new();
+        short_si.add_previous_dive(DiveProfile::square(25.0, 20, GasMix::air()), 30);
+        let mut long_si = RGBMAlgorithm::new();
+        long_si.add_previous_dive(DiveProfile::square(25.0, 20, GasMix::air()), 600);
+
+        let short = short_si.bubble_factors(&profile);
+        let long = long_si.bubble_factors(&profile);
+        assert!(short.repetitive < long.repetitive);
+        assert!(long.repetitive > 0.99);
+
+        // residual nitrogen and bubble factor both shorten the limit
+        let first = RGBMAlgorithm::new().compute_ndl(profile.clone());
+        assert!(short_si.compute_ndl(profile.clone()) < long_si.compute_ndl(profile.clone()));
+        assert!(long_si.compute_ndl(profile) <= first);
+    }
+
+    #[test]
+    fn test_previous_dive_includes_ascent() {
+        let mut algo = RGBMAlgorithm::new();
+        algo.add_previous_dive(DiveProfile::square(18.0, 30, GasMix::air()), 0);
+
+        let mut zhl = ZHL16Algorithm::new(ZHL16Variant::C);
+        zhl.run(GasMix::air(), calc_ata(18.0), 30.0);
+        zhl.run(GasMix::air(), calc_ata(9.0), 18.0 / ASCENT_RATE);
+
+        let loaded: Vec<f32> = algo.snapshot().iter().map(|s| s.pp_n2).collect();
+        let expected: Vec<f32> = zhl.snapshot().iter().map(|s| s.pp_n2).collect();
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_reverse_profile_reduced() {
+        let mut algo = RGBMAlgorithm::new();
+        algo.add_previous_dive(DiveProfile::square(18.0, 30, GasMix::air()), 60);
+
+        let shallower = algo.bubble_factors(&DiveProfile::square(15.0, 0, GasMix::air()));
+        let deeper = algo.bubble_factors(&DiveProfile::square(30.0, 0, GasMix::air()));
+
+        assert_eq!(shallower.reverse, 1.0);
+        assert!(deeper.reverse < 1.0);
+    }
+
+    #[test]
+    fn test_multi_day_reduced() {
+        let mut algo = RGBMAlgorithm::new();
+        for _ in 0..6 {
+            algo.add_previous_dive(DiveProfile::square(20.0, 30, GasMix::air()), 720);
+        }
+
+        let factors = algo.bubble_factors(&DiveProfile::square(20.0, 0, GasMix::air()));
+        assert!(factors.multi_day < 1.0);
+        assert!(factors.multi_day >= 1.0 - MULTI_DAY_MAX_REDUCTION);
+        assert_eq!(algo.history().len(), 6);
+    }
+
+    #[test]
+    fn test_conservatism() {
+        let profile = DiveProfile::square(30.0, 0, GasMix::air());
+        let base = RGBMAlgorithm::new();
+        let mut conservative = RGBMAlgorithm::new();
+        conservative.set_conservatism(2);
+
+        assert_eq!(conservative.bubble_factors(&profile).conservatism, 0.9);
+        assert!(conservative.compute_ndl(profile.clone()) < base.compute_ndl(profile));
+    }
+}
@@ -1,77 +1,348 @@
 use csv::Writer;
-use std::env;
-use std::error::Error;
 use std::fmt;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io;
 
 use crate::algorithm::DecoAlgorithm;
-use crate::profile::DiveProfile;
+use crate::error::DivesyncError;
+use crate::gas::GasMix;
+use crate::profile::{DiveProfile, Exposure};
 use crate::tissue::CompartmentSnapshot;
 use crate::utils::calc_ata;
 use crate::utils::home_dir;
 use crate::utils::timestamp;
 
+/// Remainder in minutes below which an exposure is considered complete
+const TIME_EPSILON: f32 = 1e-4;
+
+/// Longest integration step in minutes while the depth changes, constant
+/// depths are integrated exactly in one step. Algorithms with an exact
+/// `run_travel` give the same result for any step
+pub const DEFAULT_STEP: f32 = 1.0 / 6.0;
+
+/// Reason a step ends before the next output interval
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepEvent {
+    /// End of a profile segment
+    SegmentEnd,
+    /// End of a segment followed by a different breathing gas
+    GasSwitch,
+    /// End of an ascent reaching the surface
+    Surfaced,
+}
+
+impl fmt::Display for StepEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepEvent::SegmentEnd => write!(f, "segment end"),
+            StepEvent::GasSwitch => write!(f, "gas switch"),
+            StepEvent::Surfaced => write!(f, "surfaced"),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AlgorithmRunResult {
+    /// Seconds between output steps
     pub interval_period: u32,
     pub snapshots: Vec<Vec<CompartmentSnapshot>>,
+    /// Depth at the end of each snapshot, snapshots only record the depth
+    /// of the last integration step
+    pub depths: Vec<f32>,
+}
+
+impl AlgorithmRunResult {
+    pub fn new(interval_period: u32) -> Self {
+        Self {
+            interval_period,
+            snapshots: vec![],
+            depths: vec![],
+        }
+    }
+
+    pub fn add_step(&mut self, step: RunStep) {
+        self.depths.push(step.depth);
+        self.snapshots.push(step.snapshot);
+    }
+}
+
+/// State at an output interval or event of a run, `elapsed_time` in
+/// minutes since the start of the run and `depth` at that time
+#[derive(Clone, Debug)]
+pub struct RunStep {
+    pub index: usize,
+    pub elapsed_time: f32,
+    pub depth: f32,
+    pub ambient_pressure: f32,
+    pub gas_mix: GasMix,
+    pub event: Option<StepEvent>,
+    pub snapshot: Vec<CompartmentSnapshot>,
+}
+
+impl RunStep {
+    /// Number of the compartment with the highest inert gas pressure
+    pub fn leading_compartment(&self) -> Option<usize> {
+        self.snapshot
+            .iter()
+            .max_by(|a, b| inert_pressure(a).total_cmp(&inert_pressure(b)))
+            .map(|cpt| cpt.cpt_num)
+    }
+
+    /// Highest inert gas pressure of a compartment over the ambient
+    /// pressure, above 1 the tissue is supersaturated
+    pub fn supersaturation(&self) -> f32 {
+        let max = self.snapshot.iter().map(inert_pressure).fold(0.0, f32::max);
+        max / self.ambient_pressure
+    }
+}
+
+fn inert_pressure(cpt: &CompartmentSnapshot) -> f32 {
+    cpt.pp_n2 + cpt.pp_he
+}
+
+/// Iterator over the steps of a run, the algorithm is only advanced as
+/// steps are taken
+pub struct RunSteps<'a> {
+    algo: &'a mut Box<dyn DecoAlgorithm>,
+    exposures: Vec<Exposure>,
+    interval: f32,
+    step: f32,
+    exposure: usize,
+    elapsed: f32,
+    time: f32,
+    outputs: u32,
+    index: usize,
+}
+
+impl RunSteps<'_> {
+    /// Load `time` minutes of `exposure` from `elapsed`, depth changes are
+    /// split into steps no longer than `step` run as travel between their
+    /// start and end depths
+    fn integrate(&mut self, exposure: &Exposure, elapsed: f32, time: f32) {
+        let rate = (exposure.end_depth - exposure.start_depth) / exposure.time;
+        if rate == 0.0 {
+            self.algo
+                .run(exposure.gas_mix.clone(), calc_ata(exposure.end_depth), time);
+            return;
+        }
+
+        let count = (time / self.step - TIME_EPSILON).ceil().max(1.0) as u32;
+        let step = time / count as f32;
+
+        for i in 0..count {
+            let start = exposure.start_depth + rate * (elapsed + step * i as f32);
+            self.algo.run_travel(
+                exposure.gas_mix.clone(),
+                calc_ata(start),
+                calc_ata(start + rate * step),
+                step,
+            );
+        }
+    }
+
+    /// Event at the end of the current exposure
+    fn event(&self, exposure: &Exposure) -> StepEvent {
+        let next = self.exposures.get(self.exposure + 1);
+        match next {
+            Some(next) if next.gas_mix != exposure.gas_mix => StepEvent::GasSwitch,
+            _ if exposure.end_depth <= 0.0 && exposure.start_depth > 0.0 => StepEvent::Surfaced,
+            _ => StepEvent::SegmentEnd,
+        }
+    }
+}
+
+impl Iterator for RunSteps<'_> {
+    type Item = RunStep;
+
+    // run every exposure up to the next output interval or its end,
+    // whichever comes first
+    fn next(&mut self) -> Option<RunStep> {
+        loop {
+            let exposure = self.exposures.get(self.exposure)?.clone();
+            let remaining = exposure.time - self.elapsed;
+            if remaining <= TIME_EPSILON {
+                self.exposure += 1;
+                self.elapsed = 0.0;
+                continue;
+            }
+
+            let until_output = (self.outputs + 1) as f32 * self.interval - self.time;
+            let (time, event) = match remaining - until_output > TIME_EPSILON {
+                true => (until_output, None),
+                false => (remaining, Some(self.event(&exposure))),
+            };
+
+            self.integrate(&exposure, self.elapsed, time);
+            self.elapsed += time;
+            self.time += time;
+            if (self.outputs + 1) as f32 * self.interval - self.time <= TIME_EPSILON {
+                self.outputs += 1;
+            }
+            self.index += 1;
+
+            let rate = (exposure.end_depth - exposure.start_depth) / exposure.time;
+            let depth = exposure.start_depth + rate * self.elapsed;
+
+            return Some(RunStep {
+                index: self.index - 1,
+                elapsed_time: self.time,
+                depth,
+                ambient_pressure: calc_ata(depth),
+                gas_mix: exposure.gas_mix,
+                event,
+                snapshot: self.algo.snapshot(),
+            });
+        }
+    }
+}
+
+/// Statistics of a run kept without its snapshots, `max_supersaturation_time`
+/// in minutes
+#[derive(Clone, Debug, Default)]
+pub struct RunSummary {
+    pub steps: usize,
+    pub duration: f32,
+    pub max_depth: f32,
+    pub max_supersaturation: f32,
+    pub max_supersaturation_time: f32,
+    pub leading_compartment: Option<usize>,
+}
+
+impl RunSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, step: &RunStep) {
+        self.steps += 1;
+        self.duration = step.elapsed_time;
+        self.max_depth = self.max_depth.max(step.depth);
+
+        let supersaturation = step.supersaturation();
+        if supersaturation > self.max_supersaturation {
+            self.max_supersaturation = supersaturation;
+            self.max_supersaturation_time = step.elapsed_time;
+            self.leading_compartment = step.leading_compartment();
+        }
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} steps over {:.1} min, max depth {:.1}m, max supersaturation {:.2}",
+            self.steps, self.duration, self.max_depth, self.max_supersaturation
+        )?;
+        if let Some(cpt) = self.leading_compartment {
+            write!(
+                f,
+                " in compartment {cpt} at {:.1} min",
+                self.max_supersaturation_time
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the compartments of each step as CSV rows as they are run
+pub struct StepCsvWriter<W: io::Write> {
+    writer: Writer<W>,
+}
+
+impl<W: io::Write> StepCsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Writer::from_writer(writer),
+        }
+    }
+
+    pub fn write(&mut self, step: &RunStep) -> Result<(), DivesyncError> {
+        for cpt in &step.snapshot {
+            self.writer.serialize(cpt)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), DivesyncError> {
+        self.writer.flush()?;
+        Ok(())
+    }
 }
 
 pub struct AlgorithmRunner {
     algo: Box<dyn DecoAlgorithm>,
+    step: f32,
     result: Option<AlgorithmRunResult>,
 }
 
 impl AlgorithmRunner {
     pub fn new(algo: Box<dyn DecoAlgorithm>) -> Self {
-        Self { algo, result: None }
+        Self {
+            algo,
+            step: DEFAULT_STEP,
+            result: None,
+        }
+    }
+
+    /// Longest integration step in minutes while the depth changes,
+    /// independent of the output interval
+    pub fn set_step(&mut self, step: f32) {
+        self.step = step;
     }
 
     /// Run the algorithm given profile
     /// end result returns resultant TissueCompartments
-    pub fn run(&mut self, interval_period: u32, dive_profile: DiveProfile) -> AlgorithmRunResult {
-        let mut snapshots = vec![];
-
-        // calculate number of interval periods in dive profile
-        for level in dive_profile.levels {
-            let steps = level.time / interval_period;
-            let remainder = level.time % interval_period;
-
-            for _ in 0..steps {
-                self.algo.run(
-                    level.gas_mix.clone(),
-                    calc_ata(level.depth),
-                    interval_period as f32,
-                );
-
-                snapshots.push(self.algo.snapshot());
-            }
-
-            if remainder > 0 {
-                self.algo.run(
-                    level.gas_mix.clone(),
-                    calc_ata(level.depth),
-                    remainder as f32,
-                );
-
-                snapshots.push(self.algo.snapshot());
-            }
+    pub fn run(
+        &mut self,
+        interval_period: u32,
+        dive_profile: DiveProfile,
+    ) -> Result<AlgorithmRunResult, DivesyncError> {
+        let mut result = AlgorithmRunResult::new(interval_period);
+        for step in self.steps(interval_period, &dive_profile)? {
+            result.add_step(step);
         }
 
-        let result = AlgorithmRunResult {
-            interval_period,
-            snapshots,
-        };
-
         self.result = Some(result.clone());
 
-        result
+        Ok(result)
+    }
+
+    /// Run `dive_profile` with a step every `interval_period` seconds and
+    /// at every segment end, nothing is kept by the runner so callers can
+    /// stream, summarize or stop early
+    pub fn steps(
+        &mut self,
+        interval_period: u32,
+        dive_profile: &DiveProfile,
+    ) -> Result<RunSteps<'_>, DivesyncError> {
+        if interval_period == 0 {
+            return Err(DivesyncError::InvalidInterval(interval_period));
+        }
+        if self.step <= 0.0 || !self.step.is_finite() {
+            return Err(DivesyncError::ParamOutOfRange(
+                "step".to_string(),
+                self.step,
+            ));
+        }
+        dive_profile.validate()?;
+
+        Ok(RunSteps {
+            algo: &mut self.algo,
+            exposures: dive_profile.exposures(),
+            interval: interval_period as f32 / 60.0,
+            step: self.step,
+            exposure: 0,
+            elapsed: 0.0,
+            time: 0.0,
+            outputs: 0,
+            index: 0,
+        })
     }
 
-    pub fn save_results(&self) -> Result<String, Box<dyn Error>> {
-        let ts: u64 = timestamp();
-        let data_dir = Path::new(&home_dir())
+    pub fn save_results(&self) -> Result<String, DivesyncError> {
+        let ts: u64 = timestamp()?;
+        let data_dir = home_dir()?
             .join(".divesync")
             .join("data")
             .join(format!("{ts}"));
@@ -84,14 +355,14 @@ impl AlgorithmRunner {
         if let Some(result) = &self.result {
             for interval in &result.snapshots {
                 for cpt in interval {
-                    wtr.serialize(&cpt)?;
+                    wtr.serialize(cpt)?;
                 }
             }
 
             wtr.flush()?;
         }
 
-        Ok(filename.to_str().unwrap().to_string())
+        Ok(filename.to_string_lossy().to_string())
     }
 
     pub fn result(&self) -> Option<AlgorithmRunResult> {
@@ -99,22 +370,43 @@ impl AlgorithmRunner {
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
     use crate::{
-        gas::{GasMix, PPO2},
+        gas::GasMix,
+        profile::{DiveProfileBuilder, Segment},
+        utils::round_f32,
         zhl16::{algorithm::ZHL16Algorithm, tissue::ZHL16Variant},
     };
 
+    /// Descent to 40m, 10min bottom, 4min ascent to the surface on EAN32
+    fn ascent_profile() -> DiveProfile {
+        DiveProfileBuilder::new(GasMix::air())
+            .descend(40.0, 20.0)
+            .hold(10 * 60)
+            .switch_gas(GasMix::new_nitrox(0.32).unwrap(), 0)
+            .ascend(0.0, 10.0)
+            .build()
+            .unwrap()
+    }
+
+    fn final_pp_n2(interval_period: u32, step: f32) -> f32 {
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        runner.set_step(step);
+        let result = runner.run(interval_period, ascent_profile()).unwrap();
+        result.snapshots.last().unwrap()[0].pp_n2
+    }
+
     #[test]
     fn test_algorithm_runner_steps() {
         let algo = ZHL16Algorithm::new(ZHL16Variant::A);
         let mut runner = AlgorithmRunner::new(Box::new(algo));
         let mut profile = DiveProfile::new();
-        let mix = GasMix::new_nitrox(PPO2);
+        let mix = GasMix::air();
         profile.add_level(20.0, 20, mix);
 
-        runner.run(3, profile);
+        runner.run(180, profile).unwrap();
         let res = runner.result();
 
         assert!(res.is_some());
@@ -129,10 +421,10 @@ mod test {
         let algo = ZHL16Algorithm::new(ZHL16Variant::A);
         let mut runner = AlgorithmRunner::new(Box::new(algo));
         let mut profile = DiveProfile::new();
-        let mix = GasMix::new_nitrox(PPO2);
+        let mix = GasMix::air();
         profile.add_level(20.0, 38, mix);
 
-        runner.run(7, profile);
+        runner.run(420, profile).unwrap();
         let res = runner.result();
 
         assert!(res.is_some());
@@ -143,17 +435,187 @@ mod test {
         assert_eq!(result.snapshots.last().unwrap()[0].elapsed_time, 38.0);
     }
 
+    #[test]
+    fn test_algorithm_runner_segments() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::A);
+        let mut runner = AlgorithmRunner::new(Box::new(algo));
+        let profile = DiveProfileBuilder::new(GasMix::air())
+            .descend(20.0, 20.0)
+            .hold(90)
+            .switch_gas(GasMix::new_nitrox(0.32).unwrap(), 30)
+            .build()
+            .unwrap();
+
+        let result = runner.run(60, profile).unwrap();
+
+        // 1min descent, 1min and 30s hold, 30s gas switch, the descent is
+        // run as travel ending at the depth reached
+        assert_eq!(result.snapshots.len(), 4);
+        assert_eq!(round_f32(result.snapshots[0][0].last_depth, 1), 20.0);
+        assert_eq!(result.snapshots[2][0].elapsed_time, 2.5);
+        assert_eq!(result.snapshots.last().unwrap()[0].elapsed_time, 3.0);
+    }
+
+    #[test]
+    fn test_algorithm_runner_stream_steps() {
+        let profile = DiveProfileBuilder::new(GasMix::air())
+            .descend(30.0, 15.0)
+            .hold(20 * 60)
+            .ascend(0.0, 10.0)
+            .build()
+            .unwrap();
+
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        let expected = runner.run(60, profile.clone()).unwrap();
+
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        let mut summary = RunSummary::new();
+        let mut data = vec![];
+        {
+            let mut writer = StepCsvWriter::new(&mut data);
+            for step in runner.steps(60, &profile).unwrap() {
+                assert_eq!(
+                    step.snapshot[0].pp_n2,
+                    expected.snapshots[step.index][0].pp_n2
+                );
+                writer.write(&step).unwrap();
+                summary.add(&step);
+            }
+            writer.flush().unwrap();
+        }
+
+        assert_eq!(summary.steps, expected.snapshots.len());
+        assert_eq!(summary.duration, 25.0);
+        assert_eq!(summary.max_depth, 30.0);
+        // supersaturated on the ascent, led by the fastest compartment
+        assert!(summary.max_supersaturation > 1.0);
+        assert_eq!(summary.leading_compartment, Some(0));
+        assert_eq!(summary.max_supersaturation_time, 25.0);
+
+        let rows = String::from_utf8(data).unwrap().lines().count();
+        assert_eq!(rows, 1 + summary.steps * 16);
+        assert!(runner.result().is_none());
+    }
+
+    #[test]
+    fn test_algorithm_runner_stop_early() {
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        let mut profile = DiveProfile::new();
+        profile.add_level(40.0, 60 * 24, GasMix::air());
+
+        // stop once the fastest compartment is close to saturation
+        let steps = runner.steps(60, &profile).unwrap();
+        let taken = steps
+            .take_while(|step| step.snapshot[0].pp_n2 < 3.8)
+            .count();
+
+        assert!(taken > 0 && taken < 60);
+        assert!(matches!(
+            runner.steps(0, &profile),
+            Err(DivesyncError::InvalidInterval(0))
+        ));
+    }
+
+    #[test]
+    fn test_algorithm_runner_output_interval() {
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        let profile = ascent_profile();
+
+        let steps: Vec<RunStep> = runner.steps(300, &profile).unwrap().collect();
+        let times: Vec<f32> = steps.iter().map(|s| s.elapsed_time).collect();
+        let events: Vec<Option<StepEvent>> = steps.iter().map(|s| s.event).collect();
+
+        assert_eq!(times, vec![2.0, 5.0, 10.0, 12.0, 15.0, 16.0]);
+        assert_eq!(
+            events,
+            vec![
+                Some(StepEvent::SegmentEnd),
+                None,
+                None,
+                Some(StepEvent::GasSwitch),
+                None,
+                Some(StepEvent::Surfaced),
+            ]
+        );
+        assert_eq!(steps[4].depth, 10.0);
+        assert_eq!(steps[4].gas_mix, GasMix::new_nitrox(0.32).unwrap());
+    }
+
+    #[test]
+    fn test_algorithm_runner_step_independent_of_interval() {
+        // neither the output interval nor the step change the result of an
+        // ascent, travel is loaded by the Schreiner equation
+        let fine = final_pp_n2(60, DEFAULT_STEP);
+        assert!((final_pp_n2(300, DEFAULT_STEP) - fine).abs() < 1e-4);
+        assert!((final_pp_n2(300, 5.0) - fine).abs() < 1e-4);
+        assert!((final_pp_n2(30, 5.0) - fine).abs() < 1e-4);
+
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        runner.set_step(0.0);
+        assert!(matches!(
+            runner.run(60, ascent_profile()),
+            Err(DivesyncError::ParamOutOfRange(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_algorithm_runner_interval_seconds() {
+        let mut runner = AlgorithmRunner::new(Box::new(ZHL16Algorithm::new(ZHL16Variant::C)));
+        let mut profile = DiveProfile::new();
+        profile.add_level(20.0, 2, GasMix::air());
+
+        let result = runner.run(30, profile).unwrap();
+        let times: Vec<f32> = result.snapshots.iter().map(|s| s[0].elapsed_time).collect();
+
+        assert_eq!(result.interval_period, 30);
+        assert_eq!(times, vec![0.5, 1.0, 1.5, 2.0]);
+    }
+
     #[test]
     fn test_algorithm_runner_save() {
         let algo = ZHL16Algorithm::new(ZHL16Variant::A);
         let mut runner = AlgorithmRunner::new(Box::new(algo));
         let mut profile = DiveProfile::new();
-        let mix = GasMix::new_nitrox(PPO2);
+        let mix = GasMix::air();
         profile.add_level(24.0, 20, mix);
 
-        runner.run(3, profile);
+        runner.run(180, profile).unwrap();
 
         let path = runner.save_results().unwrap();
         println!("path: {path}");
     }
+
+    #[test]
+    fn test_algorithm_runner_zero_interval() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::A);
+        let mut runner = AlgorithmRunner::new(Box::new(algo));
+        let mut profile = DiveProfile::new();
+        profile.add_level(20.0, 20, GasMix::air());
+
+        let res = runner.run(0, profile);
+
+        assert!(matches!(res, Err(DivesyncError::InvalidInterval(0))));
+        assert!(runner.result().is_none());
+    }
+
+    #[test]
+    fn test_algorithm_runner_invalid_profile() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::A);
+        let mut runner = AlgorithmRunner::new(Box::new(algo));
+        let mut profile = DiveProfile::new();
+        profile.add_segment(Segment::Travel {
+            depth: 20.0,
+            rate: 0.0,
+            gas_mix: GasMix::air(),
+        });
+
+        assert!(matches!(
+            runner.steps(60, &profile),
+            Err(DivesyncError::InvalidProfile(_))
+        ));
+        assert!(matches!(
+            runner.run(60, profile),
+            Err(DivesyncError::InvalidProfile(_))
+        ));
+    }
 }
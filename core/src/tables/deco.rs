@@ -0,0 +1,286 @@
+use std::fmt;
+
+use crate::algorithm::DecoAlgorithm;
+use crate::deco::{time_to_surface, DecoStop};
+use crate::error::DivesyncError;
+use crate::gas::{GasMix, MAX_BOTTOM_PPO2};
+use crate::profile::DiveProfile;
+use crate::repetitive::RepetitiveGroup;
+use crate::tables::depth_range;
+
+/// Default bottom times in minutes of each depth of the table book
+pub const DEFAULT_BOTTOM_TIMES: [u32; 12] = [10, 15, 20, 25, 30, 40, 50, 60, 70, 80, 90, 120];
+
+/// Schedule of a single depth and bottom time, `ascent_time` is the total
+/// time from leaving the bottom to surfacing
+#[derive(Debug, Clone)]
+pub struct DecoTableEntry {
+    pub depth: f32,
+    pub bottom_time: u32,
+    pub stops: Vec<DecoStop>,
+    pub ascent_time: u32,
+    /// Group derived from the 120 minute compartment, not a USN designator
+    pub group: Option<RepetitiveGroup>,
+}
+
+impl DecoTableEntry {
+    fn stop_time(&self, depth: f32) -> Option<u32> {
+        self.stops.iter().find(|s| s.depth == depth).map(|s| s.time)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DecoTable {
+    pub algorithm: String,
+    pub bottom_mix: GasMix,
+    pub deco_mixes: Vec<GasMix>,
+    pub entries: Vec<DecoTableEntry>,
+}
+
+impl DecoTable {
+    /// All stop depths used by any entry, deepest first
+    pub fn stop_depths(&self) -> Vec<f32> {
+        let mut depths: Vec<f32> = vec![];
+
+        for stop in self.entries.iter().flat_map(|e| &e.stops) {
+            if !depths.contains(&stop.depth) {
+                depths.push(stop.depth);
+            }
+        }
+
+        depths.sort_by(|a, b| b.total_cmp(a));
+        depths
+    }
+
+    /// One row per depth and bottom time, with a stop time column for every
+    /// stop depth of the table
+    pub fn to_csv(&self) -> Result<String, DivesyncError> {
+        let depths = self.stop_depths();
+        let mut wtr = csv::Writer::from_writer(vec![]);
+
+        let mut header = vec!["depth".to_string(), "bottom_time".to_string()];
+        header.extend(depths.iter().map(|d| format!("stop_{d}m")));
+        header.extend(["ascent_time".to_string(), "model_group".to_string()]);
+        wtr.write_record(&header)?;
+
+        for entry in &self.entries {
+            let mut record = vec![entry.depth.to_string(), entry.bottom_time.to_string()];
+            record.extend(
+                depths
+                    .iter()
+                    .map(|d| entry.stop_time(*d).map_or(String::new(), |t| t.to_string())),
+            );
+            record.push(entry.ascent_time.to_string());
+            record.push(entry.group.map_or(String::new(), |g| g.to_string()));
+            wtr.write_record(&record)?;
+        }
+
+        let bytes = wtr.into_inner().map_err(|e| e.into_error())?;
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+}
+
+impl fmt::Display for DecoTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let depths = self.stop_depths();
+
+        write!(f, "{}, bottom gas {}", self.algorithm, self.bottom_mix)?;
+        if !self.deco_mixes.is_empty() {
+            let mixes: Vec<String> = self.deco_mixes.iter().map(|m| m.to_string()).collect();
+            write!(f, ", deco gas {}", mixes.join(", "))?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "MGrp: group derived from the 120 minute compartment, not a USN designator"
+        )?;
+
+        let mut last_depth = None;
+        for entry in &self.entries {
+            if last_depth != Some(entry.depth) {
+                writeln!(f)?;
+                writeln!(f, "{:.0}m", entry.depth)?;
+                write!(f, "{:>5} |", "BT")?;
+                for depth in &depths {
+                    write!(f, " {:>3}", format!("{depth:.0}"))?;
+                }
+                writeln!(f, " | {:>4} {:>4}", "TAT", "MGrp")?;
+                last_depth = Some(entry.depth);
+            }
+
+            write!(f, "{:>5} |", entry.bottom_time)?;
+            for depth in &depths {
+                match entry.stop_time(*depth) {
+                    Some(time) => write!(f, " {time:>3}")?,
+                    None => write!(f, " {:>3}", "-")?,
+                }
+            }
+
+            let group = entry.group.map_or("-".to_string(), |g| g.to_string());
+            writeln!(f, " | {:>4} {:>4}", entry.ascent_time, group)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Table book of stops for a range of depths and bottom times
+pub struct DecoTableGenerator {
+    min_depth: f32,
+    max_depth: f32,
+    step: f32,
+    bottom_times: Vec<u32>,
+    max_ppo2: f32,
+}
+
+impl DecoTableGenerator {
+    pub fn new(min_depth: f32, max_depth: f32, step: f32) -> Self {
+        Self {
+            min_depth,
+            max_depth,
+            step,
+            bottom_times: DEFAULT_BOTTOM_TIMES.to_vec(),
+            max_ppo2: MAX_BOTTOM_PPO2,
+        }
+    }
+
+    pub fn set_bottom_times(&mut self, bottom_times: Vec<u32>) {
+        self.bottom_times = bottom_times;
+    }
+
+    /// Depths below the MOD of the bottom mix at `max_ppo2` are left out
+    pub fn set_max_ppo2(&mut self, max_ppo2: f32) {
+        self.max_ppo2 = max_ppo2;
+    }
+
+    /// Compute stops of every depth and bottom time, gradient factors and
+    /// other conservatism settings are taken from `algo`
+    pub fn generate(
+        &self,
+        algo: &dyn DecoAlgorithm,
+        bottom_mix: &GasMix,
+        deco_mixes: &[GasMix],
+    ) -> Result<DecoTable, DivesyncError> {
+        let mut entries = vec![];
+        let max_depth = bottom_mix.max_operating_depth(self.max_ppo2);
+
+        for depth in depth_range(self.min_depth, self.max_depth, self.step)? {
+            if depth > max_depth {
+                break;
+            }
+
+            for bottom_time in &self.bottom_times {
+                let mut profile = DiveProfile::new();
+                profile.add_level(depth, *bottom_time, bottom_mix.clone());
+                for mix in deco_mixes {
+                    profile.add_deco_mix(mix.clone());
+                }
+
+                let stops = algo.compute_deco_stops(profile.clone());
+
+                entries.push(DecoTableEntry {
+                    depth,
+                    bottom_time: *bottom_time,
+                    ascent_time: time_to_surface(depth, &stops),
+                    group: RepetitiveGroup::after_dive(&profile, &stops),
+                    stops,
+                });
+            }
+        }
+
+        Ok(DecoTable {
+            algorithm: algo.variant().to_string(),
+            bottom_mix: bottom_mix.clone(),
+            deco_mixes: deco_mixes.to_vec(),
+            entries,
+        })
+    }
+}
+
+impl Default for DecoTableGenerator {
+    /// 12 to 45m in 3m steps
+    fn default() -> Self {
+        Self::new(12.0, 45.0, 3.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deco::GradientFactors;
+    use crate::zhl16::{algorithm::ZHL16Algorithm, tissue::ZHL16Variant};
+
+    fn table(algo: &dyn DecoAlgorithm) -> DecoTable {
+        let mut generator = DecoTableGenerator::new(30.0, 36.0, 3.0);
+        generator.set_bottom_times(vec![10, 20, 40]);
+
+        generator
+            .generate(algo, &GasMix::air(), &[GasMix::new_nitrox(0.5).unwrap()])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_deco_table_entries() {
+        let table = table(&ZHL16Algorithm::new(ZHL16Variant::C));
+
+        assert_eq!(table.entries.len(), 9);
+
+        let short = &table.entries[0];
+        assert_eq!((short.depth, short.bottom_time), (30.0, 10));
+        assert!(short.stops.is_empty());
+        assert_eq!(short.ascent_time, 3);
+
+        let long = &table.entries[8];
+        assert_eq!((long.depth, long.bottom_time), (36.0, 40));
+        assert!(!long.stops.is_empty());
+        assert!(long.ascent_time > short.ascent_time);
+        assert!(long.group > short.group);
+    }
+
+    #[test]
+    fn test_deco_table_gradient_factors() {
+        let raw = table(&ZHL16Algorithm::new(ZHL16Variant::C));
+
+        let mut algo = ZHL16Algorithm::new(ZHL16Variant::C);
+        algo.set_gradient_factors(GradientFactors::new(0.3, 0.7));
+        let conservative = table(&algo);
+
+        assert!(conservative.entries[8].ascent_time > raw.entries[8].ascent_time);
+    }
+
+    #[test]
+    fn test_deco_table_skips_depths_below_mod() {
+        let mut generator = DecoTableGenerator::new(33.0, 39.0, 3.0);
+        generator.set_bottom_times(vec![20]);
+
+        let table = generator
+            .generate(
+                &ZHL16Algorithm::new(ZHL16Variant::C),
+                &GasMix::new_nitrox(0.32).unwrap(),
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(table.entries.len(), 1);
+        assert_eq!(table.entries[0].depth, 33.0);
+    }
+
+    #[test]
+    fn test_deco_table_formats() {
+        let table = table(&ZHL16Algorithm::new(ZHL16Variant::C));
+        let depths = table.stop_depths();
+
+        let csv = table.to_csv().unwrap();
+        let header = csv.lines().next().unwrap();
+        assert!(header.starts_with("depth,bottom_time,stop_"));
+        assert!(header.ends_with(",ascent_time,model_group"));
+        assert_eq!(csv.lines().count(), 10);
+
+        let text = table.to_string();
+        assert!(text.starts_with("ZHL16-C, bottom gas Air, deco gas EAN50\n"));
+        assert!(text.contains("\n36m\n"));
+        assert!(text.contains("MGrp: group derived from the 120 minute compartment"));
+        assert_eq!(text.matches("TAT").count(), 3);
+        assert!(depths.windows(2).all(|d| d[0] > d[1]));
+    }
+}
@@ -0,0 +1,15 @@
+use crate::error::DivesyncError;
+
+pub mod deco;
+pub mod ndl;
+pub mod usn;
+
+/// Depths from `min_depth` to `max_depth` inclusive in steps of `step`
+pub fn depth_range(min_depth: f32, max_depth: f32, step: f32) -> Result<Vec<f32>, DivesyncError> {
+    if step <= 0.0 || min_depth < 0.0 || min_depth > max_depth {
+        return Err(DivesyncError::InvalidDepthRange(min_depth, max_depth, step));
+    }
+
+    let count = ((max_depth - min_depth) / step + 1e-3).floor() as usize;
+    Ok((0..=count).map(|i| min_depth + i as f32 * step).collect())
+}
@@ -0,0 +1,204 @@
+use serde::Serialize;
+
+use crate::algorithm::DecoAlgorithm;
+use crate::error::DivesyncError;
+use crate::gas::{GasMix, MAX_BOTTOM_PPO2};
+use crate::profile::DiveProfile;
+use crate::tables::depth_range;
+
+/// No decompression limits of one depth, `None` for gases deeper than their MOD
+#[derive(Debug, Clone, Serialize)]
+pub struct NdlRow {
+    pub depth: f32,
+    pub ndl: Vec<Option<u32>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NdlTable {
+    pub algorithm: String,
+    pub gases: Vec<String>,
+    pub rows: Vec<NdlRow>,
+}
+
+impl NdlTable {
+    pub fn to_csv(&self) -> Result<String, DivesyncError> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+
+        let mut header = vec!["depth".to_string()];
+        header.extend(self.gases.iter().cloned());
+        wtr.write_record(&header)?;
+
+        for row in &self.rows {
+            let mut record = vec![format!("{}", row.depth)];
+            record.extend(
+                row.ndl
+                    .iter()
+                    .map(|n| n.map_or(String::new(), |n| n.to_string())),
+            );
+            wtr.write_record(&record)?;
+        }
+
+        let bytes = wtr.into_inner().map_err(|e| e.into_error())?;
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+
+    /// Markdown table, depths beyond the MOD of a gas are shown as `-`
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("| Depth | {} |\n", self.gases.join(" | "));
+        md.push_str(&format!(
+            "|------:|{}\n",
+            "-----:|".repeat(self.gases.len())
+        ));
+
+        for row in &self.rows {
+            let cells: Vec<String> = row
+                .ndl
+                .iter()
+                .map(|n| n.map_or("-".to_string(), |n| n.to_string()))
+                .collect();
+            md.push_str(&format!("| {}m | {} |\n", row.depth, cells.join(" | ")));
+        }
+
+        md
+    }
+
+    pub fn to_json(&self) -> Result<String, DivesyncError> {
+        serde_json::to_string_pretty(self).map_err(|e| DivesyncError::Parse(e.to_string()))
+    }
+}
+
+/// Sweep a depth range for several gases, depths in meters
+pub struct NdlTableGenerator {
+    min_depth: f32,
+    max_depth: f32,
+    step: f32,
+    max_ppo2: f32,
+}
+
+impl NdlTableGenerator {
+    pub fn new(min_depth: f32, max_depth: f32, step: f32) -> Self {
+        Self {
+            min_depth,
+            max_depth,
+            step,
+            max_ppo2: MAX_BOTTOM_PPO2,
+        }
+    }
+
+    /// Set partial pressure of oxygen used for the MOD of each gas
+    pub fn set_max_ppo2(&mut self, max_ppo2: f32) {
+        self.max_ppo2 = max_ppo2;
+    }
+
+    pub fn depths(&self) -> Result<Vec<f32>, DivesyncError> {
+        depth_range(self.min_depth, self.max_depth, self.step)
+    }
+
+    pub fn generate(
+        &self,
+        algo: &dyn DecoAlgorithm,
+        gases: &[GasMix],
+    ) -> Result<NdlTable, DivesyncError> {
+        let mut rows = vec![];
+
+        for depth in self.depths()? {
+            let ndl = gases
+                .iter()
+                .map(|mix| {
+                    if depth > mix.max_operating_depth(self.max_ppo2) {
+                        return None;
+                    }
+
+                    let mut profile = DiveProfile::new();
+                    profile.add_level(depth, 0, mix.clone());
+                    Some(algo.compute_ndl(profile))
+                })
+                .collect();
+
+            rows.push(NdlRow { depth, ndl });
+        }
+
+        Ok(NdlTable {
+            algorithm: algo.variant().to_string(),
+            gases: gases.iter().map(|g| g.to_string()).collect(),
+            rows,
+        })
+    }
+}
+
+impl Default for NdlTableGenerator {
+    /// 9 to 40m in 3m steps
+    fn default() -> Self {
+        Self::new(9.0, 40.0, 3.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::zhl16::{algorithm::ZHL16Algorithm, tissue::ZHL16Variant};
+
+    fn table() -> NdlTable {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+        let gases = vec![
+            GasMix::air(),
+            GasMix::new_nitrox(0.32).unwrap(),
+            GasMix::new_nitrox(0.36).unwrap(),
+        ];
+
+        NdlTableGenerator::default()
+            .generate(&algo, &gases)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_depths() {
+        let depths = NdlTableGenerator::default().depths().unwrap();
+
+        assert_eq!(depths.first(), Some(&9.0));
+        assert_eq!(depths.last(), Some(&39.0));
+        assert_eq!(depths.len(), 11);
+        assert!(NdlTableGenerator::new(9.0, 40.0, 0.0).depths().is_err());
+        assert!(NdlTableGenerator::new(40.0, 9.0, 3.0).depths().is_err());
+    }
+
+    #[test]
+    fn test_ndl_table_mod() {
+        let table = table();
+        let row_33 = table.rows.iter().find(|r| r.depth == 33.0).unwrap();
+        let row_36 = table.rows.iter().find(|r| r.depth == 36.0).unwrap();
+
+        assert_eq!(table.gases, vec!["Air", "EAN32", "EAN36"]);
+        // MOD of EAN32 is 33.8m, EAN36 is 28.9m at ppO2 1.4
+        assert!(row_33.ndl[1].is_some());
+        assert!(row_33.ndl[2].is_none());
+        assert!(row_36.ndl[1].is_none());
+        assert!(row_36.ndl[0].is_some());
+    }
+
+    #[test]
+    fn test_ndl_table_nitrox_longer() {
+        let table = table();
+        let row = table.rows.iter().find(|r| r.depth == 24.0).unwrap();
+
+        assert!(row.ndl[1] > row.ndl[0]);
+        assert!(row.ndl[2] > row.ndl[1]);
+    }
+
+    #[test]
+    fn test_ndl_table_formats() {
+        let table = table();
+
+        let csv = table.to_csv().unwrap();
+        assert!(csv.starts_with("depth,Air,EAN32,EAN36\n9,"));
+        assert!(csv.contains("\n39,"));
+
+        let md = table.to_markdown();
+        assert!(md.starts_with("| Depth | Air | EAN32 | EAN36 |\n"));
+        assert!(md.contains("| 39m |"));
+
+        let json: serde_json::Value = serde_json::from_str(&table.to_json().unwrap()).unwrap();
+        assert_eq!(json["algorithm"], "ZHL16-C");
+        assert_eq!(json["rows"][10]["ndl"][2], serde_json::Value::Null);
+    }
+}
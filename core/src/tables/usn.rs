@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::error::DivesyncError;
+use crate::repetitive::{RepetitiveGroup, GROUP_LETTERS};
+
+/// Feet of sea water per meter, depths of the USN tables are in fsw
+pub const FSW_PER_METER: f32 = 3.28084;
+
+/// Row of the no-decompression limits and repetitive group designation
+/// table at `depth` fsw. `groups` holds the longest bottom time in minutes
+/// of each group starting at A. `limit` is `None` for unlimited depths,
+/// where bottom times past the last group get the next letter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoDecompressionRow {
+    pub depth: u32,
+    #[serde(default)]
+    pub limit: Option<u32>,
+    pub groups: Vec<u32>,
+}
+
+/// Surface interval credit of a dive ending in `group`. `until` holds the
+/// longest surface interval in minutes spent in each group from `group`
+/// down to A, past the last entry the next dive is no longer repetitive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurfaceIntervalRow {
+    pub group: char,
+    pub until: Vec<u32>,
+}
+
+/// Residual nitrogen time in minutes of `group` for a dive to `depth` fsw
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResidualNitrogenCell {
+    pub group: char,
+    pub depth: u32,
+    pub minutes: u32,
+}
+
+/// Air no-decompression, surface interval credit and residual nitrogen
+/// time tables of the US Navy Diving Manual. The tables are not shipped
+/// with the crate, they are loaded from a TOML or JSON transcription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsnTables {
+    pub name: String,
+    pub no_decompression: Vec<NoDecompressionRow>,
+    pub surface_interval: Vec<SurfaceIntervalRow>,
+    pub residual_nitrogen: Vec<ResidualNitrogenCell>,
+}
+
+impl UsnTables {
+    pub fn from_toml(s: &str) -> Result<Self, DivesyncError> {
+        let tables: Self = toml::from_str(s).map_err(|e| DivesyncError::Parse(e.to_string()))?;
+        tables.validate()?;
+        Ok(tables)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, DivesyncError> {
+        let tables: Self =
+            serde_json::from_str(s).map_err(|e| DivesyncError::Parse(e.to_string()))?;
+        tables.validate()?;
+        Ok(tables)
+    }
+
+    /// Load tables from a `.toml` or `.json` file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, DivesyncError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&content),
+            Some("json") => Self::from_json(&content),
+            _ => Err(DivesyncError::Parse(format!(
+                "unsupported table file: {}, expected .toml or .json",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), DivesyncError> {
+        let invalid = |msg: String| Err(DivesyncError::InvalidTables(msg));
+
+        if self.name.trim().is_empty() {
+            return invalid("table name is empty".to_string());
+        }
+        if self.no_decompression.is_empty() {
+            return invalid("no-decompression table has no rows".to_string());
+        }
+
+        for (i, row) in self.no_decompression.iter().enumerate() {
+            if i > 0 && row.depth <= self.no_decompression[i - 1].depth {
+                return invalid(format!("{} fsw: depths must increase", row.depth));
+            }
+            if row.groups.is_empty() || row.groups.len() > GROUP_LETTERS.len() {
+                return invalid(format!(
+                    "{} fsw: expected 1 to {} groups",
+                    row.depth,
+                    GROUP_LETTERS.len()
+                ));
+            }
+            if !increasing(&row.groups) {
+                return invalid(format!("{} fsw: group times must increase", row.depth));
+            }
+            match row.limit {
+                Some(limit) if row.groups.last().is_some_and(|t| *t > limit) => {
+                    return invalid(format!("{} fsw: group past the limit", row.depth));
+                }
+                None if row.groups.len() == GROUP_LETTERS.len() => {
+                    return invalid(format!("{} fsw: no group left past the table", row.depth));
+                }
+                _ => (),
+            }
+        }
+
+        for row in &self.surface_interval {
+            let Some(group) = RepetitiveGroup::from_letter(row.group) else {
+                return invalid(format!("unknown surface interval group: {}", row.group));
+            };
+            if row.until.len() != group.index() + 1 {
+                return invalid(format!(
+                    "surface interval group {}: expected {} intervals",
+                    row.group,
+                    group.index() + 1
+                ));
+            }
+            if !increasing(&row.until) {
+                return invalid(format!(
+                    "surface interval group {}: intervals must increase",
+                    row.group
+                ));
+            }
+        }
+
+        for cell in &self.residual_nitrogen {
+            if RepetitiveGroup::from_letter(cell.group).is_none() {
+                return invalid(format!("unknown residual nitrogen group: {}", cell.group));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Group after `bottom_time` minutes at `depth` meters, using the next
+    /// deeper row and the next longer time as the tables require
+    pub fn group_after_dive(
+        &self,
+        depth: f32,
+        bottom_time: u32,
+    ) -> Result<RepetitiveGroup, DivesyncError> {
+        let fsw = to_fsw(depth);
+        let row = self
+            .no_decompression
+            .iter()
+            .find(|row| row.depth as f32 >= fsw)
+            .ok_or_else(|| {
+                DivesyncError::OutsideTables(format!("{depth}m is deeper than the table"))
+            })?;
+
+        if let Some(limit) = row.limit {
+            if bottom_time > limit {
+                return Err(DivesyncError::OutsideTables(format!(
+                    "{bottom_time}min at {} fsw is past the no-decompression limit of {limit}min",
+                    row.depth
+                )));
+            }
+        }
+
+        let index = row
+            .groups
+            .iter()
+            .position(|t| bottom_time <= *t)
+            .unwrap_or(row.groups.len());
+
+        RepetitiveGroup::from_index(index).ok_or_else(|| {
+            DivesyncError::OutsideTables(format!(
+                "{bottom_time}min at {} fsw has no group",
+                row.depth
+            ))
+        })
+    }
+
+    /// Group after a surface interval of `minutes` starting in `group`,
+    /// `None` once the next dive is no longer repetitive
+    pub fn group_after_surface_interval(
+        &self,
+        group: RepetitiveGroup,
+        minutes: f32,
+    ) -> Result<Option<RepetitiveGroup>, DivesyncError> {
+        let row = self
+            .surface_interval
+            .iter()
+            .find(|row| RepetitiveGroup::from_letter(row.group) == Some(group))
+            .ok_or_else(|| {
+                DivesyncError::OutsideTables(format!("no surface interval credit for {group}"))
+            })?;
+
+        Ok(row
+            .until
+            .iter()
+            .position(|t| minutes <= *t as f32)
+            .and_then(|steps| RepetitiveGroup::from_index(group.index() - steps)))
+    }
+
+    /// Residual nitrogen time of `group` for a dive to `depth` meters, from
+    /// the next deeper tabulated depth
+    pub fn residual_nitrogen_time(
+        &self,
+        group: RepetitiveGroup,
+        depth: f32,
+    ) -> Result<u32, DivesyncError> {
+        let fsw = to_fsw(depth);
+
+        self.residual_nitrogen
+            .iter()
+            .filter(|cell| RepetitiveGroup::from_letter(cell.group) == Some(group))
+            .filter(|cell| cell.depth as f32 >= fsw)
+            .min_by_key(|cell| cell.depth)
+            .map(|cell| cell.minutes)
+            .ok_or_else(|| {
+                DivesyncError::OutsideTables(format!(
+                    "no residual nitrogen time for {group} at {depth}m"
+                ))
+            })
+    }
+}
+
+fn to_fsw(depth: f32) -> f32 {
+    // Tolerate rounding of depths converted from whole feet
+    depth * FSW_PER_METER - 1e-2
+}
+
+fn increasing(values: &[u32]) -> bool {
+    values.windows(2).all(|w| w[0] < w[1])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/data/usn_tables_fixture.toml");
+
+    fn group(letter: char) -> RepetitiveGroup {
+        RepetitiveGroup::from_letter(letter).unwrap()
+    }
+
+    fn depth(fsw: f32) -> f32 {
+        fsw / FSW_PER_METER
+    }
+
+    #[test]
+    fn test_group_after_dive() {
+        let tables = UsnTables::from_toml(FIXTURE).unwrap();
+
+        assert_eq!(
+            tables.group_after_dive(depth(40.0), 20).unwrap(),
+            group('A')
+        );
+        assert_eq!(
+            tables.group_after_dive(depth(40.0), 21).unwrap(),
+            group('B')
+        );
+        assert_eq!(
+            tables.group_after_dive(depth(35.0), 21).unwrap(),
+            group('B')
+        );
+        assert_eq!(
+            tables.group_after_dive(depth(40.0), 60).unwrap(),
+            group('D')
+        );
+        assert!(tables.group_after_dive(depth(40.0), 61).is_err());
+        assert!(tables.group_after_dive(depth(70.0), 10).is_err());
+    }
+
+    #[test]
+    fn test_unlimited_row() {
+        let tables = UsnTables::from_toml(FIXTURE).unwrap();
+
+        assert_eq!(
+            tables.group_after_dive(depth(10.0), 100).unwrap(),
+            group('B')
+        );
+        assert_eq!(
+            tables.group_after_dive(depth(10.0), 1000).unwrap(),
+            group('C')
+        );
+    }
+
+    #[test]
+    fn test_surface_interval_credit() {
+        let tables = UsnTables::from_toml(FIXTURE).unwrap();
+
+        let after = |g: char, minutes: f32| {
+            tables
+                .group_after_surface_interval(group(g), minutes)
+                .unwrap()
+        };
+
+        assert_eq!(after('C', 30.0), Some(group('C')));
+        assert_eq!(after('C', 31.0), Some(group('B')));
+        assert_eq!(after('C', 120.0), Some(group('A')));
+        assert_eq!(after('C', 121.0), None);
+        assert!(tables
+            .group_after_surface_interval(group('E'), 10.0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_residual_nitrogen_time() {
+        let tables = UsnTables::from_toml(FIXTURE).unwrap();
+
+        assert_eq!(
+            tables
+                .residual_nitrogen_time(group('B'), depth(40.0))
+                .unwrap(),
+            25
+        );
+        assert_eq!(
+            tables
+                .residual_nitrogen_time(group('B'), depth(30.0))
+                .unwrap(),
+            25
+        );
+        assert_eq!(
+            tables
+                .residual_nitrogen_time(group('B'), depth(50.0))
+                .unwrap(),
+            20
+        );
+        assert!(tables
+            .residual_nitrogen_time(group('B'), depth(60.0))
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut tables = UsnTables::from_toml(FIXTURE).unwrap();
+        tables.no_decompression[1].groups = vec![30, 20];
+        assert!(matches!(
+            tables.validate(),
+            Err(DivesyncError::InvalidTables(_))
+        ));
+
+        let mut tables = UsnTables::from_toml(FIXTURE).unwrap();
+        tables.surface_interval[0].until.push(600);
+        assert!(tables.validate().is_err());
+
+        let json = serde_json::to_string(&UsnTables::from_toml(FIXTURE).unwrap()).unwrap();
+        assert_eq!(
+            UsnTables::from_json(&json).unwrap(),
+            UsnTables::from_toml(FIXTURE).unwrap()
+        );
+    }
+}
@@ -1,7 +1,4 @@
-use crate::{
-    algorithm::DecoAlgorithmVariant,
-    gas::{Gas, GasMix, GasSymbol, GasType},
-};
+use crate::gas::GasMix;
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -13,6 +10,10 @@ pub trait TissueCompartment {
     /// Main update method
     fn update_pressure(&mut self, ata: f32, time: f32);
 
+    /// Update while the ambient pressure changes linearly from `start_ata`
+    /// to `end_ata` over `time` minutes
+    fn update_pressure_linear(&mut self, start_ata: f32, end_ata: f32, time: f32);
+
     /// Get the gas mix currently in use
     fn gas_mix(&self) -> GasMix;
 
@@ -23,6 +24,19 @@ pub trait TissueCompartment {
     fn n2_he_pp(&self) -> (f32, f32);
 }
 
+/// Schreiner equation, tissue pressure after `time` minutes of an inspired
+/// pressure changing linearly from `start_pp` to `end_pp`
+pub fn schreiner(tissue_pp: f32, start_pp: f32, end_pp: f32, half_time: f32, time: f32) -> f32 {
+    if time <= 0.0 {
+        return tissue_pp;
+    }
+
+    let k = std::f32::consts::LN_2 / half_time;
+    let rate = (end_pp - start_pp) / time;
+
+    start_pp + rate * (time - 1.0 / k) - (start_pp - tissue_pp - rate / k) * (-k * time).exp()
+}
+
 impl fmt::Display for dyn TissueCompartment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let (pp_n2, pp_he) = self.n2_he_pp();
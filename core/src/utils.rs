@@ -1,25 +1,25 @@
-use std::{
-    env,
-    path::{Path, PathBuf},
-    time::SystemTime,
-};
+use std::{env, path::PathBuf, time::SystemTime};
+
+use crate::error::DivesyncError;
 
 pub fn calc_ata(depth: f32) -> f32 {
     (depth + 10.0) / 10.0
 }
 
-pub fn timestamp() -> u64 {
+/// Inverse of `calc_ata`, depth in meters for a given ambient pressure
+pub fn calc_depth(ata: f32) -> f32 {
+    (ata - 1.0) * 10.0
+}
+
+pub fn timestamp() -> Result<u64, DivesyncError> {
     match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-        Ok(n) => n.as_secs(),
-        Err(_) => panic!("SystemTime before UNIX EPOCH!"),
+        Ok(n) => Ok(n.as_secs()),
+        Err(_) => Err(DivesyncError::InvalidSystemTime),
     }
 }
 
-pub fn home_dir() -> PathBuf {
-    match env::home_dir() {
-        Some(path) => path,
-        None => panic!("Impossible to get your home dir!"),
-    }
+pub fn home_dir() -> Result<PathBuf, DivesyncError> {
+    env::home_dir().ok_or(DivesyncError::HomeDirNotFound)
 }
 
 pub fn round_f32(num: f32, num_dec: usize) -> f32 {
@@ -32,6 +32,7 @@ pub fn n_root(num: f32, root_n: usize) -> f32 {
     f32::powf(num, 1.0 / root_n as f32)
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -41,6 +42,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::excessive_precision)]
     fn test_round_f32() {
         assert_eq!(4.006, round_f32(4.005999999, 4))
     }
@@ -49,4 +51,9 @@ mod test {
     fn test_calc_ata() {
         assert_eq!(4.2, calc_ata(32.0))
     }
+
+    #[test]
+    fn test_calc_depth() {
+        assert_eq!(32.0, round_f32(calc_depth(calc_ata(32.0)), 3))
+    }
 }
@@ -0,0 +1,792 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::algorithm::DecoAlgorithm;
+use crate::environment::Environment;
+use crate::gas::GasMix;
+use crate::profile::{DiveProfile, Exposure};
+use crate::utils::calc_ata;
+
+/// Depth in meters by which a later part of a dive, or a later dive, must
+/// be deeper than an earlier one to count as a reverse profile
+pub const REVERSE_MARGIN: f32 = 3.0;
+
+/// Smallest ascent followed by a descent in meters counted as a sawtooth
+pub const SAWTOOTH_AMPLITUDE: f32 = 3.0;
+
+/// Number of ascents and descents in a dive flagged as a sawtooth profile
+pub const SAWTOOTH_MIN_CYCLES: usize = 2;
+
+/// Dives at least this deep in meters require a safety stop
+pub const SAFETY_STOP_MIN_DEPTH: f32 = 10.0;
+
+/// Shallowest and deepest depth in meters of a safety stop, with the
+/// tolerance of `STOP_DEPTH_TOLERANCE`
+pub const SAFETY_STOP_DEPTHS: (f32, f32) = (3.0, 6.0);
+
+/// Time in seconds of a safety stop
+pub const SAFETY_STOP_TIME: u32 = 180;
+
+/// Meters a diver may be above a stop or ceiling without a violation
+pub const STOP_DEPTH_TOLERANCE: f32 = 1.0;
+
+/// Maximum ascent rate of a depth band, applies deeper than `depth` up to
+/// the next deeper band
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AscentLimit {
+    pub depth: f32,
+    pub rate: f32,
+}
+
+impl AscentLimit {
+    pub fn new(depth: f32, rate: f32) -> Self {
+        Self { depth, rate }
+    }
+}
+
+/// Ascent rate limits in meters per minute, slower near the surface
+pub fn default_ascent_limits() -> Vec<AscentLimit> {
+    vec![
+        AscentLimit::new(18.0, 12.0),
+        AscentLimit::new(6.0, 10.0),
+        AscentLimit::new(0.0, 6.0),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ViolationKind {
+    /// Ascent faster than the `limit` of the depth band, rates in meters
+    /// per minute
+    AscentRate { rate: f32, limit: f32 },
+    /// Ascent and descent of at least `SAWTOOTH_AMPLITUDE`, in a dive with
+    /// `SAWTOOTH_MIN_CYCLES` or more of them
+    Sawtooth { ascent: f32, descent: f32 },
+    /// Descent deeper than the deepest part of the dive before it
+    ReverseProfile { earlier_depth: f32 },
+    /// Dive deeper than the dive before it
+    ReverseRepetitive { previous_depth: f32 },
+    /// Seconds spent at safety stop depths before surfacing
+    MissedSafetyStop { stop_time: u32 },
+    /// Shallower than the first stop the model requires
+    DecoCeiling { ceiling: f32 },
+    /// Surfaced while the model still requires a stop
+    MissedDecoStop { ceiling: f32 },
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViolationKind::AscentRate { rate, limit } => {
+                write!(f, "ascent rate {rate:.1} m/min above {limit:.0} m/min")
+            }
+            ViolationKind::Sawtooth { ascent, descent } => {
+                write!(f, "sawtooth, {ascent:.1}m up and {descent:.1}m down")
+            }
+            ViolationKind::ReverseProfile { earlier_depth } => {
+                write!(
+                    f,
+                    "reverse profile, deeper than {earlier_depth:.1}m earlier"
+                )
+            }
+            ViolationKind::ReverseRepetitive { previous_depth } => {
+                write!(
+                    f,
+                    "reverse repetitive dive, previous dive {previous_depth:.1}m"
+                )
+            }
+            ViolationKind::MissedSafetyStop { stop_time } => write!(
+                f,
+                "missed safety stop, {stop_time}s of {SAFETY_STOP_TIME}s at {}-{}m",
+                SAFETY_STOP_DEPTHS.0, SAFETY_STOP_DEPTHS.1
+            ),
+            ViolationKind::DecoCeiling { ceiling } => {
+                write!(f, "above the deco stop at {ceiling:.1}m")
+            }
+            ViolationKind::MissedDecoStop { ceiling } => {
+                write!(f, "surfaced with a deco stop at {ceiling:.1}m")
+            }
+        }
+    }
+}
+
+/// Unsafe pattern found in a profile, `time` and `duration` in seconds
+/// from the start of the profile
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    pub time: u32,
+    pub duration: u32,
+    pub depth: f32,
+    pub kind: ViolationKind,
+}
+
+impl Violation {
+    fn new(time: f32, depth: f32, kind: ViolationKind) -> Self {
+        Self {
+            time: time.round() as u32,
+            duration: 0,
+            depth,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>3}:{:02} {:>5.1}m {}",
+            self.time / 60,
+            self.time % 60,
+            self.depth,
+            self.kind
+        )?;
+        if self.duration > 0 {
+            write!(f, " for {}s", self.duration)?;
+        }
+        Ok(())
+    }
+}
+
+/// Depth at a time in seconds
+#[derive(Debug, Clone, Copy)]
+struct Point {
+    time: f32,
+    depth: f32,
+}
+
+/// Find ascent rate, sawtooth, reverse profile, safety stop and deco stop
+/// violations in dive profiles, ceilings come from the stops of `algo`
+pub struct ProfileAnalyzer {
+    algo: Box<dyn DecoAlgorithm>,
+    ascent_limits: Vec<AscentLimit>,
+    environment: Environment,
+}
+
+impl ProfileAnalyzer {
+    pub fn new(algo: Box<dyn DecoAlgorithm>) -> Self {
+        Self {
+            algo,
+            ascent_limits: default_ascent_limits(),
+            environment: Environment::default(),
+        }
+    }
+
+    pub fn set_ascent_limits(&mut self, mut ascent_limits: Vec<AscentLimit>) {
+        ascent_limits.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        self.ascent_limits = ascent_limits;
+    }
+
+    /// Dive site conditions used for the model's stops
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = environment;
+    }
+
+    /// Violations of `profile` ordered by time, dives separated by surface
+    /// intervals are checked for reverse repetitive dives
+    pub fn analyze(&self, profile: &DiveProfile) -> Vec<Violation> {
+        self.analyze_in(profile, &self.environment)
+    }
+
+    /// Violations of each of a series of dives with the dive site conditions
+    /// of each, a dive deeper than the dive before it is flagged as a
+    /// reverse repetitive dive at its start
+    pub fn analyze_series(&self, dives: &[(DiveProfile, Environment)]) -> Vec<Vec<Violation>> {
+        let mut series = vec![];
+        let mut previous_depth: Option<f32> = None;
+
+        for (profile, environment) in dives {
+            let mut violations = self.analyze_in(profile, environment);
+            if let Some(violation) = previous_depth.and_then(|d| reverse_repetitive(d, profile)) {
+                violations.insert(0, violation);
+            }
+
+            previous_depth = Some(profile.max_depth());
+            series.push(violations);
+        }
+
+        series
+    }
+
+    fn analyze_in(&self, profile: &DiveProfile, environment: &Environment) -> Vec<Violation> {
+        let exposures = profile.exposures();
+        let points = points(&exposures);
+
+        let mut violations = self.ascent_rate_violations(&exposures);
+
+        let mut previous_depth: Option<f32> = None;
+        for dive in split_dives(&points) {
+            let max_depth = dive.iter().map(|p| p.depth).fold(0.0, f32::max);
+            if let Some(previous_depth) = previous_depth {
+                if max_depth > previous_depth + REVERSE_MARGIN {
+                    violations.push(Violation::new(
+                        dive[0].time,
+                        max_depth,
+                        ViolationKind::ReverseRepetitive { previous_depth },
+                    ));
+                }
+            }
+            previous_depth = Some(max_depth);
+
+            violations.extend(turn_violations(dive));
+            violations.extend(safety_stop_violation(dive));
+        }
+
+        violations.extend(self.deco_violations(profile, environment));
+        violations.sort_by_key(|v| v.time);
+        violations
+    }
+
+    /// Ascents faster than the limit of each depth band they cross,
+    /// consecutive violations are merged
+    fn ascent_rate_violations(&self, exposures: &[Exposure]) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = vec![];
+        let mut time = 0.0;
+
+        for exposure in exposures {
+            let start_time = time;
+            time += exposure.time * 60.0;
+
+            let ascent = exposure.start_depth - exposure.end_depth;
+            if ascent <= 0.0 || exposure.time <= 0.0 {
+                continue;
+            }
+            let rate = ascent / exposure.time;
+
+            let mut band_top = f32::INFINITY;
+            for limit in &self.ascent_limits {
+                let (deep, shallow) = (
+                    exposure.start_depth.min(band_top),
+                    exposure.end_depth.max(limit.depth),
+                );
+                band_top = limit.depth;
+
+                if deep <= shallow || rate <= limit.rate {
+                    continue;
+                }
+
+                let entered = start_time + (exposure.start_depth - deep) / rate * 60.0;
+                let duration = (deep - shallow) / rate * 60.0;
+                let kind = ViolationKind::AscentRate {
+                    rate,
+                    limit: limit.rate,
+                };
+
+                match violations.last_mut() {
+                    Some(last) if continues(last, entered, &kind) => {
+                        last.duration = (entered + duration).round() as u32 - last.time;
+                        if let ViolationKind::AscentRate {
+                            rate: last_rate, ..
+                        } = &mut last.kind
+                        {
+                            *last_rate = last_rate.max(rate);
+                        }
+                    }
+                    _ => {
+                        let mut violation = Violation::new(entered, deep, kind);
+                        violation.duration = duration.round() as u32;
+                        violations.push(violation);
+                    }
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Segment ends shallower than the model's first stop, consecutive
+    /// violations are merged
+    fn deco_violations(&self, profile: &DiveProfile, environment: &Environment) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = vec![];
+        let equivalent = environment.equivalent_profile(profile);
+        let mut start_time = 0.0;
+
+        // tissues are advanced one exposure at a time from a copy of the
+        // algorithm, air saturated as compute_* start on a fresh one
+        let mut algo = self.algo.clone();
+        algo.run(GasMix::air(), calc_ata(0.0), 0.0);
+        let mut status: Option<DiveProfile> = None;
+        let mut max_depth: f32 = 0.0;
+
+        for (exposure, last) in equivalent.exposures().into_iter().zip(profile.exposures()) {
+            // stops required before this exposure, checked at its end depth
+            let ceiling = status
+                .as_ref()
+                .map_or(0.0, |s| ceiling(algo.as_ref(), s, environment));
+
+            algo.run(
+                exposure.gas_mix.clone(),
+                calc_ata(exposure.mean_depth()),
+                exposure.time,
+            );
+            max_depth = max_depth.max(exposure.start_depth).max(exposure.end_depth);
+            status = Some(DiveProfile::status(
+                max_depth,
+                exposure.end_depth,
+                exposure.gas_mix,
+                &profile.deco_mixes,
+            ));
+
+            let time = start_time;
+            let end_time = start_time + last.time * 60.0;
+            let depth = last.end_depth;
+            start_time = end_time;
+
+            if ceiling <= 0.0 || depth + STOP_DEPTH_TOLERANCE >= ceiling {
+                continue;
+            }
+
+            let kind = match depth <= 0.0 {
+                true => ViolationKind::MissedDecoStop { ceiling },
+                false => ViolationKind::DecoCeiling { ceiling },
+            };
+
+            match violations.last_mut() {
+                Some(last)
+                    if matches!(kind, ViolationKind::DecoCeiling { .. })
+                        && continues(last, time, &kind) =>
+                {
+                    last.duration = end_time.round() as u32 - last.time;
+                }
+                _ => violations.push(Violation::new(end_time, depth, kind)),
+            }
+        }
+
+        violations
+    }
+}
+
+/// Depth in meters of the first stop `algo` requires from its tissues at
+/// the end of `status`, a profile in equivalent depths
+fn ceiling(algo: &dyn DecoAlgorithm, status: &DiveProfile, environment: &Environment) -> f32 {
+    let stops = algo.compute_deco_stops(status.clone());
+    let ceiling = stops.iter().map(|s| s.depth).fold(0.0, f32::max);
+
+    environment.actual_depth(ceiling)
+}
+
+/// Reverse repetitive violation at the start of `profile` when it is deeper
+/// than a previous dive to `previous_depth`
+fn reverse_repetitive(previous_depth: f32, profile: &DiveProfile) -> Option<Violation> {
+    let max_depth = profile.max_depth();
+    match max_depth > previous_depth + REVERSE_MARGIN {
+        true => Some(Violation::new(
+            0.0,
+            max_depth,
+            ViolationKind::ReverseRepetitive { previous_depth },
+        )),
+        false => None,
+    }
+}
+
+/// Whether a violation of `kind` at `time` extends `last`
+fn continues(last: &Violation, time: f32, kind: &ViolationKind) -> bool {
+    std::mem::discriminant(&last.kind) == std::mem::discriminant(kind)
+        && (last.time + last.duration) as f32 >= time.round() - 1.0
+}
+
+/// Depth at the surface start and at the end of every exposure, jumps
+/// to the depth of a hold are kept as separate points
+fn points(exposures: &[Exposure]) -> Vec<Point> {
+    let mut points = vec![Point {
+        time: 0.0,
+        depth: 0.0,
+    }];
+    let mut time = 0.0;
+
+    for exposure in exposures {
+        let last = points[points.len() - 1];
+        if exposure.start_depth != last.depth {
+            points.push(Point {
+                time,
+                depth: exposure.start_depth,
+            });
+        }
+
+        time += exposure.time * 60.0;
+        points.push(Point {
+            time,
+            depth: exposure.end_depth,
+        });
+    }
+
+    points
+}
+
+/// Points of each dive, from the last surface point before it to the first
+/// surface point after it
+fn split_dives(points: &[Point]) -> Vec<&[Point]> {
+    let mut dives = vec![];
+    let mut start: Option<usize> = None;
+
+    for (i, point) in points.iter().enumerate() {
+        match (start, point.depth > 0.0) {
+            (None, true) => start = Some(i.saturating_sub(1)),
+            (Some(first), false) => {
+                dives.push(&points[first..=i]);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(first) = start {
+        dives.push(&points[first..]);
+    }
+
+    dives
+}
+
+/// Deepest and shallowest points between ascents and descents of at
+/// least `SAWTOOTH_AMPLITUDE`, alternating starting with the deepest
+fn turning_points(dive: &[Point]) -> Vec<Point> {
+    let mut turns = vec![];
+    let mut descending = true;
+    let mut extreme = dive[0];
+
+    for &point in &dive[1..] {
+        let reversed = match descending {
+            true => extreme.depth - point.depth >= SAWTOOTH_AMPLITUDE,
+            false => point.depth - extreme.depth >= SAWTOOTH_AMPLITUDE,
+        };
+        let further = match descending {
+            true => point.depth > extreme.depth,
+            false => point.depth < extreme.depth,
+        };
+
+        if reversed {
+            turns.push(extreme);
+            descending = !descending;
+            extreme = point;
+        } else if further {
+            extreme = point;
+        }
+    }
+
+    if descending {
+        turns.push(extreme);
+    }
+    turns
+}
+
+/// Sawtooth and reverse profile violations from the turning points of a
+/// dive
+fn turn_violations(dive: &[Point]) -> Vec<Violation> {
+    let turns = turning_points(dive);
+    let mut violations = vec![];
+    let mut sawtooth = vec![];
+    let mut deepest = turns.first().map_or(0.0, |t| t.depth);
+
+    // turns alternate deepest, shallowest, deepest ...
+    for (i, bottom) in turns.iter().enumerate().skip(2).step_by(2) {
+        let top = turns[i - 1];
+        let previous = turns[i - 2];
+
+        if top.depth > 0.0 {
+            sawtooth.push(Violation::new(
+                top.time,
+                top.depth,
+                ViolationKind::Sawtooth {
+                    ascent: previous.depth - top.depth,
+                    descent: bottom.depth - top.depth,
+                },
+            ));
+        }
+
+        if bottom.depth > deepest + REVERSE_MARGIN {
+            violations.push(Violation::new(
+                bottom.time,
+                bottom.depth,
+                ViolationKind::ReverseProfile {
+                    earlier_depth: deepest,
+                },
+            ));
+        }
+        deepest = deepest.max(bottom.depth);
+    }
+
+    if sawtooth.len() >= SAWTOOTH_MIN_CYCLES {
+        violations.extend(sawtooth);
+    }
+    violations
+}
+
+/// Missed safety stop of a dive deeper than `SAFETY_STOP_MIN_DEPTH` which
+/// surfaces, the stop is the time at stop depths after last being deeper
+fn safety_stop_violation(dive: &[Point]) -> Option<Violation> {
+    let max_depth = dive.iter().map(|p| p.depth).fold(0.0, f32::max);
+    let last = dive.last()?;
+    if max_depth < SAFETY_STOP_MIN_DEPTH || last.depth > 0.0 {
+        return None;
+    }
+
+    let shallow = SAFETY_STOP_DEPTHS.0 - STOP_DEPTH_TOLERANCE;
+    let deep = SAFETY_STOP_DEPTHS.1 + STOP_DEPTH_TOLERANCE;
+    let start = dive.iter().rposition(|p| p.depth > deep)?;
+
+    let mut stop_time = 0.0;
+    for leg in dive[start..].windows(2) {
+        let (a, b) = (leg[0], leg[1]);
+        let duration = b.time - a.time;
+        let (low, high) = (a.depth.min(b.depth), a.depth.max(b.depth));
+
+        stop_time += match high - low > 0.0 {
+            true => duration * (high.min(deep) - low.max(shallow)).max(0.0) / (high - low),
+            false if (shallow..=deep).contains(&low) => duration,
+            false => 0.0,
+        };
+    }
+
+    let stop_time = stop_time.round() as u32;
+    match stop_time < SAFETY_STOP_TIME {
+        true => Some(Violation::new(
+            last.time,
+            0.0,
+            ViolationKind::MissedSafetyStop { stop_time },
+        )),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algorithm::get_algo;
+    use crate::gas::GasMix;
+    use crate::profile::Segment;
+
+    fn analyzer() -> ProfileAnalyzer {
+        ProfileAnalyzer::new(get_algo("zhl16-c").unwrap())
+    }
+
+    /// Profile travelling through `(depth, rate)` pairs and holding each
+    /// `(depth, 0.0)` for a minute
+    fn profile(legs: &[(f32, f32)]) -> DiveProfile {
+        let mut profile = DiveProfile::new();
+        for &(depth, rate) in legs {
+            let gas_mix = GasMix::air();
+            profile.add_segment(match rate > 0.0 {
+                true => Segment::Travel {
+                    depth,
+                    rate,
+                    gas_mix,
+                },
+                false => Segment::Hold {
+                    depth,
+                    duration: 60,
+                    gas_mix,
+                },
+            });
+        }
+        profile
+    }
+
+    fn kinds<'a>(violations: &'a [Violation], name: &str) -> Vec<&'a Violation> {
+        violations
+            .iter()
+            .filter(|v| format!("{:?}", v.kind).starts_with(name))
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_dive() {
+        let mut dive = profile(&[
+            (18.0, 18.0),
+            (18.0, 0.0),
+            (6.0, 9.0),
+            (5.0, 5.0),
+            (5.0, 0.0),
+        ]);
+        for _ in 0..2 {
+            dive.add_level(5.0, 1, GasMix::air());
+        }
+        dive.add_segment(Segment::Travel {
+            depth: 0.0,
+            rate: 5.0,
+            gas_mix: GasMix::air(),
+        });
+
+        assert_eq!(analyzer().analyze(&dive), vec![]);
+    }
+
+    #[test]
+    fn test_ascent_rate_bands() {
+        let dive = profile(&[(30.0, 20.0), (30.0, 0.0), (0.0, 9.0)]);
+        let violations = analyzer().analyze(&dive);
+        let ascents = kinds(&violations, "AscentRate");
+
+        // 9 m/min only breaks the 6 m/min limit of the shallowest band
+        assert_eq!(ascents.len(), 1);
+        assert_eq!(ascents[0].depth, 6.0);
+        assert_eq!(
+            ascents[0].kind,
+            ViolationKind::AscentRate {
+                rate: 9.0,
+                limit: 6.0
+            }
+        );
+        assert_eq!(ascents[0].duration, 40);
+
+        // consecutive bands merge into one event
+        let dive = profile(&[(30.0, 20.0), (30.0, 0.0), (0.0, 15.0)]);
+        let violations = analyzer().analyze(&dive);
+        let ascents = kinds(&violations, "AscentRate");
+        assert_eq!(ascents.len(), 1);
+        assert_eq!(ascents[0].time, 90 + 60);
+        assert_eq!(ascents[0].duration, 120);
+    }
+
+    #[test]
+    fn test_sawtooth_and_reverse_profile() {
+        let dive = profile(&[
+            (15.0, 15.0),
+            (10.0, 5.0),
+            (16.0, 6.0),
+            (11.0, 5.0),
+            (25.0, 7.0),
+            (25.0, 0.0),
+        ]);
+        let violations = analyzer().analyze(&dive);
+
+        let sawtooth = kinds(&violations, "Sawtooth");
+        assert_eq!(sawtooth.len(), 2);
+        assert_eq!(sawtooth[0].depth, 10.0);
+        assert_eq!(
+            sawtooth[1].kind,
+            ViolationKind::Sawtooth {
+                ascent: 5.0,
+                descent: 14.0
+            }
+        );
+
+        let reverse = kinds(&violations, "ReverseProfile");
+        assert_eq!(reverse.len(), 1);
+        assert_eq!(reverse[0].depth, 25.0);
+        assert_eq!(
+            reverse[0].kind,
+            ViolationKind::ReverseProfile {
+                earlier_depth: 16.0
+            }
+        );
+
+        // a single excursion is not a sawtooth
+        let dive = profile(&[(15.0, 15.0), (10.0, 5.0), (14.0, 5.0)]);
+        assert_eq!(kinds(&analyzer().analyze(&dive), "Sawtooth").len(), 0);
+    }
+
+    #[test]
+    fn test_reverse_repetitive() {
+        let mut first = profile(&[(12.0, 12.0), (12.0, 0.0), (5.0, 5.0)]);
+        first.add_level(5.0, 3, GasMix::air());
+        first.add_segment(Segment::Travel {
+            depth: 0.0,
+            rate: 5.0,
+            gas_mix: GasMix::air(),
+        });
+        let mut second = first.clone();
+        second.segments[0] = Segment::Travel {
+            depth: 20.0,
+            rate: 20.0,
+            gas_mix: GasMix::air(),
+        };
+
+        let series = analyzer().analyze_series(&[
+            (first.clone(), Environment::default()),
+            (second.clone(), Environment::default()),
+        ]);
+        assert_eq!(series[0], vec![]);
+        assert_eq!(
+            series[1][0].kind,
+            ViolationKind::ReverseRepetitive {
+                previous_depth: 12.0
+            }
+        );
+
+        // dives within one profile separated by a surface interval
+        let mut both = first;
+        both.add_segment(Segment::SurfaceInterval { duration: 3600 });
+        both.segments.extend(second.segments);
+        let violations = analyzer().analyze(&both);
+        assert_eq!(kinds(&violations, "ReverseRepetitive").len(), 1);
+    }
+
+    #[test]
+    fn test_missed_safety_stop() {
+        let dive = profile(&[
+            (20.0, 20.0),
+            (20.0, 0.0),
+            (5.0, 10.0),
+            (5.0, 0.0),
+            (0.0, 5.0),
+        ]);
+        let violations = analyzer().analyze(&dive);
+        let missed = kinds(&violations, "MissedSafetyStop");
+
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].depth, 0.0);
+        // a minute held plus travel through the 2-7m band
+        assert_eq!(
+            missed[0].kind,
+            ViolationKind::MissedSafetyStop { stop_time: 108 }
+        );
+
+        // shallow dives do not need one
+        let dive = profile(&[(8.0, 8.0), (8.0, 0.0), (0.0, 5.0)]);
+        assert_eq!(
+            kinds(&analyzer().analyze(&dive), "MissedSafetyStop").len(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_deco_violations() {
+        let mut dive = DiveProfile::new();
+        dive.add_segment(Segment::Travel {
+            depth: 40.0,
+            rate: 20.0,
+            gas_mix: GasMix::air(),
+        });
+        dive.add_level(40.0, 25, GasMix::air());
+        dive.add_segment(Segment::Travel {
+            depth: 3.0,
+            rate: 9.0,
+            gas_mix: GasMix::air(),
+        });
+        dive.add_level(3.0, 2, GasMix::air());
+        dive.add_segment(Segment::Travel {
+            depth: 0.0,
+            rate: 3.0,
+            gas_mix: GasMix::air(),
+        });
+
+        let violations = analyzer().analyze(&dive);
+        let ceiling = kinds(&violations, "DecoCeiling");
+        let missed = kinds(&violations, "MissedDecoStop");
+
+        assert_eq!(ceiling.len(), 1);
+        assert_eq!(ceiling[0].depth, 3.0);
+        assert_eq!(ceiling[0].kind, ViolationKind::DecoCeiling { ceiling: 9.0 });
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].depth, 0.0);
+        assert!(violations.windows(2).all(|w| w[0].time <= w[1].time));
+    }
+
+    #[test]
+    fn test_display() {
+        let violation = Violation {
+            time: 754,
+            duration: 30,
+            depth: 4.5,
+            kind: ViolationKind::AscentRate {
+                rate: 9.0,
+                limit: 6.0,
+            },
+        };
+
+        assert_eq!(
+            violation.to_string(),
+            " 12:34   4.5m ascent rate 9.0 m/min above 6 m/min for 30s"
+        );
+    }
+}
@@ -1,31 +1,65 @@
 use crate::{
     algorithm::{DecoAlgorithm, DecoAlgorithmVariant},
-    deco::DecoStop,
-    gas::{self, GasMix},
+    deco::{schedule_stops, DecoStop, DecoTissues, GradientFactors},
+    error::DivesyncError,
+    gas::GasMix,
     profile::DiveProfile,
     tissue::{CompartmentSnapshot, TissueCompartment},
-    utils::calc_ata,
+    utils::{calc_ata, calc_depth},
+    zhl16::coefficients::{CoefficientTable, HeliumCoefficients},
     zhl16::tissue::ZHL16Compartment,
 };
 
 use super::tissue::ZHL16Variant;
 
+/// Upper bound returned by `compute_ndl` when no limit is reached
+pub const MAX_NDL: u32 = 999;
+
+#[derive(Clone)]
 pub struct ZHL16Algorithm {
     tissues: Vec<ZHL16Compartment>,
-    variant: ZHL16Variant,
+    variant: DecoAlgorithmVariant,
+    table: CoefficientTable,
+    gradient_factors: GradientFactors,
 }
 
 impl DecoAlgorithm for ZHL16Algorithm {
     fn variant(&self) -> DecoAlgorithmVariant {
-        DecoAlgorithmVariant::ZHL16(self.variant.clone())
+        self.variant.clone()
     }
 
     fn compute_deco_stops(&self, dive_profile: DiveProfile) -> Vec<DecoStop> {
-        vec![]
+        let Some(last) = dive_profile.last_exposure() else {
+            return vec![];
+        };
+
+        let mut tissues = self.load_profile(&dive_profile);
+        schedule_stops(
+            &mut tissues,
+            last.end_depth,
+            &last.gas_mix,
+            &dive_profile.deco_mixes,
+            self.gradient_factors,
+        )
     }
 
     fn compute_ndl(&self, dive_profile: DiveProfile) -> u32 {
-        42
+        let Some(last) = dive_profile.last_exposure() else {
+            return MAX_NDL;
+        };
+
+        let mut tissues = self.load_profile(&dive_profile);
+        let gf_high = self.gradient_factors.high;
+
+        for minute in 0..MAX_NDL {
+            if tissues.ceiling(gf_high) > 0.0 {
+                return minute;
+            }
+
+            tissues.expose(&last.gas_mix, last.end_depth, 1.0);
+        }
+
+        MAX_NDL
     }
 
     fn snapshot(&self) -> Vec<CompartmentSnapshot> {
@@ -52,8 +86,8 @@ impl DecoAlgorithm for ZHL16Algorithm {
     }
 
     fn run(&mut self, mix: GasMix, ata: f32, time: f32) {
-        if self.tissues.len() == 0 {
-            self.init(mix.clone());
+        if self.tissues.is_empty() {
+            self.tissues = build_tissues(mix.clone(), &self.table);
         }
 
         for t in &mut self.tissues {
@@ -61,34 +95,255 @@ impl DecoAlgorithm for ZHL16Algorithm {
             t.update_pressure(ata, time);
         }
     }
+
+    /// Exact loading of a depth change by the Schreiner equation
+    fn run_travel(&mut self, mix: GasMix, start_ata: f32, end_ata: f32, time: f32) {
+        if self.tissues.is_empty() {
+            self.tissues = build_tissues(mix.clone(), &self.table);
+        }
+
+        for t in &mut self.tissues {
+            t.set_gas_mix(mix.clone());
+            t.update_pressure_linear(start_ata, end_ata, time);
+        }
+    }
 }
 
 impl ZHL16Algorithm {
     pub fn new(variant: ZHL16Variant) -> Self {
+        Self::with_helium(variant, HeliumCoefficients::default())
+    }
+
+    /// Build preset variant with the given source of helium coefficients
+    pub fn with_helium(variant: ZHL16Variant, helium: HeliumCoefficients) -> Self {
         Self {
             tissues: vec![],
-            variant,
+            table: CoefficientTable::preset_with_helium(&variant, helium),
+            variant: DecoAlgorithmVariant::ZHL16(variant),
+            gradient_factors: GradientFactors::default(),
         }
     }
 
-    pub fn init(&mut self, mix: GasMix) {
-        if self.tissues.len() > 0 {
-            panic!(
-                "Cannot re-initialize the ZHL16 algorithm after it has already bean initialized."
-            )
+    /// Build algorithm from a user defined coefficient table, reported
+    /// as a custom variant named after the table
+    pub fn from_table(table: CoefficientTable) -> Result<Self, DivesyncError> {
+        table.validate()?;
+
+        Ok(Self {
+            tissues: vec![],
+            variant: DecoAlgorithmVariant::Custom(table.name.clone()),
+            table,
+            gradient_factors: GradientFactors::default(),
+        })
+    }
+
+    pub fn table(&self) -> &CoefficientTable {
+        &self.table
+    }
+
+    pub fn init(&mut self, mix: GasMix) -> Result<(), DivesyncError> {
+        if !self.tissues.is_empty() {
+            return Err(DivesyncError::AlreadyInitialized(self.variant.to_string()));
         }
 
-        let mut tissues: Vec<ZHL16Compartment> = vec![];
+        self.tissues = build_tissues(mix, &self.table);
+        Ok(())
+    }
+
+    /// Set gradient factors used for deco stops and no decompression limits
+    pub fn set_gradient_factors(&mut self, gradient_factors: GradientFactors) {
+        self.gradient_factors = gradient_factors;
+    }
+
+    pub fn gradient_factors(&self) -> GradientFactors {
+        self.gradient_factors
+    }
+
+    /// Copy of current tissues loaded with all segments of the dive profile,
+    /// tissues saturated with air at the surface are used if the algorithm
+    /// has not been run yet
+    fn load_profile(&self, dive_profile: &DiveProfile) -> Vec<ZHL16Compartment> {
+        let mut tissues = if self.tissues.is_empty() {
+            build_tissues(GasMix::air(), &self.table)
+        } else {
+            self.tissues.clone()
+        };
 
-        for i in 0..16 {
-            let t = ZHL16Compartment::new(i, mix.clone(), Some(self.variant.clone()));
-            tissues.push(t)
+        for exposure in dive_profile.exposures() {
+            tissues.expose(&exposure.gas_mix, exposure.mean_depth(), exposure.time);
         }
 
-        self.tissues = tissues;
+        tissues
+    }
+}
+
+fn build_tissues(mix: GasMix, table: &CoefficientTable) -> Vec<ZHL16Compartment> {
+    table
+        .compartments
+        .iter()
+        .enumerate()
+        .map(|(i, c)| ZHL16Compartment::with_coefficients(i, mix.clone(), c.clone()))
+        .collect()
+}
+
+impl DecoTissues for Vec<ZHL16Compartment> {
+    fn ceiling(&self, gf: f32) -> f32 {
+        let tolerated = self.iter().map(|t| t.tolerated_ata(gf)).fold(0.0, f32::max);
+
+        calc_depth(tolerated).max(0.0)
+    }
+
+    fn expose(&mut self, mix: &GasMix, depth: f32, time: f32) {
+        for t in self {
+            t.set_gas_mix(mix.clone());
+            t.update_pressure(calc_ata(depth), time);
+        }
     }
 }
 
+#[cfg(test)]
 mod test {
-    // TODO: ZHL16 algorithm tests
+    use super::*;
+    use crate::deco::total_stop_time;
+
+    #[test]
+    fn test_ndl_decreases_with_depth() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+
+        let ndl_18 = algo.compute_ndl(DiveProfile::square(18.0, 0, GasMix::air()));
+        let ndl_30 = algo.compute_ndl(DiveProfile::square(30.0, 0, GasMix::air()));
+        let ndl_40 = algo.compute_ndl(DiveProfile::square(40.0, 0, GasMix::air()));
+
+        assert!(ndl_18 > ndl_30);
+        assert!(ndl_30 > ndl_40);
+        assert!(ndl_40 > 0);
+    }
+
+    #[test]
+    fn test_ndl_shallow_is_unlimited() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+
+        assert_eq!(
+            algo.compute_ndl(DiveProfile::square(3.0, 0, GasMix::air())),
+            MAX_NDL
+        );
+    }
+
+    #[test]
+    fn test_no_stops_within_ndl() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+        let ndl = algo.compute_ndl(DiveProfile::square(30.0, 0, GasMix::air()));
+
+        let stops = algo.compute_deco_stops(DiveProfile::square(30.0, ndl - 1, GasMix::air()));
+
+        assert!(stops.is_empty());
+    }
+
+    #[test]
+    fn test_deco_stops_ascending_order() {
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+
+        let stops = algo.compute_deco_stops(DiveProfile::square(45.0, 30, GasMix::air()));
+
+        assert!(!stops.is_empty());
+        assert_eq!(stops.last().unwrap().depth, 3.0);
+        for pair in stops.windows(2) {
+            assert!(pair[0].depth > pair[1].depth);
+        }
+    }
+
+    #[test]
+    fn test_gradient_factors_add_deco() {
+        let profile = DiveProfile::square(45.0, 30, GasMix::air());
+        let raw = ZHL16Algorithm::new(ZHL16Variant::C);
+        let mut conservative = ZHL16Algorithm::new(ZHL16Variant::C);
+        conservative.set_gradient_factors(GradientFactors::new(0.3, 0.7));
+
+        let raw_stops = raw.compute_deco_stops(profile.clone());
+        let gf_stops = conservative.compute_deco_stops(profile);
+
+        assert!(total_stop_time(&gf_stops) > total_stop_time(&raw_stops));
+        assert!(gf_stops[0].depth > raw_stops[0].depth);
+    }
+
+    #[test]
+    fn test_deco_mix_shortens_deco() {
+        let mut profile = DiveProfile::square(45.0, 30, GasMix::air());
+        let algo = ZHL16Algorithm::new(ZHL16Variant::C);
+        let air_stops = algo.compute_deco_stops(profile.clone());
+
+        profile.add_deco_mix(GasMix::new_nitrox(0.5).unwrap());
+        let ean50_stops = algo.compute_deco_stops(profile);
+
+        assert!(total_stop_time(&ean50_stops) < total_stop_time(&air_stops));
+        assert_eq!(ean50_stops.last().unwrap().gas_mix.fo2(), 0.5);
+    }
+
+    #[test]
+    fn test_from_table() {
+        let profile = DiveProfile::square(30.0, 0, GasMix::air());
+        let preset = ZHL16Algorithm::new(ZHL16Variant::C);
+
+        let mut table = CoefficientTable::preset(&ZHL16Variant::C);
+        table.name = "ZHL16-C-MOD".to_string();
+        let same = ZHL16Algorithm::from_table(table.clone()).unwrap();
+        assert_eq!(same.variant().to_string(), "ZHL16-C-MOD");
+        assert_eq!(
+            same.compute_ndl(profile.clone()),
+            preset.compute_ndl(profile.clone())
+        );
+
+        // lower a values reduce tolerated overpressure
+        for cpt in &mut table.compartments {
+            cpt.n2_a *= 0.8;
+        }
+        let strict = ZHL16Algorithm::from_table(table.clone()).unwrap();
+        assert!(strict.compute_ndl(profile.clone()) < preset.compute_ndl(profile));
+
+        table.compartments.truncate(8);
+        let mut short = ZHL16Algorithm::from_table(table).unwrap();
+        short.init(GasMix::air()).unwrap();
+        assert_eq!(short.snapshot().len(), 8);
+    }
+
+    #[test]
+    fn test_helium_coefficients() {
+        let mut profile = DiveProfile::new();
+        profile.add_level(60.0, 20, GasMix::new_trimix(0.45, 0.18).unwrap());
+
+        let published = ZHL16Algorithm::new(ZHL16Variant::C);
+        let graham = ZHL16Algorithm::with_helium(ZHL16Variant::C, HeliumCoefficients::Graham);
+
+        let published_stops = published.compute_deco_stops(profile.clone());
+        let graham_stops = graham.compute_deco_stops(profile);
+
+        assert!(!published_stops.is_empty());
+        assert_ne!(
+            total_stop_time(&published_stops),
+            total_stop_time(&graham_stops)
+        );
+    }
+
+    #[test]
+    fn test_variant_compartment_count() {
+        for variant in [ZHL16Variant::A, ZHL16Variant::L12] {
+            let mut algo = ZHL16Algorithm::new(variant.clone());
+            algo.init(GasMix::air()).unwrap();
+
+            assert_eq!(algo.snapshot().len(), variant.compartment_count());
+            assert_eq!(algo.variant().to_string(), variant.to_string());
+            assert!(algo.compute_ndl(DiveProfile::square(30.0, 0, GasMix::air())) < MAX_NDL);
+        }
+    }
+
+    #[test]
+    fn test_from_invalid_table() {
+        let mut table = CoefficientTable::preset(&ZHL16Variant::A);
+        table.compartments[2].n2_half_time = -1.0;
+
+        assert!(matches!(
+            ZHL16Algorithm::from_table(table),
+            Err(DivesyncError::InvalidCoefficients(_))
+        ));
+    }
 }
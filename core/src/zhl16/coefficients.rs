@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::error::DivesyncError;
+use crate::utils::n_root;
+use crate::zhl16::tissue::{ZHL16Compartment, ZHL16Variant};
+
+/// Half-times in minutes and Bühlmann a/b coefficients of a single compartment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompartmentCoefficients {
+    pub n2_half_time: f32,
+    pub n2_a: f32,
+    pub n2_b: f32,
+    pub he_half_time: f32,
+    pub he_a: f32,
+    pub he_b: f32,
+}
+
+/// Source of the helium half times and a/b values of a preset
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum HeliumCoefficients {
+    /// Table published by Bühlmann
+    #[default]
+    Published,
+    /// Half times scaled from Nitrogen by Graham's law, a/b derived
+    /// from half times
+    Graham,
+}
+
+/// Coefficients of all compartments of a ZHL model, can be loaded from
+/// TOML or JSON to test modified coefficient sets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoefficientTable {
+    pub name: String,
+    pub compartments: Vec<CompartmentCoefficients>,
+}
+
+impl CoefficientTable {
+    /// Built-in coefficients of a ZHL16 variant, with published helium values
+    pub fn preset(variant: &ZHL16Variant) -> Self {
+        Self::preset_with_helium(variant, HeliumCoefficients::Published)
+    }
+
+    /// Built-in coefficients with the given helium source, only ZHL16
+    /// A to C have a published helium table, ZH-L12 uses its own
+    pub fn preset_with_helium(variant: &ZHL16Variant, helium: HeliumCoefficients) -> Self {
+        let compartments = (0..variant.compartment_count())
+            .map(|cpt_num| match variant {
+                ZHL16Variant::L12 => zhl12_compartment(cpt_num),
+                _ => preset_compartment(variant, helium, cpt_num),
+            })
+            .collect();
+
+        Self {
+            name: variant.to_string(),
+            compartments,
+        }
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, DivesyncError> {
+        let table: Self = toml::from_str(s).map_err(|e| DivesyncError::Parse(e.to_string()))?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, DivesyncError> {
+        let table: Self =
+            serde_json::from_str(s).map_err(|e| DivesyncError::Parse(e.to_string()))?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Load table from a `.toml` or `.json` file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, DivesyncError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&content),
+            Some("json") => Self::from_json(&content),
+            _ => Err(DivesyncError::Parse(format!(
+                "unsupported coefficient file: {}, expected .toml or .json",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), DivesyncError> {
+        let invalid = |msg: String| Err(DivesyncError::InvalidCoefficients(msg));
+
+        if self.name.trim().is_empty() {
+            return invalid("table name is empty".to_string());
+        }
+        if self.compartments.is_empty() {
+            return invalid("table has no compartments".to_string());
+        }
+
+        for (i, cpt) in self.compartments.iter().enumerate() {
+            let values = [
+                ("n2_half_time", cpt.n2_half_time),
+                ("n2_a", cpt.n2_a),
+                ("n2_b", cpt.n2_b),
+                ("he_half_time", cpt.he_half_time),
+                ("he_a", cpt.he_a),
+                ("he_b", cpt.he_b),
+            ];
+
+            for (key, value) in values {
+                if !value.is_finite() {
+                    return invalid(format!("compartment {i}: {key} is not a number"));
+                }
+            }
+            if cpt.n2_half_time <= 0.0 || cpt.he_half_time <= 0.0 {
+                return invalid(format!("compartment {i}: half-times must be above zero"));
+            }
+            if cpt.n2_a < 0.0 || cpt.he_a < 0.0 {
+                return invalid(format!("compartment {i}: a must not be negative"));
+            }
+            if cpt.n2_b <= 0.0 || cpt.n2_b > 1.0 || cpt.he_b <= 0.0 || cpt.he_b > 1.0 {
+                return invalid(format!("compartment {i}: b must be within (0, 1]"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn preset_compartment(
+    variant: &ZHL16Variant,
+    helium: HeliumCoefficients,
+    cpt_num: usize,
+) -> CompartmentCoefficients {
+    let n2_half_time = ZHL16Compartment::N2_HALF_TIMES[cpt_num];
+
+    let (he_half_time, he_a, he_b) = match helium {
+        HeliumCoefficients::Published => (
+            ZHL16Compartment::HE_HALF_TIMES[cpt_num],
+            ZHL16Compartment::HE_A[cpt_num],
+            ZHL16Compartment::HE_B[cpt_num],
+        ),
+        HeliumCoefficients::Graham => {
+            let ht = ZHL16Compartment::GRAHAM_HE_HALF_TIMES[cpt_num];
+            (ht, derive_a(ht), derive_b(ht))
+        }
+    };
+
+    CompartmentCoefficients {
+        n2_half_time,
+        n2_a: preset_n2_a(variant, cpt_num).unwrap_or_else(|| derive_a(n2_half_time)),
+        n2_b: preset_n2_b(cpt_num).unwrap_or_else(|| derive_b(n2_half_time)),
+        he_half_time,
+        he_a,
+        he_b,
+    }
+}
+
+fn zhl12_compartment(cpt_num: usize) -> CompartmentCoefficients {
+    CompartmentCoefficients {
+        n2_half_time: ZHL16Compartment::ZHL12_N2_HALF_TIMES[cpt_num],
+        n2_a: ZHL16Compartment::ZHL12_N2_A[cpt_num],
+        n2_b: ZHL16Compartment::ZHL12_N2_B[cpt_num],
+        he_half_time: ZHL16Compartment::ZHL12_HE_HALF_TIMES[cpt_num],
+        he_a: ZHL16Compartment::ZHL12_HE_A[cpt_num],
+        he_b: ZHL16Compartment::ZHL12_HE_B[cpt_num],
+    }
+}
+
+/// Regression A based on half time, per Bühlmann
+fn derive_a(half_time: f32) -> f32 {
+    2.0 / n_root(half_time, 3)
+}
+
+/// Regression B based on half time, per Bühlmann
+fn derive_b(half_time: f32) -> f32 {
+    1.005 - (1.0 / n_root(half_time, 2))
+}
+
+/// Nitrogen A values which differ from the derived value in each variant
+fn preset_n2_a(variant: &ZHL16Variant, cpt_num: usize) -> Option<f32> {
+    match (variant, cpt_num) {
+        (ZHL16Variant::B, 5) => Some(0.5600),
+        (ZHL16Variant::B, 6) => Some(0.4947),
+        (ZHL16Variant::B, 7) => Some(0.4500),
+        (ZHL16Variant::B, 12) => Some(0.2850),
+        (ZHL16Variant::C, 4) => Some(0.6200),
+        (ZHL16Variant::C, 5) => Some(0.5043),
+        (ZHL16Variant::C, 6) => Some(0.4410),
+        (ZHL16Variant::C, 7) => Some(0.4000),
+        (ZHL16Variant::C, 8) => Some(0.3750),
+        (ZHL16Variant::C, 9) => Some(0.3500),
+        (ZHL16Variant::C, 10) => Some(0.3295),
+        (ZHL16Variant::C, 11) => Some(0.3065),
+        (ZHL16Variant::C, 12) => Some(0.2835),
+        (ZHL16Variant::C, 13) => Some(0.2610),
+        (ZHL16Variant::C, 14) => Some(0.2480),
+        _ => None,
+    }
+}
+
+/// Nitrogen B values which differ from the derived value in all variants
+fn preset_n2_b(cpt_num: usize) -> Option<f32> {
+    match cpt_num {
+        3 => Some(0.7825),
+        4 => Some(0.8126),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::round_f32;
+
+    const TOML_TABLE: &str = r#"
+name = "ZHL-2"
+
+[[compartments]]
+n2_half_time = 5.0
+n2_a = 1.1696
+n2_b = 0.5578
+he_half_time = 1.88
+he_a = 1.6189
+he_b = 0.4770
+
+[[compartments]]
+n2_half_time = 8.0
+n2_a = 1.0
+n2_b = 0.6514
+he_half_time = 3.02
+he_a = 1.383
+he_b = 0.5747
+"#;
+
+    #[test]
+    fn test_preset_tables() {
+        let a = CoefficientTable::preset(&ZHL16Variant::A);
+        let c = CoefficientTable::preset(&ZHL16Variant::C);
+
+        assert_eq!(a.name, "ZHL16-A");
+        assert_eq!(a.compartments.len(), 16);
+        assert_eq!(c.compartments[4].n2_a, 0.6200);
+        assert_eq!(a.compartments[3].n2_b, 0.7825);
+        assert!(a.validate().is_ok());
+        assert!(c.validate().is_ok());
+    }
+
+    #[test]
+    fn test_preset_compartment_counts() {
+        let variants = [(ZHL16Variant::C, 16), (ZHL16Variant::L12, 16)];
+
+        for (variant, count) in variants {
+            let table = CoefficientTable::preset(&variant);
+            assert_eq!(table.compartments.len(), count);
+            assert_eq!(table.compartments.len(), variant.compartment_count());
+            assert!(table.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_preset_helium() {
+        let published = CoefficientTable::preset(&ZHL16Variant::C);
+        let graham =
+            CoefficientTable::preset_with_helium(&ZHL16Variant::C, HeliumCoefficients::Graham);
+
+        assert_eq!(published.compartments[0].he_half_time, 1.51);
+        assert_eq!(published.compartments[15].he_a, 0.5119);
+        assert_eq!(graham.compartments[0].he_half_time, 1.5119);
+        assert_eq!(round_f32(graham.compartments[15].he_a, 4), 0.3218);
+
+        // nitrogen values do not depend on helium source
+        assert_eq!(published.compartments[7].n2_a, graham.compartments[7].n2_a);
+    }
+
+    #[test]
+    fn test_table_from_toml() {
+        let table = CoefficientTable::from_toml(TOML_TABLE).unwrap();
+
+        assert_eq!(table.name, "ZHL-2");
+        assert_eq!(table.compartments.len(), 2);
+        assert_eq!(table.compartments[1].he_half_time, 3.02);
+    }
+
+    #[test]
+    fn test_table_json_round_trip() {
+        let table = CoefficientTable::preset(&ZHL16Variant::B);
+        let json = serde_json::to_string(&table).unwrap();
+
+        assert_eq!(CoefficientTable::from_json(&json).unwrap(), table);
+    }
+
+    #[test]
+    fn test_table_validation() {
+        let mut table = CoefficientTable::from_toml(TOML_TABLE).unwrap();
+        table.compartments[0].n2_b = 1.2;
+        assert!(matches!(
+            table.validate(),
+            Err(DivesyncError::InvalidCoefficients(_))
+        ));
+
+        let mut table = CoefficientTable::from_toml(TOML_TABLE).unwrap();
+        table.compartments[1].he_half_time = 0.0;
+        assert!(table.validate().is_err());
+
+        let mut table = CoefficientTable::from_toml(TOML_TABLE).unwrap();
+        table.compartments.clear();
+        assert!(table.validate().is_err());
+    }
+
+    #[test]
+    fn test_table_parse_error() {
+        let err = CoefficientTable::from_toml("name = 'broken'\n[[compartments]]\nn2_a = 1.0");
+
+        assert!(matches!(err, Err(DivesyncError::Parse(_))));
+    }
+}
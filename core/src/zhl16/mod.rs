@@ -1,3 +1,4 @@
 pub mod algorithm;
+pub mod coefficients;
 pub mod tissue;
 pub mod utils;
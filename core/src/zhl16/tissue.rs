@@ -1,11 +1,8 @@
 use std::fmt;
 
-use crate::gas::GasType;
-#[allow(non_snake_case)]
-use crate::gas::{Gas, GasMix, GasSymbol, PPN2};
-use crate::tissue::TissueCompartment;
-use crate::utils::n_root;
-use crate::zhl16::utils::build_air_tissue;
+use crate::gas::{GasMix, GasType};
+use crate::tissue::{schreiner, TissueCompartment};
+use crate::zhl16::coefficients::{CoefficientTable, CompartmentCoefficients};
 
 #[derive(Debug, Clone)]
 pub struct ZHL16Compartment {
@@ -13,24 +10,24 @@ pub struct ZHL16Compartment {
     pp_he: f32,
     pub cpt_num: usize,
     pub gas_mix: GasMix,
-    pub variant: ZHL16Variant,
+    pub coefficients: CompartmentCoefficients,
     pub elapsed_time: f32,
     pub last_depth: f32,
 }
 
 impl TissueCompartment for ZHL16Compartment {
     fn update_pressure(&mut self, ata: f32, time: f32) {
-        // update N2 pressure
-        let exp: f32 = 2_f32.powf(-(time / self.n2_ht()));
-        let gas_pp = self.gas_mix.pp_n2(ata);
+        // update N2 pressure, argon is loaded as nitrogen
+        let gas_pp = self.gas_mix.pp_n2(ata) + self.gas_mix.pp_ar(ata);
         let current_pp = self.pp_n2;
+        let exp: f32 = 2_f32.powf(-(time / self.n2_ht()));
         let new_pp = current_pp + (gas_pp - current_pp) * (1.0 - exp);
         self.pp_n2 = new_pp;
 
         // update He pressure
-        let exp: f32 = 2_f32.powf(-(time / self.he_ht()));
         let gas_pp = self.gas_mix.pp_he(ata);
         let current_pp = self.pp_he;
+        let exp: f32 = 2_f32.powf(-(time / self.he_ht()));
         let new_pp = current_pp + (gas_pp - current_pp) * (1.0 - exp);
         self.pp_he = new_pp;
 
@@ -41,6 +38,19 @@ impl TissueCompartment for ZHL16Compartment {
         self.last_depth = (ata - 1.0) * 10.0
     }
 
+    fn update_pressure_linear(&mut self, start_ata: f32, end_ata: f32, time: f32) {
+        // argon is loaded as nitrogen
+        let inert_n2 = |ata: f32| self.gas_mix.pp_n2(ata) + self.gas_mix.pp_ar(ata);
+        let (start_n2, end_n2) = (inert_n2(start_ata), inert_n2(end_ata));
+        self.pp_n2 = schreiner(self.pp_n2, start_n2, end_n2, self.n2_ht(), time);
+
+        let (start_he, end_he) = (self.gas_mix.pp_he(start_ata), self.gas_mix.pp_he(end_ata));
+        self.pp_he = schreiner(self.pp_he, start_he, end_he, self.he_ht(), time);
+
+        self.elapsed_time += time;
+        self.last_depth = (end_ata - 1.0) * 10.0
+    }
+
     fn half_time(&self) -> f32 {
         match self.gas_mix.mix_type() {
             GasType::Nitrox => self.n2_ht(),
@@ -89,25 +99,91 @@ impl ZHL16Compartment {
         498.0, 635.0,
     ];
 
+    /// Helium half times published by Bühlmann
     pub const HE_HALF_TIMES: [f32; 16] = [
+        1.51, 3.02, 4.72, 6.99, 10.21, 14.48, 20.53, 29.11, 41.20, 55.19, 70.69, 90.34, 115.29,
+        147.42, 188.24, 240.03,
+    ];
+
+    /// Helium A values published by Bühlmann
+    pub const HE_A: [f32; 16] = [
+        1.7424, 1.3830, 1.1919, 1.0458, 0.9220, 0.8205, 0.7305, 0.6502, 0.5950, 0.5545, 0.5333,
+        0.5189, 0.5181, 0.5176, 0.5172, 0.5119,
+    ];
+
+    /// Helium B values published by Bühlmann
+    pub const HE_B: [f32; 16] = [
+        0.4245, 0.5747, 0.6527, 0.7223, 0.7582, 0.7957, 0.8279, 0.8553, 0.8757, 0.8903, 0.8997,
+        0.9073, 0.9122, 0.9171, 0.9217, 0.9267,
+    ];
+
+    /// Nitrogen half times of ZH-L12, from Bühlmann, Dekompression -
+    /// Dekompressionskrankheit (1983). The model has 16 compartments, its
+    /// name counts the a and b pairs, which repeat across compartments
+    pub const ZHL12_N2_HALF_TIMES: [f32; 16] = [
+        2.65, 7.94, 12.2, 18.5, 26.5, 37.0, 53.0, 79.0, 114.0, 146.0, 185.0, 238.0, 304.0, 397.0,
+        503.0, 635.0,
+    ];
+
+    /// Nitrogen A values of ZH-L12, shared by neighbouring compartments
+    pub const ZHL12_N2_A: [f32; 16] = [
+        2.2, 1.5, 1.08, 0.9, 0.75, 0.58, 0.47, 0.455, 0.455, 0.455, 0.455, 0.38, 0.255, 0.255,
+        0.255, 0.255,
+    ];
+
+    /// Nitrogen B values of ZH-L12
+    pub const ZHL12_N2_B: [f32; 16] = [
+        0.82, 0.82, 0.825, 0.835, 0.845, 0.86, 0.87, 0.89, 0.89, 0.934, 0.934, 0.944, 0.962, 0.962,
+        0.962, 0.962,
+    ];
+
+    /// Helium half times of ZH-L12
+    pub const ZHL12_HE_HALF_TIMES: [f32; 16] = [
+        1.0, 3.0, 4.6, 7.0, 10.0, 14.0, 20.0, 30.0, 43.0, 55.0, 70.0, 90.0, 115.0, 150.0, 190.0,
+        240.0,
+    ];
+
+    /// Helium A values of ZH-L12
+    pub const ZHL12_HE_A: [f32; 16] = [
+        2.2, 1.5, 1.08, 0.9, 0.75, 0.58, 0.47, 0.455, 0.455, 0.515, 0.515, 0.515, 0.515, 0.515,
+        0.515, 0.515,
+    ];
+
+    /// Helium B values of ZH-L12
+    pub const ZHL12_HE_B: [f32; 16] = [
+        0.82, 0.82, 0.825, 0.835, 0.845, 0.86, 0.87, 0.89, 0.89, 0.926, 0.926, 0.926, 0.926, 0.926,
+        0.926, 0.926,
+    ];
+
+    /// Helium half times derived from Nitrogen half times by Graham's law,
+    /// see `generate_he_half_times`
+    pub const GRAHAM_HE_HALF_TIMES: [f32; 16] = [
         1.5119, 3.0237, 4.7245, 6.9923, 10.205, 14.476, 20.5234, 29.1032, 41.198, 55.1826, 70.6791,
         90.3332, 115.2788, 147.4056, 188.2256, 240.0066,
     ];
 
+    /// Build compartment `cpt_num` of a preset variant, `cpt_num` must be
+    /// below `ZHL16Variant::compartment_count`
     pub fn new(cpt_num: usize, gas_mix: GasMix, variant: Option<ZHL16Variant>) -> Self {
-        let variant = if variant.is_some() {
-            variant.unwrap()
-        } else {
-            ZHL16Variant::A
-        };
+        let variant = variant.unwrap_or(ZHL16Variant::A);
+        let coefficients = CoefficientTable::preset(&variant).compartments[cpt_num].clone();
 
+        Self::with_coefficients(cpt_num, gas_mix, coefficients)
+    }
+
+    /// Build compartment from user defined coefficients
+    pub fn with_coefficients(
+        cpt_num: usize,
+        gas_mix: GasMix,
+        coefficients: CompartmentCoefficients,
+    ) -> Self {
         Self {
             elapsed_time: 0.0,
             cpt_num,
             pp_n2: gas_mix.pp_n2(1.0),
             pp_he: gas_mix.pp_he(1.0),
             gas_mix,
-            variant,
+            coefficients,
             last_depth: 0.0,
         }
     }
@@ -125,9 +201,25 @@ impl ZHL16Compartment {
         ((self.he_b() * pp_he) + (self.n2_b() * pp_n2)) / (pp_he + pp_n2)
     }
 
-    /// Set ZHL variant of tissue compartment
+    /// Get tolerated ambient pressure in ata for the current tissue loading,
+    /// M-value is reduced by gradient factor `gf`, 1.0 being the raw M-value
+    pub fn tolerated_ata(&self, gf: f32) -> f32 {
+        let pp_total = self.pp_n2 + self.pp_he;
+
+        if pp_total <= 0.0 {
+            return 0.0;
+        }
+
+        let a = ((self.n2_a() * self.pp_n2) + (self.he_a() * self.pp_he)) / pp_total;
+        let b = ((self.n2_b() * self.pp_n2) + (self.he_b() * self.pp_he)) / pp_total;
+
+        (pp_total - a * gf) / (gf / b + 1.0 - gf)
+    }
+
+    /// Set ZHL variant of tissue compartment, replaces coefficients
+    /// with the preset of the variant
     pub fn set_variant(&mut self, variant: ZHL16Variant) {
-        self.variant = variant;
+        self.coefficients = CoefficientTable::preset(&variant).compartments[self.cpt_num].clone();
     }
 
     /// Set partial pressure of Helium and Nitrogen of current tissue
@@ -146,121 +238,30 @@ impl ZHL16Compartment {
     // PRIVATE METHODS
     // ---
 
-    /// Get regression A of Nitrogen of current tissue
-    /// Nitrogen half time, based on Bühlmann decompression algorithm
     fn n2_a(&self) -> f32 {
-        let ht = self.n2_ht();
-        let denom = n_root(ht, 3); // denominator
-        let a = 2.0 / denom;
-
-        // return a early if self.gas_symbol is not Nitrogen
-        // only nitrogen tissue compartment type uses variants
-
-        // only use variant if compartment gas type is N2
-        let cpt_num = self.cpt_num;
-        match self.variant {
-            ZHL16Variant::A => {
-                return a;
-            }
-            ZHL16Variant::B => {
-                if cpt_num == 5 {
-                    return 0.5600;
-                }
-                if cpt_num == 6 {
-                    return 0.4947;
-                }
-                if cpt_num == 7 {
-                    return 0.4500;
-                }
-                if cpt_num == 12 {
-                    return 0.2850;
-                }
-                return a;
-            }
-            ZHL16Variant::C => {
-                if cpt_num == 4 {
-                    return 0.6200;
-                }
-                if cpt_num == 5 {
-                    return 0.5043;
-                }
-                if cpt_num == 6 {
-                    return 0.4410;
-                }
-                if cpt_num == 7 {
-                    return 0.4000;
-                }
-                if cpt_num == 8 {
-                    return 0.3750;
-                }
-                if cpt_num == 9 {
-                    return 0.3500;
-                }
-                if cpt_num == 10 {
-                    return 0.3295;
-                }
-                if cpt_num == 11 {
-                    return 0.3065;
-                }
-                if cpt_num == 12 {
-                    return 0.2835;
-                }
-                if cpt_num == 13 {
-                    return 0.2610;
-                }
-                if cpt_num == 14 {
-                    return 0.2480;
-                }
-                return a;
-            }
-        }
+        self.coefficients.n2_a
     }
 
-    /// Get regression B of Nitrogen of current tissue
-    /// Nitrogen half time, based on Bühlmann decompression algorithm
     fn n2_b(&self) -> f32 {
-        let ht = self.n2_ht();
-
-        let demon: f32 = n_root(ht, 2);
-        let b = 1.005 - (1.0 / demon);
-
-        if self.cpt_num == 3 {
-            return 0.7825;
-        }
-        if self.cpt_num == 4 {
-            return 0.8126;
-        }
-
-        b
+        self.coefficients.n2_b
     }
 
-    /// Get regression A of Helium of current tissue
-    /// Helium half time, based on Bühlmann decompression algorithm
     fn he_a(&self) -> f32 {
-        let ht = self.he_ht();
-        let denom = n_root(ht, 3); // denominator
-        let a = 2.0 / denom;
-        a
+        self.coefficients.he_a
     }
 
-    /// Get regression B of Helium of current tissue
-    /// Helium half time, based on Bühlmann decompression algorithm
     fn he_b(&self) -> f32 {
-        let ht = self.he_ht();
-
-        let demon: f32 = n_root(ht, 2);
-        let b = 1.005 - (1.0 / demon);
-        b
+        self.coefficients.he_b
     }
 
     /// Get half time of Nitrogen of current tissue compartment
     fn n2_ht(&self) -> f32 {
-        ZHL16Compartment::N2_HALF_TIMES[self.cpt_num]
+        self.coefficients.n2_half_time
     }
 
     /// Get half time of Helium of current tissue compartment
     fn he_ht(&self) -> f32 {
-        ZHL16Compartment::HE_HALF_TIMES[self.cpt_num]
+        self.coefficients.he_half_time
     }
 }
 
@@ -269,6 +270,16 @@ pub enum ZHL16Variant {
     A,
     B,
     C,
+    /// Original 16 compartment model of 1983, compartments share their
+    /// a and b pairs rather than being padded to 16. ZHL16-D and ZH-L8 ADT
+    /// are not provided, no sourced coefficients are available for them
+    L12,
+}
+
+impl ZHL16Variant {
+    pub fn compartment_count(&self) -> usize {
+        ZHL16Compartment::N2_HALF_TIMES.len()
+    }
 }
 
 impl fmt::Display for ZHL16Variant {
@@ -277,18 +288,16 @@ impl fmt::Display for ZHL16Variant {
             ZHL16Variant::A => write!(f, "ZHL16-A"),
             ZHL16Variant::B => write!(f, "ZHL16-B"),
             ZHL16Variant::C => write!(f, "ZHL16-C"),
+            ZHL16Variant::L12 => write!(f, "ZHL12"),
         }
     }
 }
 
-// #[cfg(test)]
+#[cfg(test)]
 mod tests {
     #![allow(non_snake_case)]
-    use std::clone;
-
     use super::*;
     use crate::{
-        gas::{Gas, GasMix, PPN2},
         utils::round_f32,
         zhl16::utils::{build_air_tissue, build_trimix_tissue},
     };
@@ -315,6 +324,28 @@ mod tests {
         assert_eq!(round_f32(t1_n2, 3), round_f32(t2_n2, 3))
     }
 
+    #[test]
+    fn test_tissue_travel_matches_fine_steps() {
+        let (_, mut tissue1) = build_trimix_tissue(0, 0.35, 0.21).unwrap();
+        let mut tissue2 = tissue1.clone();
+
+        // descent from the surface to 40m over 2min
+        tissue1.update_pressure_linear(1.0, 5.0, 2.0);
+
+        let steps = 2 * 600;
+        let step = 2.0 / steps as f32;
+        for i in 0..steps {
+            tissue2.update_pressure(1.0 + 4.0 * (i as f32 + 0.5) / steps as f32, step)
+        }
+
+        let (t1_n2, t1_he) = tissue1.n2_he_pp();
+        let (t2_n2, t2_he) = tissue2.n2_he_pp();
+
+        assert_eq!(round_f32(t1_n2, 3), round_f32(t2_n2, 3));
+        assert_eq!(round_f32(t1_he, 3), round_f32(t2_he, 3));
+        assert_eq!(tissue1.last_depth, 40.0);
+    }
+
     #[test]
     fn test_tissue_nitrox_diffuse_rate() {
         let (mix, mut tissue1) = build_air_tissue(0);
@@ -390,11 +421,10 @@ mod tests {
             0.3223, 0.2971, 0.2737, 0.2523, 0.2327,
         ];
 
-        for i in 0..16 {
+        for (i, expected_a) in excepted.iter().enumerate() {
             let (_, tissue) = build_air_tissue(i);
-            let expected_a = excepted[i];
 
-            assert_eq!(round_f32(expected_a, 3), round_f32(tissue.get_a(), 3));
+            assert_eq!(round_f32(*expected_a, 3), round_f32(tissue.get_a(), 3));
         }
     }
 
@@ -436,6 +466,19 @@ mod tests {
         assert_eq!(round_f32(t.get_b(), 4), 0.9319);
     }
 
+    #[test]
+    fn test_tissue_nitrox_tolerated_ata() {
+        let (_, mut t) = build_air_tissue(0);
+
+        // surface saturated tissue tolerates any ambient pressure above vacuum
+        assert!(t.tolerated_ata(1.0) < 1.0);
+
+        // loaded tissue, lowering gradient factor increases tolerated pressure
+        t.update_pressure(5.0, 60.0);
+        assert!(t.tolerated_ata(1.0) > 1.0);
+        assert!(t.tolerated_ata(0.3) > t.tolerated_ata(0.85));
+    }
+
     // TODO: Test nitrox M value
 
     // ---
@@ -444,7 +487,7 @@ mod tests {
 
     #[test]
     fn test_tissue_heliox_diffuse_rate() {
-        let (mix, mut t1) = build_trimix_tissue(4, 0.78, 0.21);
+        let (mix, mut t1) = build_trimix_tissue(4, 0.79, 0.21).unwrap();
 
         let mut t2 = t1.clone();
 
@@ -473,7 +516,7 @@ mod tests {
     #[test]
     fn test_tissue_heliox_half_time() {
         for i in 0..16 {
-            let (_, tissue) = build_trimix_tissue(i, 0.78, 0.21);
+            let (_, tissue) = build_trimix_tissue(i, 0.79, 0.21).unwrap();
             let expected_ht = ZHL16Compartment::HE_HALF_TIMES[i];
 
             assert_eq!(round_f32(tissue.half_time(), 3), round_f32(expected_ht, 3));
@@ -483,26 +526,36 @@ mod tests {
     #[test]
     fn test_tissue_heliox_a() {
         for i in 0..16 {
-            let (_, tissue) = build_trimix_tissue(i, 0.78, 0.21);
-
-            let he_ht = ZHL16Compartment::HE_HALF_TIMES[i];
-            let denom = n_root(he_ht, 3); // denominator
-            let a = 2.0 / denom;
+            let (_, tissue) = build_trimix_tissue(i, 0.79, 0.21).unwrap();
 
-            assert_eq!(round_f32(tissue.get_a(), 3), round_f32(a, 3));
+            assert_eq!(round_f32(tissue.get_a(), 4), ZHL16Compartment::HE_A[i]);
         }
     }
 
     #[test]
     fn test_tissue_heliox_b() {
         for i in 0..16 {
-            let (_, tissue) = build_trimix_tissue(i, 0.78, 0.21);
+            let (_, tissue) = build_trimix_tissue(i, 0.79, 0.21).unwrap();
+
+            assert_eq!(round_f32(tissue.get_b(), 4), ZHL16Compartment::HE_B[i]);
+        }
+    }
+
+    #[test]
+    fn test_tissue_heliox_published_coefficients() {
+        // Bühlmann ZH-L16 helium half time, a and b of compartments 1, 5 and 16
+        let published = [
+            (0, 1.51, 1.7424, 0.4245),
+            (4, 10.21, 0.9220, 0.7582),
+            (15, 240.03, 0.5119, 0.9267),
+        ];
 
-            let he_ht = ZHL16Compartment::HE_HALF_TIMES[i];
-            let demon: f32 = n_root(he_ht, 2);
-            let b = 1.005 - (1.0 / demon);
+        for (i, half_time, a, b) in published {
+            let (_, tissue) = build_trimix_tissue(i, 0.79, 0.21).unwrap();
 
-            assert_eq!(round_f32(tissue.get_b(), 3), round_f32(b, 3));
+            assert_eq!(round_f32(tissue.half_time(), 2), half_time);
+            assert_eq!(round_f32(tissue.get_a(), 4), a);
+            assert_eq!(round_f32(tissue.get_b(), 4), b);
         }
     }
 
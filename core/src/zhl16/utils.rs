@@ -1,4 +1,4 @@
-use crate::gas::{Gas, GasMix, GasSymbol, PPN2, PPO2};
+use crate::gas::{GasError, GasMix};
 use crate::utils::{n_root, round_f32};
 use crate::zhl16::tissue::ZHL16Compartment;
 
@@ -11,38 +11,47 @@ pub fn generate_he_half_times() -> [f32; 16] {
 
     let mut v: [f32; 16] = [0_f32; 16];
 
-    for i in 0..16 {
-        v[i] = round_f32(ZHL16Compartment::N2_HALF_TIMES[i] / coef, 4);
+    for (ht, n2_ht) in v.iter_mut().zip(ZHL16Compartment::N2_HALF_TIMES) {
+        *ht = round_f32(n2_ht / coef, 4);
     }
 
     v
 }
 
 pub fn build_air_tissue(cpt_num: usize) -> (GasMix, ZHL16Compartment) {
-    let mix = GasMix::new_nitrox(PPO2);
+    let mix = GasMix::air();
 
     let tissue1 = ZHL16Compartment::new(cpt_num, mix.clone(), None);
 
     (mix, tissue1)
 }
 
-pub fn build_nitrox_tissue(cpt_num: usize, oxygen: f32) -> (GasMix, ZHL16Compartment) {
-    let mix = GasMix::new_nitrox(oxygen);
+pub fn build_nitrox_tissue(
+    cpt_num: usize,
+    oxygen: f32,
+) -> Result<(GasMix, ZHL16Compartment), GasError> {
+    let mix = GasMix::new_nitrox(oxygen)?;
 
     let tissue1 = ZHL16Compartment::new(cpt_num, mix.clone(), None);
 
-    (mix, tissue1)
+    Ok((mix, tissue1))
 }
 
-pub fn build_trimix_tissue(cpt_num: usize, helium: f32, oxygen: f32) -> (GasMix, ZHL16Compartment) {
-    let mix = GasMix::new_trimix(helium, oxygen);
+pub fn build_trimix_tissue(
+    cpt_num: usize,
+    helium: f32,
+    oxygen: f32,
+) -> Result<(GasMix, ZHL16Compartment), GasError> {
+    let mix = GasMix::new_trimix(helium, oxygen)?;
 
     let tissue1 = ZHL16Compartment::new(cpt_num, mix.clone(), None);
 
-    (mix, tissue1)
+    Ok((mix, tissue1))
 }
 
+#[cfg(test)]
 mod test {
+    use crate::gas::AIR_N2;
     use crate::tissue::TissueCompartment;
 
     use super::*;
@@ -51,21 +60,21 @@ mod test {
     fn test_build_he_half_times() {
         let he_ht = generate_he_half_times();
 
-        assert_eq!(ZHL16Compartment::HE_HALF_TIMES, he_ht);
+        assert_eq!(ZHL16Compartment::GRAHAM_HE_HALF_TIMES, he_ht);
     }
 
     #[test]
     fn test_build_air_tissue() {
         let (_, t): (GasMix, ZHL16Compartment) = build_air_tissue(0);
         let (n2, _) = t.n2_he_pp();
-        assert_eq!(n2, PPN2);
+        assert_eq!(n2, AIR_N2);
     }
 
     #[test]
     fn test_build_nitrox_tissue() {
-        let (_, t): (GasMix, ZHL16Compartment) = build_nitrox_tissue(0, 0.32);
+        let (_, t): (GasMix, ZHL16Compartment) = build_nitrox_tissue(0, 0.32).unwrap();
 
-        let exp = PPN2 - (0.32 - PPO2);
+        let exp = 1.0 - 0.32;
         let (n2, _) = t.n2_he_pp();
         assert_eq!(n2, exp);
     }
@@ -74,11 +83,11 @@ mod test {
     fn test_build_trimix_tissue() {
         let helium = 0.10;
         let oxygen = 0.30;
-        let (_, t): (GasMix, ZHL16Compartment) = build_trimix_tissue(0, helium, oxygen);
+        let (_, t): (GasMix, ZHL16Compartment) = build_trimix_tissue(0, helium, oxygen).unwrap();
 
-        let exp = (PPN2 - (helium + (oxygen - PPO2))) + helium;
+        let exp = 1.0 - oxygen;
         let (n2, he) = t.n2_he_pp();
 
-        assert_eq!(n2 + he, exp);
+        assert_eq!(round_f32(n2 + he, 4), round_f32(exp, 4));
     }
 }